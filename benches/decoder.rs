@@ -0,0 +1,39 @@
+//! Decoding throughput on a stream of large packets, received in socket-sized chunks.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+use oblivion::net::codec::{Decoder, Encoder};
+
+const IV: [u8; 4] = [0x46, 0x72, 0x7A, 0x21];
+const PACKET_SIZE: usize = 8 * 1024;
+const PACKET_COUNT: usize = 1024;
+const CHUNK_SIZE: usize = 16 * 1024;
+
+fn large_packet_stream(c: &mut Criterion) {
+    let payload = (0..PACKET_SIZE).map(|i| i as u8).collect::<Vec<_>>();
+    let mut enc = Encoder::server(83, IV);
+    let mut wire = Vec::with_capacity((PACKET_SIZE + 4) * PACKET_COUNT);
+    for _ in 0..PACKET_COUNT {
+        wire.extend(enc.encode(&payload));
+    }
+
+    let mut group = c.benchmark_group("decoder");
+    group.throughput(Throughput::Bytes(wire.len() as u64));
+    group.bench_function("large_packet_stream", |b| {
+        b.iter(|| {
+            let mut dec = Decoder::client(83, IV);
+            let mut decoded = 0;
+            for chunk in wire.chunks(CHUNK_SIZE) {
+                dec.append(chunk);
+                for packet in dec.packets() {
+                    decoded += packet.unwrap().len();
+                }
+            }
+            assert_eq!(decoded, PACKET_SIZE * PACKET_COUNT);
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, large_packet_stream);
+criterion_main!(benches);
@@ -0,0 +1,224 @@
+//! Derive macros for the packet traits of `oblivion::io`.
+//!
+//! Fields are written and read in declaration order, integers little-endian. Plain fields go through
+//! `Encode`/`Decode`, which covers integers, `bool`, arrays, `SystemTime` and other derived types.
+//! The `#[packet(...)]` attribute selects another encoding for a field:
+//!
+//! - `#[packet(string)]`: `String` prefixed with its `u16` length
+//! - `#[packet(ascii = N)]`: `String` padded with NULs to exactly `N` bytes
+//! - `#[packet(list = T)]`: `Vec` prefixed with its length as the integer type `T`
+//!
+//! `#[opcode(...)]` on the struct takes a `ClientOp`/`ServerOp` (or any integer). `OutPacket` writes it
+//! before the fields, `InPacket` exposes it through `Opcode` and expects it to be already consumed.
+//!
+//! ```ignore
+//! #[derive(OutPacket)]
+//! #[opcode(ClientOp::CHECK_CHAR_NAME)]
+//! pub struct CheckCharName {
+//!     #[packet(string)]
+//!     pub name: String,
+//! }
+//! ```
+
+extern crate proc_macro;
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Expr, Fields, Ident, Index, Member, Token, Type};
+
+#[proc_macro_derive(OutPacket, attributes(packet, opcode))]
+pub fn derive_out_packet(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_out_packet(&input).unwrap_or_else(|e| e.to_compile_error()).into()
+}
+
+#[proc_macro_derive(InPacket, attributes(packet, opcode))]
+pub fn derive_in_packet(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_in_packet(&input).unwrap_or_else(|e| e.to_compile_error()).into()
+}
+
+/// How a single field is put on the wire.
+enum Encoding {
+    Plain,
+    String,
+    Ascii(Expr),
+    List(Type),
+}
+
+impl Parse for Encoding {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key: Ident = input.parse()?;
+        let encoding = match key.to_string().as_str() {
+            "string" => Encoding::String,
+            "ascii" => {
+                input.parse::<Token![=]>()?;
+                Encoding::Ascii(input.parse()?)
+            }
+            "list" => {
+                input.parse::<Token![=]>()?;
+                Encoding::List(input.parse()?)
+            }
+            _ => return Err(syn::Error::new(key.span(), "expected `string`, `ascii = N` or `list = T`")),
+        };
+        if !input.is_empty() {
+            return Err(input.error("unexpected tokens after the field encoding"));
+        }
+        Ok(encoding)
+    }
+}
+
+struct Field {
+    member: Member,
+    binding: Ident,
+    encoding: Encoding,
+}
+
+fn fields(input: &DeriveInput) -> syn::Result<(Vec<Field>, &Fields)> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => return Err(syn::Error::new(input.span(), "packets can only be derived for structs")),
+    };
+
+    let mut out = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(i)),
+        };
+        let binding = format_ident!("field_{}", i);
+        let encoding = encoding(&field.attrs)?;
+        out.push(Field { member, binding, encoding });
+    }
+    Ok((out, fields))
+}
+
+fn encoding(attrs: &[Attribute]) -> syn::Result<Encoding> {
+    let mut found = None;
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("packet")) {
+        if found.is_some() {
+            return Err(syn::Error::new(attr.span(), "duplicate `packet` attribute"));
+        }
+        found = Some(attr.parse_args()?);
+    }
+    Ok(found.unwrap_or(Encoding::Plain))
+}
+
+fn opcode(input: &DeriveInput) -> syn::Result<Option<Expr>> {
+    let mut found = None;
+    for attr in input.attrs.iter().filter(|attr| attr.path.is_ident("opcode")) {
+        if found.is_some() {
+            return Err(syn::Error::new(attr.span(), "duplicate `opcode` attribute"));
+        }
+        found = Some(attr.parse_args()?);
+    }
+    Ok(found)
+}
+
+fn expand_out_packet(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let (fields, _) = fields(input)?;
+
+    let opcode = opcode(input)?.map(|op| {
+        quote! { ::oblivion::io::Encode::encode(&((#op) as u16), buf); }
+    });
+    let writes = fields.iter().map(|field| {
+        let member = &field.member;
+        match &field.encoding {
+            Encoding::Plain => quote! {
+                ::oblivion::io::Encode::encode(&self.#member, buf);
+            },
+            Encoding::String => quote! {
+                ::oblivion::io::BufWrite::put_ascii_string_with_length(buf, &self.#member);
+            },
+            Encoding::Ascii(len) => quote! {
+                ::oblivion::io::BufWrite::put_ascii_string_fixed(buf, &self.#member, #len);
+            },
+            Encoding::List(prefix) => quote! {
+                ::oblivion::io::Encode::encode(&(self.#member.len() as #prefix), buf);
+                for item in self.#member.iter() {
+                    ::oblivion::io::Encode::encode(item, buf);
+                }
+            },
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::oblivion::io::Encode for #name #ty_generics #where_clause {
+            fn encode<B: ::oblivion::io::BufWrite>(&self, buf: &mut B) {
+                #opcode
+                #(#writes)*
+            }
+        }
+
+        impl #impl_generics ::oblivion::io::OutPacket for #name #ty_generics #where_clause {
+            fn serialize(&self) -> Vec<u8> {
+                let mut p = Vec::new();
+                ::oblivion::io::Encode::encode(self, &mut p);
+                p
+            }
+        }
+    })
+}
+
+fn expand_in_packet(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let (fields, shape) = fields(input)?;
+
+    let reads = fields.iter().map(|field| {
+        let binding = &field.binding;
+        match &field.encoding {
+            Encoding::Plain => quote! {
+                let #binding = ::oblivion::io::Decode::decode(buf)?;
+            },
+            Encoding::String => quote! {
+                let #binding = ::oblivion::io::BufRead::try_get_ascii_string(buf)?;
+            },
+            Encoding::Ascii(len) => quote! {
+                let #binding = ::oblivion::io::BufRead::try_get_ascii_string_padded(buf, #len)?;
+            },
+            Encoding::List(prefix) => quote! {
+                let len = <#prefix as ::oblivion::io::Decode>::decode(buf)? as usize;
+                let #binding = (0..len)
+                    .map(|_| ::oblivion::io::Decode::decode(buf))
+                    .collect::<::oblivion::io::Result<Vec<_>>>()?;
+            },
+        }
+    });
+
+    let bindings = fields.iter().map(|field| &field.binding);
+    let members = fields.iter().map(|field| &field.member);
+    let construct = match shape {
+        Fields::Unit => quote! { #name },
+        _ => quote! { #name { #(#members: #bindings),* } },
+    };
+
+    let opcode = opcode(input)?.map(|op| {
+        quote! {
+            impl #impl_generics ::oblivion::io::Opcode for #name #ty_generics #where_clause {
+                const OPCODE: u16 = (#op) as u16;
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::oblivion::io::Decode for #name #ty_generics #where_clause {
+            fn decode<B: ::oblivion::io::BufRead>(buf: &mut B) -> ::oblivion::io::Result<Self> {
+                #(#reads)*
+                Ok(#construct)
+            }
+        }
+
+        impl #impl_generics ::oblivion::io::InPacket for #name #ty_generics #where_clause {
+            fn deserialize<B: ::oblivion::io::BufRead>(buf: &mut B) -> ::oblivion::io::Result<Self> {
+                ::oblivion::io::Decode::decode(buf)
+            }
+        }
+
+        #opcode
+    })
+}
@@ -0,0 +1,57 @@
+#[macro_use]
+extern crate log;
+
+use std::convert::TryFrom;
+use std::io::Cursor;
+use std::time::Duration;
+
+use bytes::Bytes;
+
+use oblivion::client::{Account, Client, Event};
+use oblivion::io::Result;
+use oblivion::net::{ConnectOptions, ServerOp, VersionProfile};
+
+fn main() {
+    pretty_env_logger::init();
+
+    let remote = "127.0.0.1:8484";
+
+    let options = ConnectOptions {
+        profile: VersionProfile::v83(),
+        ..Default::default()
+    };
+    let account = Account {
+        name: "admin".to_owned(),
+        password: "admin".to_owned(),
+        mac1: [00, 0xE1, 0xFF, 0xFF, 0xFF, 0xFF],
+        hdd_id: [0x00, 0xE7, 0x89, 0x1B],
+        mac2: [00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF],
+        ..Default::default()
+    };
+
+    let mut client = Client::connect(remote, &options, account).unwrap();
+    client.dispatcher_mut().set_fallback(on_unhandled);
+    debug!("Start");
+
+    loop {
+        client.poll_timeout(Duration::from_secs(1)).unwrap();
+        for event in client.events() {
+            match event {
+                Event::StateChanged { from, to } => info!("{} -> {}", from, to),
+                Event::LoginFailed(status) => error!("Login failed: {:?}", status),
+                Event::AuthFailed(error) => error!("Authentication failed: {}", error),
+                Event::CharacterSelectFailed(status) => error!("Character selection failed: {}", status),
+                Event::WorldsListed | Event::CharactersListed | Event::InventoryChanged | Event::StatsChanged => {}
+            }
+        }
+    }
+}
+
+fn on_unhandled(_client: &mut Client, opcode: u16, buf: &mut Cursor<Bytes>) -> Result<()> {
+    match ServerOp::try_from(opcode) {
+        Ok(ServerOp::PING) | Ok(ServerOp::LOGIN_STATUS) => {}
+        Ok(op) => warn!("Unhandled server message {}, len={}", op, buf.get_ref().len()),
+        Err(_) => warn!("Unknown server message OPCODE={:#06X}, len={}", opcode, buf.get_ref().len()),
+    }
+    Ok(())
+}
@@ -0,0 +1,143 @@
+//! Runs every account of a config file (`bots.toml` unless given as the first argument)
+//! concurrently, one thread and session each, and logs how each of them is doing.
+
+#[macro_use]
+extern crate log;
+
+use std::convert::TryFrom;
+use std::io::Cursor;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use bytes::Bytes;
+
+use oblivion::client::{AccountConfig, Client, ClientState, Config, Event};
+use oblivion::io::{Error, Result};
+use oblivion::net::{ConnectOptions, ServerOp};
+
+/// What a bot thread reports about its account.
+#[derive(Debug)]
+enum Status {
+    State(ClientState),
+    LoginFailed(String),
+    Stopped(Option<Error>),
+}
+
+fn main() {
+    pretty_env_logger::init();
+
+    let path = std::env::args().nth(1).unwrap_or_else(|| "bots.toml".to_owned());
+    let (config, options) = match load(&path) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            error!("Failed to load {}: {}", path, e);
+            std::process::exit(1);
+        }
+    };
+    info!("Running {} accounts against {}", config.accounts.len(), config.server);
+
+    let (tx, rx) = mpsc::channel();
+    for (index, account) in config.accounts.iter().cloned().enumerate() {
+        let tx = tx.clone();
+        let server = config.server.clone();
+        let options = options.clone();
+        std::thread::spawn(move || {
+            let result = run(&server, &options, &account, |status| tx.send((index, status)).is_ok());
+            tx.send((index, Status::Stopped(result.err()))).ok();
+        });
+    }
+    drop(tx);
+
+    // Last state reached by each account, and why it stopped.
+    let mut summary = vec![(ClientState::Handshaking, None); config.accounts.len()];
+    for (index, status) in rx {
+        let name = &config.accounts[index].name;
+        match status {
+            Status::State(state) => {
+                info!("{}: {}", name, state);
+                summary[index].0 = state;
+            }
+            Status::LoginFailed(reason) => {
+                error!("{}: login failed: {}", name, reason);
+                summary[index].1 = Some(format!("login failed: {}", reason));
+            }
+            Status::Stopped(Some(e)) => {
+                error!("{}: stopped: {}", name, e);
+                summary[index].1.get_or_insert(e.to_string());
+            }
+            Status::Stopped(None) => info!("{}: stopped", name),
+        }
+    }
+
+    for (account, (state, failure)) in config.accounts.iter().zip(summary) {
+        match failure {
+            Some(failure) => info!("{:<16} {} ({})", account.name, state, failure),
+            None => info!("{:<16} {}", account.name, state),
+        }
+    }
+}
+
+fn load(path: &str) -> Result<(Config, ConnectOptions)> {
+    let config = Config::load(path)?;
+    let options = config.connect_options()?;
+    Ok((config, options))
+}
+
+/// Drives one account until it disconnects, `report` returns false once nobody listens anymore.
+fn run<F: FnMut(Status) -> bool>(server: &str, options: &ConnectOptions, account: &AccountConfig, mut report: F) -> Result<()> {
+    let mut client = Client::connect(server, options, account.account())?;
+    client.dispatcher_mut().set_fallback(on_unhandled);
+    report(Status::State(client.state()));
+
+    while client.state() != ClientState::Disconnected {
+        client.poll_timeout(Duration::from_secs(1))?;
+        let events = client.events().collect::<Vec<_>>();
+        for event in events {
+            let status = match event {
+                Event::StateChanged { to, .. } => Status::State(to),
+                Event::LoginFailed(status) => Status::LoginFailed(format!("{:?}", status)),
+                Event::WorldsListed => {
+                    for world in client.worlds().unwrap_or_default() {
+                        debug!("{}: world {} {}, {} channels", account.name, world.id, world.name, world.channels.len());
+                    }
+                    client.select_channel(account.world, account.channel)?;
+                    continue;
+                }
+                Event::CharactersListed => {
+                    for character in client.characters() {
+                        debug!("{}: character {} level {}", account.name, character.stat.name, character.stat.level);
+                    }
+                    let picked = client.characters().iter().find(|character| match &account.character {
+                        Some(name) => character.stat.name.eq_ignore_ascii_case(name),
+                        None => true,
+                    });
+                    match picked.map(|character| character.stat.id) {
+                        Some(id) => client.select_character(id)?,
+                        None => warn!("{}: no character to play", account.name),
+                    }
+                    continue;
+                }
+                Event::AuthFailed(error) => Status::LoginFailed(error.to_string()),
+                Event::CharacterSelectFailed(status) => {
+                    warn!("{}: character selection failed with status {}", account.name, status);
+                    continue;
+                }
+                Event::InventoryChanged | Event::StatsChanged => continue,
+            };
+            if !report(status) {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn on_unhandled(client: &mut Client, opcode: u16, buf: &mut Cursor<Bytes>) -> Result<()> {
+    let name = &client.account().name;
+    match ServerOp::try_from(opcode) {
+        Ok(ServerOp::PING) | Ok(ServerOp::LOGIN_STATUS) | Ok(ServerOp::SERVERLIST) | Ok(ServerOp::CHARLIST) => {}
+        Ok(op) => debug!("{}: unhandled server message {}, len={}", name, op, buf.get_ref().len()),
+        Err(_) => debug!("{}: unknown server message OPCODE={:#06X}, len={}", name, opcode, buf.get_ref().len()),
+    }
+    Ok(())
+}
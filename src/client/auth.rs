@@ -0,0 +1,149 @@
+use crate::io::{Error, Result};
+use crate::net::client_packet::{
+    AfterLogin, CharSelect, CharSelectWithPic, PickAllChar, RegisterPic, RegisterPin, ViewAllPicRegister,
+    ViewAllWithPic,
+};
+use crate::net::server_packet::{CheckPincode, CheckSpwResult, PinOperation, UpdatePincode};
+
+use super::{Client, ClientState, Event};
+
+/// How the server wants a character selection confirmed, as sent with the character list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
+pub enum PicMode {
+    /// The account has no PIC yet, the selection registers one.
+    Register = 0,
+    Request = 1,
+    Disabled = 2,
+}
+
+/// Why the PIN or PIC of an account was not accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthError {
+    /// The server asked for a PIN but the account has none configured.
+    PinRequired,
+    WrongPin,
+    /// Registering the account's PIN failed with this status.
+    PinRegistrationFailed(u8),
+    /// The server could not check the PIN.
+    PinSystemError,
+    /// The server asked for a PIC but the account has none configured.
+    PicRequired,
+    WrongPic,
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthError::PinRequired => write!(f, "The server asks for a PIN but none is configured"),
+            AuthError::WrongPin => write!(f, "The PIN was not accepted"),
+            AuthError::PinRegistrationFailed(status) => write!(f, "Registering the PIN failed with status {}", status),
+            AuthError::PinSystemError => write!(f, "The server failed to check the PIN"),
+            AuthError::PicRequired => write!(f, "The server asks for a PIC but none is configured"),
+            AuthError::WrongPic => write!(f, "The PIC was not accepted"),
+        }
+    }
+}
+
+impl Client {
+    /// Plays the character `id` of `characters`, confirmed with the account's PIC if the server uses them.
+    ///
+    /// The server answers by sending the client to the channel, or with `Event::AuthFailed` if the PIC is wrong.
+    /// Characters listed by `view_all_characters` are picked on their own world.
+    pub fn select_character(&mut self, id: i32) -> Result<()> {
+        if self.state != ClientState::CharacterSelect {
+            return Err(self.not_now("select a character"));
+        }
+        let macs = self.account.macs();
+        let hwid = self.account.hwid();
+        let world = self.character_worlds.get(&id).map(|&world| i32::from(world));
+        match (self.pic_mode, self.account.pic.clone(), world) {
+            (PicMode::Disabled, _, None) => self.sess.send_packet(CharSelect {
+                character: id,
+                macs,
+                hwid,
+            }),
+            (PicMode::Disabled, _, Some(world)) => self.sess.send_packet(PickAllChar {
+                character: id,
+                world,
+                macs,
+                hwid,
+            }),
+            (PicMode::Request, Some(pic), None) => self.sess.send_packet(CharSelectWithPic {
+                pic,
+                character: id,
+                macs,
+                hwid,
+            }),
+            (PicMode::Request, Some(pic), Some(world)) => self.sess.send_packet(ViewAllWithPic {
+                pic,
+                character: id,
+                world,
+                macs,
+                hwid,
+            }),
+            (PicMode::Register, Some(pic), None) => self.sess.send_packet(RegisterPic {
+                mode: 1,
+                character: id,
+                macs,
+                hwid,
+                pic,
+            }),
+            (PicMode::Register, Some(pic), Some(world)) => self.sess.send_packet(ViewAllPicRegister {
+                mode: 1,
+                character: id,
+                world,
+                macs,
+                hwid,
+                pic,
+            }),
+            (_, None, _) => {
+                self.events.push_back(Event::AuthFailed(AuthError::PicRequired));
+                Err(Error::State(AuthError::PicRequired.to_string()))
+            }
+        }
+    }
+
+    /// Reports a PIN or PIC that was not accepted, a PIN failure ends the login.
+    fn auth_failed(&mut self, error: AuthError) {
+        error!("{}: {}", self.account.name, error);
+        self.events.push_back(Event::AuthFailed(error));
+        if self.state == ClientState::LoginPending {
+            self.set_state(ClientState::Disconnected);
+        }
+    }
+
+    pub(super) fn on_check_pincode(&mut self, check: CheckPincode) -> Result<()> {
+        if self.state != ClientState::LoginPending {
+            return Ok(());
+        }
+        let pin = self.account.pin.clone();
+        match (check.operation(), pin) {
+            (Some(PinOperation::Accepted), _) => self.enter_world_select()?,
+            (Some(PinOperation::Request), Some(pin)) => self.sess.send_packet(AfterLogin::CheckPin(pin))?,
+            (Some(PinOperation::Register), Some(pin)) => self.sess.send_packet(RegisterPin { pin: Some(pin) })?,
+            (Some(PinOperation::Request), None) | (Some(PinOperation::Register), None) => {
+                self.auth_failed(AuthError::PinRequired)
+            }
+            (Some(PinOperation::Invalid), _) => self.auth_failed(AuthError::WrongPin),
+            (Some(PinOperation::SystemError), _) | (None, _) => self.auth_failed(AuthError::PinSystemError),
+        }
+        Ok(())
+    }
+
+    /// The server logs the account out after a PIN registration, log in again to enter it.
+    pub(super) fn on_update_pincode(&mut self, update: UpdatePincode) -> Result<()> {
+        match update.status {
+            0 if self.state == ClientState::LoginPending => self.send_login(),
+            0 => Ok(()),
+            status => {
+                self.auth_failed(AuthError::PinRegistrationFailed(status));
+                Ok(())
+            }
+        }
+    }
+
+    pub(super) fn on_check_spw_result(&mut self, _: CheckSpwResult) -> Result<()> {
+        self.auth_failed(AuthError::WrongPic);
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use num_traits::FromPrimitive;
+
+use crate::game::CharacterEntry;
+use crate::io::Result;
+use crate::net::client_packet::{CheckCharName, CreateChar, DeleteChar};
+use crate::net::server_packet::{AddNewCharEntry, CharNameResponse, DeleteCharError, DeleteCharResponse};
+
+use super::{Client, ClientState};
+
+/// What became of `Client::create_character`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CharacterCreation {
+    Created(Box<CharacterEntry>),
+    /// The name is taken or not allowed, nothing was created.
+    NameUnavailable,
+    /// The server refused the character with this status.
+    Failed(u8),
+}
+
+/// What became of `Client::delete_character`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharacterDeletion {
+    Deleted,
+    Failed(DeleteCharError),
+}
+
+impl Client {
+    /// Asks the server whether `name` is free, waiting up to `timeout` for the answer.
+    pub fn check_char_name(&mut self, name: &str, timeout: Duration) -> Result<bool> {
+        if self.state != ClientState::CharacterSelect {
+            return Err(self.not_now("check a name"));
+        }
+        self.name_check = None;
+        self.sess.send_packet(CheckCharName { name: name.to_owned() })?;
+        let response = self.wait_for(timeout, "the name check", |client| {
+            client.name_check.take_if(|response| response.name.eq_ignore_ascii_case(name))
+        })?;
+        Ok(!response.unavailable)
+    }
+
+    /// Creates a character after checking its name, waiting up to `timeout` for each answer.
+    ///
+    /// The new character is added to `characters` once created.
+    pub fn create_character(&mut self, character: CreateChar, timeout: Duration) -> Result<CharacterCreation> {
+        if !self.check_char_name(&character.name, timeout)? {
+            return Ok(CharacterCreation::NameUnavailable);
+        }
+
+        self.created = None;
+        self.sess.send_packet(character)?;
+        let created = self.wait_for(timeout, "the new character", |client| client.created.take())?;
+        Ok(match created {
+            AddNewCharEntry::Added(entry) => CharacterCreation::Created(entry),
+            AddNewCharEntry::Failed(status) => CharacterCreation::Failed(status),
+        })
+    }
+
+    /// Deletes one of the `characters`, waiting up to `timeout` for the answer.
+    pub fn delete_character(&mut self, id: i32, pic: &str, timeout: Duration) -> Result<CharacterDeletion> {
+        if self.state != ClientState::CharacterSelect {
+            return Err(self.not_now("delete a character"));
+        }
+        self.deleted = None;
+        self.sess.send_packet(DeleteChar {
+            pic: pic.to_owned(),
+            character: id,
+        })?;
+        let response = self.wait_for(timeout, "the character deletion", |client| {
+            client.deleted.take_if(|response| response.character == id)
+        })?;
+        Ok(match response.status {
+            0 => CharacterDeletion::Deleted,
+            status => CharacterDeletion::Failed(DeleteCharError::from_u8(status).unwrap_or(DeleteCharError::Unknown)),
+        })
+    }
+
+    pub(super) fn on_char_name_response(&mut self, response: CharNameResponse) -> Result<()> {
+        self.name_check = Some(response);
+        Ok(())
+    }
+
+    pub(super) fn on_add_new_char_entry(&mut self, added: AddNewCharEntry) -> Result<()> {
+        if let AddNewCharEntry::Added(entry) = &added {
+            debug!("{}: created character {} ({})", self.account.name, entry.stat.name, entry.stat.id);
+            self.characters.push(CharacterEntry::clone(entry));
+        }
+        self.created = Some(added);
+        Ok(())
+    }
+
+    pub(super) fn on_delete_char_response(&mut self, response: DeleteCharResponse) -> Result<()> {
+        if response.status == 0 {
+            debug!("{}: deleted character {}", self.account.name, response.character);
+            self.characters.retain(|entry| entry.stat.id != response.character);
+        }
+        self.deleted = Some(response);
+        Ok(())
+    }
+}
@@ -0,0 +1,205 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::io::{Error, Result};
+use crate::net::{ConnectOptions, VersionProfile};
+
+use super::Account;
+
+/// A set of accounts to run against one server, loaded from TOML.
+///
+/// `profile` is `"v83"` for the built-in profile or the path of a profile file, relative to the
+/// config file. MACs and HDD ids are hex bytes, optionally separated by `:` or `-`.
+///
+/// ```toml
+/// server = "127.0.0.1:8484"
+/// profile = "v83"
+/// timeout = 10
+///
+/// [[accounts]]
+/// name = "admin"
+/// password = "admin"
+/// mac1 = "00:E1:FF:FF:FF:FF"
+/// hdd_id = "00E7891B"
+/// mac2 = "00:FF:FF:FF:FF:FF"
+/// world = 0
+/// channel = 1
+/// character = "Admin"
+/// pin = "1234"
+/// pic = "123456"
+/// gender = 0
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub server: String,
+    #[serde(default = "default_profile")]
+    pub profile: String,
+    /// Seconds allowed for connecting and for the handshake.
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+    pub accounts: Vec<AccountConfig>,
+    /// Where relative profile paths are looked up, the config file's directory.
+    #[serde(skip)]
+    base: PathBuf,
+}
+
+/// One account of a `Config` and where it should end up.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountConfig {
+    pub name: String,
+    pub password: String,
+    #[serde(deserialize_with = "hex_bytes")]
+    pub mac1: [u8; 6],
+    #[serde(deserialize_with = "hex_bytes")]
+    pub hdd_id: [u8; 4],
+    #[serde(deserialize_with = "hex_bytes")]
+    pub mac2: [u8; 6],
+    #[serde(default)]
+    pub world: u8,
+    #[serde(default)]
+    pub channel: u8,
+    /// Name of the character to play, the first one listed if missing.
+    pub character: Option<String>,
+    pub pin: Option<String>,
+    pub pic: Option<String>,
+    /// 0 male or 1 female, for accounts that have none yet.
+    #[serde(default)]
+    pub gender: u8,
+}
+
+fn default_profile() -> String {
+    "v83".to_owned()
+}
+
+fn default_timeout() -> u64 {
+    10
+}
+
+impl Config {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut config = Self::from_toml(&std::fs::read_to_string(path)?)?;
+        config.base = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(config)
+    }
+
+    pub fn from_toml(s: &str) -> Result<Self> {
+        let config: Config = toml::from_str(s)?;
+        if config.accounts.is_empty() {
+            return Err(Error::Config("No accounts configured".to_owned()));
+        }
+        Ok(config)
+    }
+
+    /// Loads the configured version profile.
+    pub fn version_profile(&self) -> Result<VersionProfile> {
+        match self.profile.as_str() {
+            "v83" => Ok(VersionProfile::v83()),
+            path => VersionProfile::load(self.base.join(path)),
+        }
+    }
+
+    pub fn connect_options(&self) -> Result<ConnectOptions> {
+        Ok(ConnectOptions {
+            profile: self.version_profile()?,
+            timeout: Duration::from_secs(self.timeout),
+        })
+    }
+}
+
+impl AccountConfig {
+    pub fn account(&self) -> Account {
+        Account {
+            name: self.name.clone(),
+            password: self.password.clone(),
+            mac1: self.mac1,
+            hdd_id: self.hdd_id,
+            mac2: self.mac2,
+            pin: self.pin.clone(),
+            pic: self.pic.clone(),
+            gender: self.gender,
+        }
+    }
+}
+
+fn hex_bytes<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + AsMut<[u8]>,
+{
+    use serde::de::Error as _;
+
+    let s = String::deserialize(deserializer)?;
+    let digits = s.chars().filter(|c| *c != ':' && *c != '-').collect::<String>();
+    let mut bytes = T::default();
+    let out = bytes.as_mut();
+    if digits.len() != out.len() * 2 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(D::Error::custom(format!("expected {} hex bytes, got {:?}", out.len(), s)));
+    }
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&digits[i * 2..i * 2 + 2], 16).map_err(D::Error::custom)?;
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod test_config {
+    use crate::io::Error;
+
+    use super::Config;
+
+    const SAMPLE: &str = r#"
+        server = "127.0.0.1:8484"
+
+        [[accounts]]
+        name = "admin"
+        password = "admin"
+        mac1 = "00:E1:FF:FF:FF:FF"
+        hdd_id = "00E7891B"
+        mac2 = "00-FF-FF-FF-FF-FF"
+        world = 1
+        channel = 2
+        character = "Admin"
+        pic = "123456"
+        gender = 1
+
+        [[accounts]]
+        name = "test"
+        password = "test"
+        mac1 = "001122334455"
+        hdd_id = "DEADBEEF"
+        mac2 = "001122334455"
+    "#;
+
+    #[test]
+    fn test_parse() {
+        let config = Config::from_toml(SAMPLE).unwrap();
+        assert_eq!(config.server, "127.0.0.1:8484");
+        assert_eq!(config.version_profile().unwrap().version, 83);
+        assert_eq!(config.accounts.len(), 2);
+
+        let admin = &config.accounts[0];
+        assert_eq!(admin.mac1, [0x00, 0xE1, 0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(admin.hdd_id, [0x00, 0xE7, 0x89, 0x1B]);
+        assert_eq!(admin.mac2, [0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!((admin.world, admin.channel, admin.character.as_deref()), (1, 2, Some("Admin")));
+        assert_eq!((admin.pin.as_deref(), admin.pic.as_deref()), (None, Some("123456")));
+        assert_eq!(admin.account().gender, 1);
+
+        let test = &config.accounts[1];
+        assert_eq!(test.hdd_id, [0xDE, 0xAD, 0xBE, 0xEF]);
+        assert_eq!((test.world, test.channel, test.character.as_deref()), (0, 0, None));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let short_mac = SAMPLE.replace("00:E1:FF:FF:FF:FF", "00:E1:FF");
+        assert!(matches!(Config::from_toml(&short_mac), Err(Error::Config(_))));
+        let no_accounts = "server = \"127.0.0.1:8484\"\naccounts = []";
+        assert!(matches!(Config::from_toml(no_accounts), Err(Error::Config(_))));
+    }
+}
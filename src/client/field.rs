@@ -0,0 +1,260 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::game::{CharacterData, MovePath, Point};
+use crate::io::Result;
+use crate::net::client_packet;
+use crate::net::server_packet::{
+    DropItemFromMapObject, FieldEntry, KillMonster, Monster, MoveMonster, MovePlayer, MoveSummon, ReactorDestroy,
+    ReactorHit, ReactorSpawn, RemoveDoor, RemoveItemFromMap, RemoveMist, RemoveNpc, RemovePlayerFromMap, RemoveSummon,
+    SetField, SpawnDoor, SpawnMist, SpawnMonster, SpawnMonsterControl, SpawnNpc, SpawnPlayer, SpawnSummon,
+};
+
+use super::{Client, ClientState};
+
+/// An object of the map that moves, where it was last seen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldObject<T> {
+    /// As it appeared.
+    pub spawn: T,
+    pub position: Point,
+    pub stance: u8,
+}
+
+impl<T> FieldObject<T> {
+    fn new(spawn: T, position: Point, stance: u8) -> Self {
+        Self { spawn, position, stance }
+    }
+
+    fn moved(&mut self, path: &MovePath) {
+        self.position = path.position();
+        self.stance = path.stance().unwrap_or(self.stance);
+    }
+}
+
+/// Who and what is on the map of the character, as the server told since the last `SET_FIELD`.
+///
+/// Other characters are by character id and doors by the character that cast them, everything else by object id.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FieldState {
+    pub players: BTreeMap<i32, FieldObject<SpawnPlayer>>,
+    pub monsters: BTreeMap<i32, FieldObject<Monster>>,
+    /// The monsters whose moves the character sends.
+    pub controlled: BTreeSet<i32>,
+    pub npcs: BTreeMap<i32, SpawnNpc>,
+    pub drops: BTreeMap<i32, DropItemFromMapObject>,
+    pub reactors: BTreeMap<i32, ReactorSpawn>,
+    pub doors: BTreeMap<i32, SpawnDoor>,
+    pub mists: BTreeMap<i32, SpawnMist>,
+    pub summons: BTreeMap<i32, FieldObject<SpawnSummon>>,
+}
+
+/// A packet that changes what is on the map.
+pub trait FieldUpdate {
+    fn apply(self, field: &mut FieldState);
+}
+
+impl FieldState {
+    pub fn update<U: FieldUpdate>(&mut self, update: U) {
+        update.apply(self);
+    }
+}
+
+impl FieldUpdate for SpawnPlayer {
+    fn apply(self, field: &mut FieldState) {
+        let (position, stance) = (self.position, self.stance);
+        field.players.insert(self.character, FieldObject::new(self, position, stance));
+    }
+}
+
+impl FieldUpdate for RemovePlayerFromMap {
+    fn apply(self, field: &mut FieldState) {
+        field.players.remove(&self.character);
+    }
+}
+
+impl FieldUpdate for MovePlayer {
+    fn apply(self, field: &mut FieldState) {
+        if let Some(player) = field.players.get_mut(&self.character) {
+            player.moved(&self.path);
+        }
+    }
+}
+
+impl FieldUpdate for SpawnMonster {
+    fn apply(self, field: &mut FieldState) {
+        let (position, stance) = (self.monster.position, self.monster.stance);
+        field.monsters.insert(self.object, FieldObject::new(self.monster, position, stance));
+    }
+}
+
+impl FieldUpdate for SpawnMonsterControl {
+    fn apply(self, field: &mut FieldState) {
+        match self {
+            SpawnMonsterControl::Control { object, monster, .. } => {
+                let (position, stance) = (monster.position, monster.stance);
+                field.monsters.insert(object, FieldObject::new(*monster, position, stance));
+                field.controlled.insert(object);
+            }
+            SpawnMonsterControl::Stop { object } => {
+                field.controlled.remove(&object);
+            }
+        }
+    }
+}
+
+impl FieldUpdate for KillMonster {
+    fn apply(self, field: &mut FieldState) {
+        field.monsters.remove(&self.object);
+        field.controlled.remove(&self.object);
+    }
+}
+
+impl FieldUpdate for MoveMonster {
+    fn apply(self, field: &mut FieldState) {
+        if let Some(monster) = field.monsters.get_mut(&self.object) {
+            monster.moved(&self.path);
+        }
+    }
+}
+
+impl FieldUpdate for SpawnNpc {
+    fn apply(self, field: &mut FieldState) {
+        field.npcs.insert(self.object, self);
+    }
+}
+
+impl FieldUpdate for RemoveNpc {
+    fn apply(self, field: &mut FieldState) {
+        field.npcs.remove(&self.object);
+    }
+}
+
+impl FieldUpdate for DropItemFromMapObject {
+    fn apply(self, field: &mut FieldState) {
+        // A drop that disappears as it lands is only an animation.
+        if self.mode != 3 {
+            field.drops.insert(self.object, self);
+        }
+    }
+}
+
+impl FieldUpdate for RemoveItemFromMap {
+    fn apply(self, field: &mut FieldState) {
+        field.drops.remove(&self.object);
+    }
+}
+
+impl FieldUpdate for ReactorSpawn {
+    fn apply(self, field: &mut FieldState) {
+        field.reactors.insert(self.object, self);
+    }
+}
+
+impl FieldUpdate for ReactorHit {
+    fn apply(self, field: &mut FieldState) {
+        if let Some(reactor) = field.reactors.get_mut(&self.object) {
+            reactor.state = self.state;
+        }
+    }
+}
+
+impl FieldUpdate for ReactorDestroy {
+    fn apply(self, field: &mut FieldState) {
+        field.reactors.remove(&self.object);
+    }
+}
+
+impl FieldUpdate for SpawnDoor {
+    fn apply(self, field: &mut FieldState) {
+        field.doors.insert(self.owner, self);
+    }
+}
+
+impl FieldUpdate for RemoveDoor {
+    fn apply(self, field: &mut FieldState) {
+        field.doors.remove(&self.owner);
+    }
+}
+
+impl FieldUpdate for SpawnMist {
+    fn apply(self, field: &mut FieldState) {
+        field.mists.insert(self.object, self);
+    }
+}
+
+impl FieldUpdate for RemoveMist {
+    fn apply(self, field: &mut FieldState) {
+        field.mists.remove(&self.object);
+    }
+}
+
+impl FieldUpdate for SpawnSummon {
+    fn apply(self, field: &mut FieldState) {
+        let (position, stance) = (self.position, self.stance);
+        field.summons.insert(self.object, FieldObject::new(self, position, stance));
+    }
+}
+
+impl FieldUpdate for RemoveSummon {
+    fn apply(self, field: &mut FieldState) {
+        field.summons.remove(&self.object);
+    }
+}
+
+impl FieldUpdate for MoveSummon {
+    fn apply(self, field: &mut FieldState) {
+        if let Some(summon) = field.summons.get_mut(&self.object) {
+            summon.moved(&self.path);
+        }
+    }
+}
+
+impl Client {
+    /// The played character as of the last `SET_FIELD`, kept up to date with later changes.
+    pub fn character_data(&self) -> Option<&CharacterData> {
+        self.data.as_deref()
+    }
+
+    /// Moves the character along `path`, see `MovePath::builder`.
+    pub fn move_player(&mut self, path: MovePath) -> Result<()> {
+        if self.state != ClientState::InField {
+            return Err(self.not_now("move"));
+        }
+        let field_key = self.field_key;
+        self.sess.send_packet(client_packet::MovePlayer { field_key, crc: 0, path })
+    }
+
+    /// What is on the map of the character, empty outside of one.
+    pub fn field(&self) -> &FieldState {
+        &self.field
+    }
+
+    /// The character is on a map, after a migration or a map change.
+    pub(super) fn on_set_field(&mut self, field: SetField) -> Result<()> {
+        self.field_key = field.field_key;
+        self.field = FieldState::default();
+        match field.entry {
+            FieldEntry::CharacterInfo { data, .. } => {
+                debug!("{}: {} entered map {}", self.account.name, data.stat.name, data.stat.map);
+                self.data = Some(data);
+            }
+            FieldEntry::Warp {
+                map, spawn_point, hp, ..
+            } => {
+                debug!("{}: warped to map {}", self.account.name, map);
+                if let Some(data) = &mut self.data {
+                    data.stat.map = map;
+                    data.stat.spawn_point = spawn_point;
+                    data.stat.hp = hp;
+                }
+            }
+        }
+        self.set_state(ClientState::InField);
+        Ok(())
+    }
+
+    pub(super) fn on_field_update<U: FieldUpdate>(&mut self, update: U) -> Result<()> {
+        self.field.update(update);
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use crate::game::{Inventories, InventoryType};
+use crate::io::Result;
+use crate::net::client_packet::{ItemMove, ItemSort, ItemSort2};
+use crate::net::server_packet::{InventoryGrow, InventoryOperation};
+
+use super::{Client, ClientState, Event};
+
+impl Client {
+    /// What the character holds, kept up to date with every `INVENTORY_OPERATION`.
+    pub fn inventories(&self) -> Option<&Inventories> {
+        self.data.as_ref().map(|data| &data.inventories)
+    }
+
+    /// Moves an item to another slot of its inventory, swapping it with the item there if any.
+    ///
+    /// Equips go to a negative slot to be worn and back to a positive one to be taken off.
+    pub fn move_item(&mut self, inventory: InventoryType, from: i16, to: i16) -> Result<()> {
+        self.send_item_move(inventory, from, to, -1)
+    }
+
+    /// Drops `quantity` items of the stack at `slot`, or the item itself when it doesn't stack.
+    pub fn drop_item(&mut self, inventory: InventoryType, slot: i16, quantity: i16) -> Result<()> {
+        self.send_item_move(inventory, slot, 0, quantity)
+    }
+
+    /// Merges the stacks of `inventory` and moves its items to the first slots.
+    pub fn gather_items(&mut self, inventory: InventoryType) -> Result<()> {
+        if self.state != ClientState::InField {
+            return Err(self.not_now("gather items"));
+        }
+        let tick = self.tick();
+        self.sess.send_packet(ItemSort { tick, inventory })
+    }
+
+    /// Sorts `inventory` by item id.
+    pub fn sort_items(&mut self, inventory: InventoryType) -> Result<()> {
+        if self.state != ClientState::InField {
+            return Err(self.not_now("sort items"));
+        }
+        let tick = self.tick();
+        self.sess.send_packet(ItemSort2 { tick, inventory })
+    }
+
+    fn send_item_move(&mut self, inventory: InventoryType, from: i16, to: i16, quantity: i16) -> Result<()> {
+        if self.state != ClientState::InField {
+            return Err(self.not_now("move items"));
+        }
+        let tick = self.tick();
+        self.sess.send_packet(ItemMove {
+            tick,
+            inventory,
+            from,
+            to,
+            quantity,
+        })
+    }
+
+    pub(super) fn on_inventory_operation(&mut self, operation: InventoryOperation) -> Result<()> {
+        let name = &self.account.name;
+        let inventories = match &mut self.data {
+            Some(data) => &mut data.inventories,
+            None => {
+                warn!("{}: inventory changed before entering the field", name);
+                return Ok(());
+            }
+        };
+        for change in &operation.changes {
+            if !inventories.apply(change) {
+                warn!("{}: inventory change {:?} doesn't match the items held", name, change);
+            }
+        }
+        self.events.push_back(Event::InventoryChanged);
+        Ok(())
+    }
+
+    pub(super) fn on_inventory_grow(&mut self, grow: InventoryGrow) -> Result<()> {
+        match &mut self.data {
+            Some(data) => data.inventories.get_mut(grow.inventory).slot_limit = grow.slot_limit,
+            None => warn!("{}: inventory grew before entering the field", self.account.name),
+        }
+        self.events.push_back(Event::InventoryChanged);
+        Ok(())
+    }
+}
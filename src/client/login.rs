@@ -0,0 +1,57 @@
+use crate::io::Result;
+use crate::net::client_packet::{AfterLogin, Relog};
+use crate::net::server_packet::{ConfirmEulaResult, GenderDone, LoginError, LoginStatus, RelogResponse};
+
+use super::{Client, ClientState, Event};
+
+impl Client {
+    /// Goes back to the login from the world or character selection, the client logs in again once the
+    /// server agrees.
+    pub fn relog(&mut self) -> Result<()> {
+        if !matches!(self.state, ClientState::WorldSelect | ClientState::CharacterSelect) {
+            return Err(self.not_now("relog"));
+        }
+        self.sess.send_packet(Relog)
+    }
+
+    /// Ends a login the server refused after the terms of service or the gender selection.
+    fn login_refused(&mut self, error: LoginError) {
+        warn!("{}: login failed: {:?}", self.account.name, error);
+        self.events.push_back(Event::LoginFailed(LoginStatus::Failed(error)));
+        self.set_state(ClientState::Disconnected);
+    }
+
+    pub(super) fn on_confirm_eula_result(&mut self, result: ConfirmEulaResult) -> Result<()> {
+        if result.success {
+            self.send_login()
+        } else {
+            self.login_refused(LoginError::LicenseAgreement);
+            Ok(())
+        }
+    }
+
+    /// The PIN check the login result asked for is still due, servers without PINs accept it right away.
+    pub(super) fn on_gender_done(&mut self, done: GenderDone) -> Result<()> {
+        if done.success {
+            self.sess.send_packet(AfterLogin::RequestPin)
+        } else {
+            self.login_refused(LoginError::Unknown);
+            Ok(())
+        }
+    }
+
+    pub(super) fn on_relog_response(&mut self, response: RelogResponse) -> Result<()> {
+        if !response.success {
+            warn!("{}: relog refused", self.account.name);
+            return Ok(());
+        }
+        self.worlds.clear();
+        self.worlds_listed = false;
+        self.characters.clear();
+        self.character_worlds.clear();
+        self.tos_accepted = false;
+        self.send_login()?;
+        self.set_state(ClientState::LoginPending);
+        Ok(())
+    }
+}
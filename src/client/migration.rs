@@ -0,0 +1,69 @@
+use std::io::Cursor;
+
+use bytes::Bytes;
+
+use crate::io::Result;
+use crate::net::client_packet::{ChangeChannel, EnterCashShop, LeaveCashShop};
+use crate::net::server_packet::{self, ServerIp};
+use crate::net::Migration;
+
+use super::{Client, ClientState, Event};
+
+impl Client {
+    /// Moves the character to another channel of its world, the client is `InField` again once there.
+    pub fn change_channel(&mut self, channel: u8) -> Result<()> {
+        if self.state != ClientState::InField {
+            return Err(self.not_now("change the channel"));
+        }
+        let tick = self.tick();
+        self.sess.send_packet(ChangeChannel { channel, tick })
+    }
+
+    /// Takes the character to the cash shop, the client is `CashShop` once there.
+    pub fn enter_cash_shop(&mut self) -> Result<()> {
+        if self.state != ClientState::InField {
+            return Err(self.not_now("enter the cash shop"));
+        }
+        self.sess.send_packet(EnterCashShop)
+    }
+
+    /// Takes the character back to its channel.
+    pub fn leave_cash_shop(&mut self) -> Result<()> {
+        if self.state != ClientState::CashShop {
+            return Err(self.not_now("leave the cash shop"));
+        }
+        self.sess.send_packet(LeaveCashShop)
+    }
+
+    /// Replaces the session with one to the server of `migration`, keeping everything else.
+    fn migrate(&mut self, migration: Migration) -> Result<()> {
+        self.set_state(ClientState::Migrating);
+        self.sess = migration.connect(&self.options)?;
+        self.character = Some(migration.character);
+        Ok(())
+    }
+
+    pub(super) fn on_server_ip(&mut self, server_ip: ServerIp) -> Result<()> {
+        if server_ip.status != 0 {
+            warn!("{}: character selection failed with status {}", self.account.name, server_ip.status);
+            self.events.push_back(Event::CharacterSelectFailed(server_ip.status));
+            return Ok(());
+        }
+        self.migrate(Migration::from_server_ip(&server_ip))
+    }
+
+    pub(super) fn on_change_channel(&mut self, change: server_packet::ChangeChannel) -> Result<()> {
+        match self.character {
+            Some(character) => self.migrate(Migration::from_change_channel(&change, character)),
+            None => {
+                warn!("{}: told to change the channel without a character", self.account.name);
+                Ok(())
+            }
+        }
+    }
+
+    pub(super) fn on_set_cash_shop(&mut self, _: &mut Cursor<Bytes>) -> Result<()> {
+        self.set_state(ClientState::CashShop);
+        Ok(())
+    }
+}
@@ -0,0 +1,1317 @@
+//! A game client on top of `net::Session`, following the login flow from the handshake to the field.
+
+pub use auth::{AuthError, PicMode};
+pub use characters::{CharacterCreation, CharacterDeletion};
+pub use config::{AccountConfig, Config};
+pub use field::{FieldObject, FieldState, FieldUpdate};
+pub use state::{ClientState, Event};
+
+mod auth;
+mod characters;
+mod config;
+mod field;
+mod inventory;
+mod login;
+mod migration;
+mod stat;
+mod state;
+
+use std::collections::{HashMap, VecDeque};
+use std::net::ToSocketAddrs;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use num_traits::FromPrimitive;
+
+use crate::io::{Error, Result};
+use crate::game::{CharacterData, CharacterEntry, ForcedStat};
+use crate::net::server_packet::{
+    AddNewCharEntry, CharList, CharNameResponse, DeleteCharResponse, LoginError, LoginStatus, Ping, ServerList,
+    ViewAllChar, World,
+};
+use crate::net::{client_packet, server_packet, ConnectOptions, Dispatcher, ServerOp, Session};
+
+/// Credentials and hardware identifiers a client logs in with.
+#[derive(Debug, Clone, Default)]
+pub struct Account {
+    pub name: String,
+    pub password: String,
+    pub mac1: [u8; 6],
+    pub hdd_id: [u8; 4],
+    pub mac2: [u8; 6],
+    /// Sent when the server asks for a PIN after the login, and registered if the account has none.
+    pub pin: Option<String>,
+    /// Sent with the character selection when the server uses PICs, registered likewise.
+    pub pic: Option<String>,
+    /// Picked when the server asks a new account for its gender, 0 male and 1 female.
+    pub gender: u8,
+}
+
+impl Account {
+    /// The MAC addresses reported with a character selection.
+    pub fn macs(&self) -> String {
+        hex(&self.mac1, "-")
+    }
+
+    /// The hardware id reported with a character selection, from `mac2` and `hdd_id`.
+    pub fn hwid(&self) -> String {
+        format!("{}_{}", hex(&self.mac2, ""), hex(&self.hdd_id, ""))
+    }
+}
+
+/// Gender of accounts that have not picked one yet.
+const GENDER_UNSET: u8 = 10;
+
+fn hex(bytes: &[u8], separator: &str) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(separator)
+}
+
+/// A logged-in (or logging-in) connection driven by the server's responses.
+///
+/// Received packets first go through the client's own handlers, which answer the server and move
+/// the `ClientState` forward, then through `dispatcher_mut` for user code. Packets the server should
+/// not send in the current state are rejected before either sees them.
+///
+/// ```no_run
+/// use std::time::{Duration, Instant};
+/// use oblivion::client::{Account, Client, Event};
+/// use oblivion::net::ConnectOptions;
+///
+/// let account = Account {
+///     name: "admin".to_owned(),
+///     password: "admin".to_owned(),
+///     mac1: [0; 6],
+///     hdd_id: [0; 4],
+///     mac2: [0; 6],
+///     ..Default::default()
+/// };
+/// let mut client = Client::connect("127.0.0.1:8484", &ConnectOptions::default(), account)?;
+/// loop {
+///     client.poll_timeout(Duration::from_secs(1))?;
+///     for event in client.events() {
+///         println!("{:?}", event);
+///     }
+/// }
+/// # Ok::<(), oblivion::io::Error>(())
+/// ```
+pub struct Client {
+    sess: Session,
+    /// Options of the first connection, reused for each migration.
+    options: ConnectOptions,
+    account: Account,
+    state: ClientState,
+    events: VecDeque<Event>,
+    worlds: Vec<World>,
+    /// Whether `worlds` is complete, the end of the server list was received.
+    worlds_listed: bool,
+    characters: Vec<CharacterEntry>,
+    /// World of each character listed by `view_all_characters`, empty for the characters of a single world.
+    character_worlds: HashMap<i32, u8>,
+    /// Worlds whose characters `view_all_characters` still waits for.
+    pending_worlds: i32,
+    /// How the server wants character selections confirmed, from the character list.
+    pic_mode: PicMode,
+    /// Answers awaited by the character selection requests.
+    name_check: Option<CharNameResponse>,
+    created: Option<AddNewCharEntry>,
+    deleted: Option<DeleteCharResponse>,
+    /// Whether the terms of service were accepted during this login, a second refusal ends it.
+    tos_accepted: bool,
+    /// The character played, from the `SERVER_IP` that sent it to its channel.
+    character: Option<i32>,
+    /// Its data, from the `SET_FIELD` that follows.
+    data: Option<Box<CharacterData>>,
+    forced_stats: Vec<(ForcedStat, i64)>,
+    /// Of the last `SET_FIELD`, sent back with the moves.
+    field_key: u8,
+    field: FieldState,
+    /// Start of the client's tick count, as sent with some requests.
+    started: Instant,
+    machine: Dispatcher<Client>,
+    dispatcher: Dispatcher<Client>,
+}
+
+impl std::fmt::Debug for Client {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Client")
+            .field("sess", &self.sess)
+            .field("account", &self.account.name)
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+impl Client {
+    /// Connects and sends the credentials of `account`, the client is `LoginPending` afterwards.
+    pub fn connect<A: ToSocketAddrs>(addr: A, options: &ConnectOptions, account: Account) -> Result<Self> {
+        let sess = Session::connect_server(addr, options)?;
+        let mut client = Self::new(sess, options.clone(), account);
+        client.login()?;
+        Ok(client)
+    }
+
+    fn new(sess: Session, options: ConnectOptions, account: Account) -> Self {
+        let mut machine = Dispatcher::new();
+        machine.on(Client::on_ping);
+        machine.on(Client::on_login_status);
+        machine.on(Client::on_server_list);
+        machine.on(Client::on_char_list);
+        machine.on(Client::on_view_all_char);
+        machine.on(Client::on_char_name_response);
+        machine.on(Client::on_add_new_char_entry);
+        machine.on(Client::on_delete_char_response);
+        machine.on(Client::on_check_pincode);
+        machine.on(Client::on_update_pincode);
+        machine.on(Client::on_check_spw_result);
+        machine.on(Client::on_confirm_eula_result);
+        machine.on(Client::on_gender_done);
+        machine.on(Client::on_relog_response);
+        machine.on(Client::on_server_ip);
+        machine.on(Client::on_change_channel);
+        machine.on(Client::on_set_field);
+        machine.on(Client::on_inventory_operation);
+        machine.on(Client::on_inventory_grow);
+        machine.on(Client::on_stat_changed);
+        machine.on(Client::on_forced_stat_set);
+        machine.on(Client::on_forced_stat_reset);
+        machine.on(Client::on_field_update::<server_packet::SpawnPlayer>);
+        machine.on(Client::on_field_update::<server_packet::RemovePlayerFromMap>);
+        machine.on(Client::on_field_update::<server_packet::MovePlayer>);
+        machine.on(Client::on_field_update::<server_packet::SpawnMonster>);
+        machine.on(Client::on_field_update::<server_packet::SpawnMonsterControl>);
+        machine.on(Client::on_field_update::<server_packet::KillMonster>);
+        machine.on(Client::on_field_update::<server_packet::MoveMonster>);
+        machine.on(Client::on_field_update::<server_packet::SpawnNpc>);
+        machine.on(Client::on_field_update::<server_packet::RemoveNpc>);
+        machine.on(Client::on_field_update::<server_packet::DropItemFromMapObject>);
+        machine.on(Client::on_field_update::<server_packet::RemoveItemFromMap>);
+        machine.on(Client::on_field_update::<server_packet::ReactorSpawn>);
+        machine.on(Client::on_field_update::<server_packet::ReactorHit>);
+        machine.on(Client::on_field_update::<server_packet::ReactorDestroy>);
+        machine.on(Client::on_field_update::<server_packet::SpawnDoor>);
+        machine.on(Client::on_field_update::<server_packet::RemoveDoor>);
+        machine.on(Client::on_field_update::<server_packet::SpawnMist>);
+        machine.on(Client::on_field_update::<server_packet::RemoveMist>);
+        machine.on(Client::on_field_update::<server_packet::SpawnSummon>);
+        machine.on(Client::on_field_update::<server_packet::RemoveSummon>);
+        machine.on(Client::on_field_update::<server_packet::MoveSummon>);
+        machine.insert(ServerOp::SET_CASH_SHOP as u16, Box::new(Client::on_set_cash_shop));
+
+        Self {
+            sess,
+            options,
+            account,
+            state: ClientState::Handshaking,
+            events: VecDeque::new(),
+            worlds: Vec::new(),
+            worlds_listed: false,
+            characters: Vec::new(),
+            character_worlds: HashMap::new(),
+            pending_worlds: 0,
+            pic_mode: PicMode::Disabled,
+            name_check: None,
+            created: None,
+            deleted: None,
+            tos_accepted: false,
+            character: None,
+            data: None,
+            forced_stats: Vec::new(),
+            field_key: 0,
+            field: FieldState::default(),
+            started: Instant::now(),
+            machine,
+            dispatcher: Dispatcher::new(),
+        }
+    }
+
+    pub fn state(&self) -> ClientState {
+        self.state
+    }
+
+    pub fn account(&self) -> &Account {
+        &self.account
+    }
+
+    /// The server list, `None` until it was received in full.
+    pub fn worlds(&self) -> Option<&[World]> {
+        if self.worlds_listed {
+            Some(&self.worlds)
+        } else {
+            None
+        }
+    }
+
+    /// Waits up to `timeout` for the server list to be complete, handling every packet meanwhile.
+    pub fn list_worlds(&mut self, timeout: Duration) -> Result<&[World]> {
+        self.wait_for(timeout, "the server list", |client| client.worlds_listed.then_some(()))?;
+        Ok(&self.worlds)
+    }
+
+    /// Picks a channel of a listed world, the client is `CharacterSelect` once its characters arrive.
+    pub fn select_channel(&mut self, world: u8, channel: u8) -> Result<()> {
+        if self.state != ClientState::WorldSelect {
+            return Err(self.not_now("select a channel"));
+        }
+        self.sess.send_packet(client_packet::CharlistRequest::new(world, channel))
+    }
+
+    /// Asks for the characters of every world, the client is `CharacterSelect` once they all arrived.
+    ///
+    /// Characters picked from this list are selected on their own world, whatever channel was picked before.
+    pub fn view_all_characters(&mut self) -> Result<()> {
+        if self.state != ClientState::WorldSelect && self.state != ClientState::CharacterSelect {
+            return Err(self.not_now("view all characters"));
+        }
+        self.sess.send_packet(client_packet::ViewAllChar)
+    }
+
+    /// Characters of the world picked with `select_channel`, or of every world after `view_all_characters`.
+    pub fn characters(&self) -> &[CharacterEntry] {
+        &self.characters
+    }
+
+    pub fn session_mut(&mut self) -> &mut Session {
+        &mut self.sess
+    }
+
+    /// Handlers run after the client's own ones, for every packet accepted in the current state.
+    pub fn dispatcher_mut(&mut self) -> &mut Dispatcher<Client> {
+        &mut self.dispatcher
+    }
+
+    /// Takes the events that happened since the last call.
+    pub fn events(&mut self) -> std::collections::vec_deque::Drain<'_, Event> {
+        self.events.drain(..)
+    }
+
+    /// Handles every packet received so far.
+    ///
+    /// Errors limited to a single packet are logged and skipped, fatal ones leave the client `Disconnected`.
+    pub fn poll(&mut self) -> Result<()> {
+        if self.state == ClientState::Disconnected {
+            return Ok(());
+        }
+
+        let packets = match self.sess.recv_all() {
+            Ok(packets) => packets,
+            Err(e) => return Err(self.fail(e)),
+        };
+        for packet in packets {
+            self.handle_logged(packet)?;
+        }
+        Ok(())
+    }
+
+    /// Sleeps until a packet arrives or `timeout` elapses, then handles every packet received so far.
+    pub fn poll_timeout(&mut self, timeout: Duration) -> Result<()> {
+        if self.state == ClientState::Disconnected {
+            return Ok(());
+        }
+
+        match self.sess.recv_timeout(timeout) {
+            Ok(Some(packet)) => self.handle_logged(packet)?,
+            Ok(None) => return Ok(()),
+            Err(e) => return Err(self.fail(e)),
+        }
+        self.poll()
+    }
+
+    /// Handles one received packet, starting with its opcode.
+    pub fn handle(&mut self, packet: Bytes) -> Result<()> {
+        let opcode = match packet.as_ref() {
+            [lo, hi, ..] => u16::from_le_bytes([*lo, *hi]),
+            _ => return Err(Error::PacketTooShort { offset: 0, wanted: 2 }),
+        };
+        if !self.state.accepts(opcode) {
+            return Err(Error::Protocol {
+                opcode,
+                offset: 2,
+                message: format!("Not expected while {}", self.state),
+            });
+        }
+
+        let mut machine = std::mem::take(&mut self.machine);
+        let result = machine.dispatch(self, packet.clone());
+        self.machine = machine;
+        result?;
+
+        let mut dispatcher = std::mem::take(&mut self.dispatcher);
+        let result = dispatcher.dispatch(self, packet);
+        self.dispatcher = dispatcher;
+        result
+    }
+
+    /// Handles packets until `done` returns something, for at most `timeout`.
+    ///
+    /// `what` names the awaited answer in the timeout error.
+    fn wait_for<T, F>(&mut self, timeout: Duration, what: &str, mut done: F) -> Result<T>
+    where
+        F: FnMut(&mut Self) -> Option<T>,
+    {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(value) = done(self) {
+                return Ok(value);
+            }
+            if self.state == ClientState::Disconnected {
+                return Err(Error::Io(std::io::ErrorKind::NotConnected.into()));
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::Io(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    format!("Timed out waiting for {}", what),
+                )));
+            }
+            self.poll_timeout(deadline - now)?;
+        }
+    }
+
+    fn handle_logged(&mut self, packet: Bytes) -> Result<()> {
+        match self.handle(packet) {
+            Err(e) if !e.is_fatal() => {
+                warn!("{}: {}", self.account.name, e);
+                Ok(())
+            }
+            Err(e) => Err(self.fail(e)),
+            Ok(()) => Ok(()),
+        }
+    }
+
+    fn set_state(&mut self, to: ClientState) {
+        let from = self.state;
+        if from != to {
+            debug!("{}: {} -> {}", self.account.name, from, to);
+            self.state = to;
+            self.events.push_back(Event::StateChanged { from, to });
+        }
+    }
+
+    /// Milliseconds since the client started, the tick some requests carry.
+    fn tick(&self) -> i32 {
+        self.started.elapsed().as_millis() as i32
+    }
+
+    /// Error for a request the current state does not allow, which leaves the client as it is.
+    fn not_now(&self, what: &str) -> Error {
+        Error::State(format!("Cannot {} while {}", what, self.state))
+    }
+
+    fn fail(&mut self, e: Error) -> Error {
+        if e.is_fatal() {
+            self.set_state(ClientState::Disconnected);
+        }
+        e
+    }
+
+    fn login(&mut self) -> Result<()> {
+        self.sess.send_packet(client_packet::ClientStart)?;
+        self.send_login()?;
+        self.set_state(ClientState::LoginPending);
+        Ok(())
+    }
+
+    fn send_login(&mut self) -> Result<()> {
+        let account = &self.account;
+        let login = client_packet::LoginPassword::new(
+            &account.name,
+            &account.password,
+            account.mac1,
+            account.hdd_id,
+            account.mac2,
+        );
+        self.sess.send_packet(login)
+    }
+
+    fn on_ping(&mut self, _: Ping) -> Result<()> {
+        self.sess.send_packet(client_packet::Pong)
+    }
+
+    fn on_login_status(&mut self, status: LoginStatus) -> Result<()> {
+        match status {
+            LoginStatus::Success { gender: GENDER_UNSET, .. } => {
+                let gender = self.account.gender;
+                self.sess.send_packet(client_packet::SetGender { confirmed: true, gender })?;
+            }
+            LoginStatus::Success { request_pin: 0, .. } => {
+                self.sess.send_packet(client_packet::AfterLogin::RequestPin)?;
+            }
+            LoginStatus::Success { .. } => self.enter_world_select()?,
+            LoginStatus::Failed(LoginError::LicenseAgreement) if !self.tos_accepted => {
+                self.tos_accepted = true;
+                self.sess.send_packet(client_packet::AcceptTos { accepted: true })?;
+            }
+            failed => {
+                warn!("{}: login failed: {:?}", self.account.name, failed);
+                self.events.push_back(Event::LoginFailed(failed));
+                self.set_state(ClientState::Disconnected);
+            }
+        }
+        Ok(())
+    }
+
+    fn enter_world_select(&mut self) -> Result<()> {
+        self.sess.send_packet(client_packet::ServerListRequest)?;
+        self.set_state(ClientState::WorldSelect);
+        Ok(())
+    }
+
+    fn on_server_list(&mut self, list: ServerList) -> Result<()> {
+        match list {
+            ServerList::World(world) => {
+                // A new list after a complete one answers SERVERLIST_REREQUEST and replaces it.
+                if self.worlds_listed {
+                    self.worlds.clear();
+                    self.worlds_listed = false;
+                }
+                debug!("{}: world {} {} with {} channels", self.account.name, world.id, world.name, world.channels.len());
+                self.worlds.push(world);
+            }
+            ServerList::End => {
+                self.worlds_listed = true;
+                self.events.push_back(Event::WorldsListed);
+            }
+        }
+        Ok(())
+    }
+
+    fn on_char_list(&mut self, list: CharList) -> Result<()> {
+        if list.status != 0 {
+            warn!("{}: character list failed with status {}", self.account.name, list.status);
+            return Ok(());
+        }
+        self.characters = list.characters;
+        self.character_worlds.clear();
+        self.pic_mode = PicMode::from_u8(list.pic).unwrap_or(PicMode::Disabled);
+        self.set_state(ClientState::CharacterSelect);
+        self.events.push_back(Event::CharactersListed);
+        Ok(())
+    }
+
+    fn on_view_all_char(&mut self, packet: ViewAllChar) -> Result<()> {
+        match packet {
+            ViewAllChar::Count { worlds, .. } => {
+                self.characters.clear();
+                self.character_worlds.clear();
+                self.pending_worlds = worlds;
+            }
+            ViewAllChar::Characters { world, characters, pic } => {
+                self.character_worlds.extend(characters.iter().map(|character| (character.stat.id, world)));
+                self.characters.extend(characters);
+                self.pic_mode = PicMode::from_u8(pic).unwrap_or(PicMode::Disabled);
+                self.pending_worlds -= 1;
+            }
+            ViewAllChar::Failed(code) => {
+                warn!("{}: viewing all characters failed with code {}", self.account.name, code);
+                return Ok(());
+            }
+        }
+        if self.pending_worlds <= 0 {
+            self.set_state(ClientState::CharacterSelect);
+            self.events.push_back(Event::CharactersListed);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_client {
+    use std::io::Cursor;
+    use std::net::{SocketAddr, SocketAddrV4, TcpListener};
+    use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+    use std::sync::{mpsc, Arc};
+    use std::time::{Duration, Instant};
+
+    use bytes::{BufMut, Bytes};
+
+    use crate::game::test_character::{look, stat};
+    use crate::game::test_character_data::character_data;
+    use crate::game::{CharacterEntry, InventoryChange, InventoryType, MovePath, Point, Stat};
+    use crate::io::{BufRead, BufWrite, Decode, Error};
+    use crate::net::client_packet::CreateChar;
+    use crate::net::server_packet::{
+        AddNewCharEntry, ChangeChannel as ServerChangeChannel, Channel, CharList, CharNameResponse, CheckPincode,
+        CheckSpwResult, ConfirmEulaResult, DeleteCharError, DeleteCharResponse, FieldEntry, ForeignBuffs, GenderDone,
+        GuildMark, InventoryOperation, LoginError, LoginStatus, Monster, MonsterStats, MoveMonster, PinOperation,
+        RelogResponse, ServerIp, ServerList, SetField, SpawnMonster, SpawnNpc, SpawnPlayer, StatChanged, UpdatePincode,
+        ViewAllChar, World,
+    };
+    use crate::net::test_session::proto;
+    use crate::net::{ConnectOptions, Session, VersionProfile};
+    use crate::time::FileTime;
+
+    use super::{
+        Account, AuthError, CharacterCreation, CharacterDeletion, Client, ClientState, Event, FieldState, PicMode,
+    };
+
+    fn account() -> Account {
+        Account {
+            name: "admin".to_owned(),
+            password: "admin".to_owned(),
+            mac1: [0x00, 0xE1, 0xFF, 0xFF, 0xFF, 0xFF],
+            hdd_id: [0x00, 0xE7, 0x89, 0x1B],
+            mac2: [0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF],
+            pin: None,
+            pic: None,
+            gender: 0,
+        }
+    }
+
+    fn login_status(result: i32, pin: bool, gender: u8) -> Vec<u8> {
+        let mut p = Vec::new();
+        p.put_u16_le(0x00);
+        p.put_i32_le(result);
+        if result == 0 {
+            p.put_u32_le(1);
+            p.put_slice(&[gender, 0, 0, 0]);
+            p.put_ascii_string_with_length("admin");
+            p.put_u8(0);
+            p.put_slice(&[0; 32]);
+            p.put_slice(&[if pin { 0 } else { 1 }, 0]);
+        }
+        p
+    }
+
+    fn world(id: u8) -> World {
+        World {
+            id,
+            name: format!("World{}", id),
+            flag: 0,
+            event_message: String::new(),
+            exp_rate: 100,
+            drop_rate: 100,
+            block_char_creation: false,
+            channels: (0..3)
+                .map(|ch| Channel {
+                    name: format!("World{}-{}", id, ch + 1),
+                    load: 0,
+                    world: id,
+                    id: ch,
+                    adult: false,
+                })
+                .collect(),
+            balloons: Vec::new(),
+        }
+    }
+
+    fn char_list() -> CharList {
+        CharList {
+            status: 0,
+            characters: vec![CharacterEntry {
+                stat: stat(),
+                look: look(),
+                ranking: None,
+            }],
+            pic: 2,
+            slots: 3,
+        }
+    }
+
+    /// The character the fake server lists on `world` when viewing all characters.
+    fn view_all_character(world: u8) -> CharacterEntry {
+        let mut entry = char_list().characters.remove(0);
+        entry.stat.id = 1 + 2 * i32::from(world);
+        entry
+    }
+
+    /// How the fake server answers.
+    #[derive(Default)]
+    struct Fake {
+        /// Login result, 0 on success.
+        result: i32,
+        /// `None` without PINs, empty until one is registered.
+        pin: Option<String>,
+        /// `None` without PICs, empty until one is registered.
+        pic: Option<String>,
+        /// Where the selected character is sent, from `serve_channel`.
+        channel: Option<SocketAddrV4>,
+        /// Whether the account still has to accept the terms of service.
+        tos: bool,
+        /// 10 until the account picked one.
+        gender: u8,
+        /// Status of the `SERVER_IP` answering a character selection, 0 on success.
+        select_status: u16,
+    }
+
+    impl Fake {
+        fn login_status(&self) -> Vec<u8> {
+            let result = if self.tos { 23 } else { self.result };
+            login_status(result, self.pin.is_some(), self.gender)
+        }
+    }
+
+    /// A server answering the login with `result`, reporting the opcodes the client sends back.
+    fn serve(result: i32) -> (std::net::SocketAddr, mpsc::Receiver<u16>) {
+        serve_with(Fake { result, ..Fake::default() })
+    }
+
+    fn serve_with(mut fake: Fake) -> (std::net::SocketAddr, mpsc::Receiver<u16>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let (socket, _) = listener.accept().unwrap();
+            let mut sess = Session::accept_client(socket, proto(), &VersionProfile::v83()).unwrap();
+            let deadline = Instant::now() + Duration::from_secs(5);
+            let mut sent = false;
+            while Instant::now() < deadline {
+                for packet in sess.recv_all().unwrap_or_default() {
+                    let opcode = u16::from_le_bytes([packet[0], packet[1]]);
+                    let mut buf = Cursor::new(packet.slice(2..));
+                    match opcode {
+                        0x01 if !sent => {
+                            sess.send([0x0B, 0x00]).unwrap(); // CHARLIST, not expected yet
+                            sess.send(fake.login_status()).unwrap();
+                            sess.send([0x11, 0x00]).unwrap();
+                            sent = true;
+                        }
+                        0x01 => sess.send(fake.login_status()).unwrap(),
+                        0x07 => {
+                            fake.tos = false;
+                            sess.send_packet(ConfirmEulaResult { success: true }).unwrap();
+                        }
+                        0x08 => {
+                            buf.try_get_u8().unwrap();
+                            fake.gender = buf.try_get_u8().unwrap();
+                            sess.send_packet(GenderDone { gender: fake.gender, success: true }).unwrap();
+                        }
+                        0x09 => {
+                            let registered = fake.pin.as_deref().unwrap_or_default();
+                            let mode = match (buf.try_get_u8().unwrap(), buf.try_get_u8().unwrap()) {
+                                _ if fake.pin.is_none() => PinOperation::Accepted,
+                                (1, 1) if registered.is_empty() => PinOperation::Register,
+                                (1, 1) => PinOperation::Request,
+                                _ if buf.try_get_ascii_string().unwrap() == registered => PinOperation::Accepted,
+                                _ => PinOperation::Invalid,
+                            };
+                            sess.send_packet(CheckPincode { mode: mode as u8 }).unwrap();
+                        }
+                        0x0A => {
+                            buf.try_get_u8().unwrap();
+                            fake.pin = Some(buf.try_get_ascii_string().unwrap());
+                            sess.send_packet(UpdatePincode { status: 0 }).unwrap();
+                        }
+                        0x0B => {
+                            for id in 0..2 {
+                                sess.send_packet(ServerList::World(world(id))).unwrap();
+                            }
+                            sess.send_packet(ServerList::End).unwrap();
+                        }
+                        0x05 => {
+                            let pic = match fake.pic.as_deref() {
+                                None => PicMode::Disabled,
+                                Some("") => PicMode::Register,
+                                Some(_) => PicMode::Request,
+                            };
+                            sess.send_packet(CharList { pic: pic as u8, ..char_list() }).unwrap();
+                        }
+                        0x0D => {
+                            sess.send_packet(ViewAllChar::Count { worlds: 2, characters: 2 }).unwrap();
+                            for world in 0..2 {
+                                let pic = if fake.pic.is_some() { PicMode::Request } else { PicMode::Disabled } as u8;
+                                let characters = vec![view_all_character(world)];
+                                sess.send_packet(ViewAllChar::Characters { world, characters, pic }).unwrap();
+                            }
+                        }
+                        0x1E if Some(buf.try_get_ascii_string().unwrap()) != fake.pic => {
+                            sess.send_packet(CheckSpwResult { status: 0 }).unwrap();
+                        }
+                        0x20 => {
+                            let pic = buf.try_get_ascii_string().unwrap();
+                            let character = buf.try_get_i32_le().unwrap();
+                            let world = buf.try_get_i32_le().unwrap();
+                            match fake.channel {
+                                _ if Some(pic) != fake.pic => sess.send_packet(CheckSpwResult { status: 0 }).unwrap(),
+                                Some(addr) if character == view_all_character(world as u8).stat.id => {
+                                    sess.send_packet(server_ip(addr, character)).unwrap()
+                                }
+                                _ => {}
+                            }
+                        }
+                        0x13 | 0x1E => {
+                            if let Some(addr) = fake.channel {
+                                let status = fake.select_status;
+                                sess.send_packet(ServerIp { status, ..server_ip(addr, 1) }).unwrap();
+                            }
+                        }
+                        0x15 => {
+                            let name = buf.try_get_ascii_string().unwrap();
+                            let unavailable = name == "Admin";
+                            sess.send_packet(CharNameResponse { name, unavailable }).unwrap();
+                        }
+                        0x16 => {
+                            let mut entry = char_list().characters.remove(0);
+                            entry.stat.id = 2;
+                            entry.stat.name = buf.try_get_ascii_string().unwrap();
+                            sess.send_packet(AddNewCharEntry::Added(Box::new(entry))).unwrap();
+                        }
+                        0x1C => sess.send_packet(RelogResponse { success: true }).unwrap(),
+                        0x17 => {
+                            let pic = buf.try_get_ascii_string().unwrap();
+                            let character = buf.try_get_i32_le().unwrap();
+                            let status = if pic == "1234" { 0 } else { 20 };
+                            sess.send_packet(DeleteCharResponse { character, status }).unwrap();
+                        }
+                        _ => {}
+                    }
+                    if tx.send(opcode).is_err() {
+                        return;
+                    }
+                }
+                std::thread::sleep(Duration::from_millis(5));
+            }
+        });
+        (addr, rx)
+    }
+
+    fn server_ip(addr: SocketAddrV4, character: i32) -> ServerIp {
+        ServerIp {
+            status: 0,
+            ip: addr.ip().octets(),
+            port: addr.port(),
+            character,
+            reserved: [0; 5],
+        }
+    }
+
+    fn set_field() -> SetField {
+        SetField {
+            channel: 0,
+            field_key: 1,
+            notifier: None,
+            entry: FieldEntry::CharacterInfo {
+                seeds: [1, 2, 3],
+                data: Box::new(character_data()),
+            },
+            server_time: FileTime::now(),
+        }
+    }
+
+    fn spawn_npc(object: i32) -> SpawnNpc {
+        SpawnNpc {
+            object,
+            npc: 9010000,
+            position: Point::new(300, -100),
+            facing: 1,
+            foothold: 3,
+            rx0: 250,
+            rx1: 350,
+            visible: true,
+        }
+    }
+
+    fn spawn_player() -> SpawnPlayer {
+        SpawnPlayer {
+            character: 7,
+            level: 10,
+            name: "bot".to_owned(),
+            guild: String::new(),
+            guild_mark: GuildMark::default(),
+            buffs: ForeignBuffs::default(),
+            job: 100,
+            look: look(),
+            choco_count: 0,
+            item_effect: 0,
+            chair: 0,
+            position: Point::new(200, 120),
+            stance: 4,
+            foothold: 12,
+            remote: vec![0, 0],
+        }
+    }
+
+    fn spawn_monster() -> SpawnMonster {
+        let monster = Monster {
+            controller_status: 5,
+            id: 100100,
+            stats: MonsterStats::default(),
+            position: Point::new(-50, 120),
+            stance: 5,
+            origin_foothold: 12,
+            foothold: 12,
+            appear: -2,
+            appear_option: None,
+            team: 0xFF,
+            item_effect: 0,
+        };
+        SpawnMonster { object: 1000, monster }
+    }
+
+    /// A channel server taking any number of migrations, reporting the opcodes sent to it.
+    ///
+    /// It sends clients back to itself when they change the channel, to its cash shop if they asked for it. Each map
+    /// it sends has a monster that follows the character and an NPC, a new one every time.
+    fn serve_channel() -> (SocketAddrV4, mpsc::Receiver<u16>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = match listener.local_addr().unwrap() {
+            SocketAddr::V4(addr) => addr,
+            SocketAddr::V6(_) => unreachable!(),
+        };
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let cash_shop = Arc::new(AtomicBool::new(false));
+            let npcs = Arc::new(AtomicI32::new(0));
+            for socket in listener.incoming() {
+                // The client only closes the old connection once the new one is up.
+                let (socket, tx, cash_shop, npcs) = (socket.unwrap(), tx.clone(), cash_shop.clone(), npcs.clone());
+                std::thread::spawn(move || {
+                    let mut sess = Session::accept_client(socket, proto(), &VersionProfile::v83()).unwrap();
+                    while let Ok(packets) = sess.recv_all() {
+                        for packet in packets {
+                            let opcode = u16::from_le_bytes([packet[0], packet[1]]);
+                            match opcode {
+                                0x14 if cash_shop.load(Ordering::SeqCst) => sess.send([0x7F, 0x00]).unwrap(),
+                                0x14 => {
+                                    sess.send_packet(set_field()).unwrap();
+                                    sess.send_packet(spawn_npc(npcs.fetch_add(1, Ordering::SeqCst) + 1)).unwrap();
+                                    sess.send_packet(spawn_player()).unwrap();
+                                    sess.send_packet(spawn_monster()).unwrap();
+                                }
+                                0x29 => {
+                                    // After the field key and the CRC.
+                                    let path = MovePath::decode(&mut Cursor::new(packet.slice(7..))).unwrap();
+                                    let monster = MoveMonster {
+                                        object: 1000,
+                                        reserved: 0,
+                                        skill_possible: false,
+                                        action: 0,
+                                        skill: 0,
+                                        skill_level: 0,
+                                        option: 0,
+                                        path,
+                                    };
+                                    sess.send_packet(monster).unwrap();
+                                }
+                                0x26..=0x28 => {
+                                    cash_shop.store(opcode == 0x28, Ordering::SeqCst);
+                                    let change = ServerChangeChannel {
+                                        mode: 1,
+                                        ip: addr.ip().octets(),
+                                        port: addr.port(),
+                                    };
+                                    sess.send_packet(change).unwrap();
+                                }
+                                0x57 => {
+                                    let stats = vec![(Stat::Str, 13), (Stat::Ap, 0), (Stat::Meso, 42)];
+                                    sess.send_packet(StatChanged { exclusive: true, stats }).unwrap();
+                                }
+                                0x47 => {
+                                    let mut buf = Cursor::new(packet.slice(6..));
+                                    let inventory = InventoryType::decode(&mut buf).unwrap();
+                                    let (from, to) = (buf.try_get_i16_le().unwrap(), buf.try_get_i16_le().unwrap());
+                                    let changes = vec![InventoryChange::Move { inventory, from, to }];
+                                    sess.send_packet(InventoryOperation { exclusive: true, changes }).unwrap();
+                                }
+                                _ => {}
+                            }
+                            if tx.send(opcode).is_err() {
+                                return;
+                            }
+                        }
+                        std::thread::sleep(Duration::from_millis(5));
+                    }
+                });
+            }
+        });
+        (addr, rx)
+    }
+
+    fn poll_until(client: &mut Client, state: ClientState) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while client.state() != state {
+            assert!(Instant::now() < deadline, "stuck in {}", client.state());
+            client.poll_timeout(Duration::from_millis(100)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_login_success() {
+        let (addr, sent) = serve(0);
+        let mut client = Client::connect(addr, &ConnectOptions::default(), account()).unwrap();
+        assert_eq!(client.state(), ClientState::LoginPending);
+
+        poll_until(&mut client, ClientState::WorldSelect);
+        let events = client.events().collect::<Vec<_>>();
+        assert!(matches!(
+            events.as_slice(),
+            [
+                Event::StateChanged { from: ClientState::Handshaking, to: ClientState::LoginPending },
+                Event::StateChanged { from: ClientState::LoginPending, to: ClientState::WorldSelect },
+            ]
+        ));
+
+        let opcodes = sent.iter().take(4).collect::<Vec<_>>();
+        assert_eq!(opcodes, [0x23, 0x01, 0x0B, 0x18]); // CLIENT_START, LOGIN_PASSWORD, SERVERLIST_REQUEST, PONG
+    }
+
+    #[test]
+    fn test_list_worlds() {
+        let (addr, _sent) = serve(0);
+        let mut client = Client::connect(addr, &ConnectOptions::default(), account()).unwrap();
+        assert!(client.worlds().is_none());
+
+        let worlds = client.list_worlds(Duration::from_secs(5)).unwrap();
+        assert_eq!(worlds, [world(0), world(1)]);
+        assert_eq!(worlds[1].channels[2].name, "World1-3");
+        assert!(client.events().any(|event| matches!(event, Event::WorldsListed)));
+        assert_eq!(client.worlds().map(<[World]>::len), Some(2));
+    }
+
+    #[test]
+    fn test_select_channel() {
+        let (addr, _sent) = serve(0);
+        let mut client = Client::connect(addr, &ConnectOptions::default(), account()).unwrap();
+        assert!(matches!(client.select_channel(0, 0), Err(Error::State(_))));
+
+        client.list_worlds(Duration::from_secs(5)).unwrap();
+        client.select_channel(1, 2).unwrap();
+        poll_until(&mut client, ClientState::CharacterSelect);
+        assert_eq!(client.characters(), char_list().characters.as_slice());
+        assert!(client.events().any(|event| matches!(event, Event::CharactersListed)));
+    }
+
+    fn new_character(name: &str) -> CreateChar {
+        CreateChar {
+            name: name.to_owned(),
+            job: 1,
+            face: 20000,
+            hair: 30030,
+            hair_color: 0,
+            skin: 0,
+            top: 1040002,
+            bottom: 1060002,
+            shoes: 1072001,
+            weapon: 1302000,
+            gender: 0,
+        }
+    }
+
+    #[test]
+    fn test_create_and_delete_character() {
+        let timeout = Duration::from_secs(5);
+        let (addr, _sent) = serve(0);
+        let mut client = Client::connect(addr, &ConnectOptions::default(), account()).unwrap();
+        client.list_worlds(timeout).unwrap();
+        client.select_channel(0, 0).unwrap();
+        poll_until(&mut client, ClientState::CharacterSelect);
+
+        assert!(!client.check_char_name("Admin", timeout).unwrap());
+        let taken = client.create_character(new_character("Admin"), timeout).unwrap();
+        assert_eq!(taken, CharacterCreation::NameUnavailable);
+
+        match client.create_character(new_character("Bob"), timeout).unwrap() {
+            CharacterCreation::Created(entry) => assert_eq!((entry.stat.id, entry.stat.name.as_str()), (2, "Bob")),
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(client.characters().len(), 2);
+
+        let wrong_pic = client.delete_character(2, "0000", timeout).unwrap();
+        assert_eq!(wrong_pic, CharacterDeletion::Failed(DeleteCharError::IncorrectPic));
+        assert_eq!(client.delete_character(2, "1234", timeout).unwrap(), CharacterDeletion::Deleted);
+        assert_eq!(client.characters().len(), 1);
+    }
+
+    #[test]
+    fn test_new_account() {
+        let (addr, sent) = serve_with(Fake {
+            tos: true,
+            gender: 10,
+            ..Fake::default()
+        });
+        let account = Account { gender: 1, ..account() };
+        let mut client = Client::connect(addr, &ConnectOptions::default(), account).unwrap();
+        poll_until(&mut client, ClientState::WorldSelect);
+
+        let opcodes = sent.iter().take_while(|op| *op != 0x0B).filter(|op| *op != 0x18).collect::<Vec<_>>();
+        // CLIENT_START, LOGIN_PASSWORD, ACCEPT_TOS, LOGIN_PASSWORD, SET_GENDER, AFTER_LOGIN
+        assert_eq!(opcodes, [0x23, 0x01, 0x07, 0x01, 0x08, 0x09]);
+    }
+
+    #[test]
+    fn test_relog() {
+        let timeout = Duration::from_secs(5);
+        let (addr, _sent) = serve(0);
+        let mut client = Client::connect(addr, &ConnectOptions::default(), account()).unwrap();
+        assert!(matches!(client.relog(), Err(Error::State(_))));
+        client.list_worlds(timeout).unwrap();
+        client.select_channel(0, 0).unwrap();
+        poll_until(&mut client, ClientState::CharacterSelect);
+        client.events().for_each(drop);
+
+        client.relog().unwrap();
+        poll_until(&mut client, ClientState::WorldSelect);
+        assert!(client.events().any(|event| matches!(event, Event::StateChanged { to: ClientState::LoginPending, .. })));
+        assert!(client.characters().is_empty());
+        assert_eq!(client.list_worlds(timeout).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_pin_registration() {
+        let (addr, sent) = serve_with(Fake {
+            pin: Some(String::new()),
+            ..Fake::default()
+        });
+        let account = Account {
+            pin: Some("1234".to_owned()),
+            ..account()
+        };
+        let mut client = Client::connect(addr, &ConnectOptions::default(), account).unwrap();
+        poll_until(&mut client, ClientState::WorldSelect);
+
+        let opcodes = sent.iter().take_while(|op| *op != 0x0B).filter(|op| *op != 0x18).collect::<Vec<_>>();
+        // CLIENT_START, LOGIN_PASSWORD, AFTER_LOGIN, REGISTER_PIN, LOGIN_PASSWORD, AFTER_LOGIN twice
+        assert_eq!(opcodes, [0x23, 0x01, 0x09, 0x0A, 0x01, 0x09, 0x09]);
+    }
+
+    #[test]
+    fn test_wrong_or_missing_pin() {
+        for (pin, error) in [(Some("0000"), AuthError::WrongPin), (None, AuthError::PinRequired)] {
+            let (addr, _sent) = serve_with(Fake {
+                pin: Some("1234".to_owned()),
+                ..Fake::default()
+            });
+            let account = Account {
+                pin: pin.map(str::to_owned),
+                ..account()
+            };
+            let mut client = Client::connect(addr, &ConnectOptions::default(), account).unwrap();
+            poll_until(&mut client, ClientState::Disconnected);
+            assert!(client.events().any(|event| matches!(event, Event::AuthFailed(e) if e == error)));
+        }
+    }
+
+    #[test]
+    fn test_pic() {
+        let timeout = Duration::from_secs(5);
+        let (addr, sent) = serve_with(Fake {
+            pic: Some("123456".to_owned()),
+            ..Fake::default()
+        });
+        let account = Account {
+            pic: Some("654321".to_owned()),
+            ..account()
+        };
+        let mut client = Client::connect(addr, &ConnectOptions::default(), account).unwrap();
+        client.list_worlds(timeout).unwrap();
+        client.select_channel(0, 0).unwrap();
+        poll_until(&mut client, ClientState::CharacterSelect);
+
+        client.select_character(1).unwrap();
+        let deadline = Instant::now() + timeout;
+        while !client.events().any(|event| matches!(event, Event::AuthFailed(AuthError::WrongPic))) {
+            assert!(Instant::now() < deadline);
+            client.poll_timeout(Duration::from_millis(100)).unwrap();
+        }
+        assert_eq!(client.state(), ClientState::CharacterSelect);
+        assert!(sent.iter().any(|op| op == 0x1E)); // CHAR_SELECT_WITH_PIC
+
+        client.account.pic = None;
+        assert!(matches!(client.select_character(1), Err(Error::State(_))));
+    }
+
+    #[test]
+    fn test_view_all_with_pic() {
+        let (channel, channel_sent) = serve_channel();
+        let (addr, sent) = serve_with(Fake {
+            pic: Some("123456".to_owned()),
+            channel: Some(channel),
+            ..Fake::default()
+        });
+        let account = Account {
+            pic: Some("123456".to_owned()),
+            ..account()
+        };
+        let mut client = Client::connect(addr, &ConnectOptions::default(), account).unwrap();
+        poll_until(&mut client, ClientState::WorldSelect);
+        client.view_all_characters().unwrap();
+        poll_until(&mut client, ClientState::CharacterSelect);
+        assert_eq!(client.characters(), [view_all_character(0), view_all_character(1)]);
+        assert_eq!(client.events().filter(|event| matches!(event, Event::CharactersListed)).count(), 1);
+
+        client.select_character(3).unwrap();
+        poll_until(&mut client, ClientState::InField);
+        assert_eq!(client.character, Some(3));
+        assert!(sent.iter().any(|op| op == 0x20)); // VIEW_ALL_WITH_PIC
+        assert_eq!(channel_sent.recv().unwrap(), 0x14);
+    }
+
+    #[test]
+    fn test_migration() {
+        let timeout = Duration::from_secs(5);
+        let (channel, channel_sent) = serve_channel();
+        let (addr, _sent) = serve_with(Fake {
+            channel: Some(channel),
+            ..Fake::default()
+        });
+        let mut client = Client::connect(addr, &ConnectOptions::default(), account()).unwrap();
+        client.list_worlds(timeout).unwrap();
+        client.select_channel(0, 0).unwrap();
+        poll_until(&mut client, ClientState::CharacterSelect);
+        assert!(matches!(client.change_channel(1), Err(Error::State(_))));
+
+        client.select_character(1).unwrap();
+        poll_until(&mut client, ClientState::InField);
+        assert!(client.events().any(|event| matches!(event, Event::StateChanged { to: ClientState::Migrating, .. })));
+        assert_eq!(client.character, Some(1));
+        assert_eq!(client.character_data(), Some(&character_data()));
+
+        client.enter_cash_shop().unwrap();
+        poll_until(&mut client, ClientState::CashShop);
+        client.leave_cash_shop().unwrap();
+        poll_until(&mut client, ClientState::InField);
+        client.events().for_each(drop);
+        client.change_channel(1).unwrap();
+        let deadline = Instant::now() + timeout;
+        while !client.events().any(|event| matches!(event, Event::StateChanged { to: ClientState::InField, .. })) {
+            assert!(Instant::now() < deadline);
+            client.poll_timeout(Duration::from_millis(100)).unwrap();
+        }
+        assert_eq!(client.account().name, "admin");
+
+        // PLAYER_LOGGEDIN on each connection, then ENTER_CASHSHOP, CHANGE_MAP and CHANGE_CHANNEL
+        let opcodes = channel_sent.iter().take(7).collect::<Vec<_>>();
+        assert_eq!(opcodes, [0x14, 0x28, 0x14, 0x26, 0x14, 0x27, 0x14]);
+    }
+
+    #[test]
+    fn test_character_select_failed() {
+        let (channel, channel_sent) = serve_channel();
+        let (addr, _sent) = serve_with(Fake {
+            channel: Some(channel),
+            select_status: 7,
+            ..Fake::default()
+        });
+        let mut client = Client::connect(addr, &ConnectOptions::default(), account()).unwrap();
+        client.list_worlds(Duration::from_secs(5)).unwrap();
+        client.select_channel(0, 0).unwrap();
+        poll_until(&mut client, ClientState::CharacterSelect);
+
+        client.select_character(1).unwrap();
+        poll_until_event(&mut client, |event| matches!(event, Event::CharacterSelectFailed(7)));
+        assert_eq!(client.state(), ClientState::CharacterSelect);
+        assert!(channel_sent.try_recv().is_err());
+    }
+
+    /// A client that selected character 1 and entered the field, with the opcodes its channel received.
+    fn in_field() -> (Client, mpsc::Receiver<u16>) {
+        let (channel, channel_sent) = serve_channel();
+        let (addr, _sent) = serve_with(Fake {
+            channel: Some(channel),
+            ..Fake::default()
+        });
+        let mut client = Client::connect(addr, &ConnectOptions::default(), account()).unwrap();
+        client.list_worlds(Duration::from_secs(5)).unwrap();
+        client.select_channel(0, 0).unwrap();
+        poll_until(&mut client, ClientState::CharacterSelect);
+        client.select_character(1).unwrap();
+        poll_until(&mut client, ClientState::InField);
+        client.events().for_each(drop);
+        (client, channel_sent)
+    }
+
+    fn poll_until_event<F: Fn(&Event) -> bool>(client: &mut Client, expected: F) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !client.events().any(|event| expected(&event)) {
+            assert!(Instant::now() < deadline, "no such event in {}", client.state());
+            client.poll_timeout(Duration::from_millis(100)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_inventory() {
+        let (addr, _sent) = serve(0);
+        let mut client = Client::connect(addr, &ConnectOptions::default(), account()).unwrap();
+        assert!(client.inventories().is_none());
+        assert!(matches!(client.move_item(InventoryType::Equip, 1, -5), Err(Error::State(_))));
+
+        let (mut client, _channel_sent) = in_field();
+        assert_eq!(client.inventories().unwrap().equip.items[&1].id, 1040002);
+        client.move_item(InventoryType::Equip, 1, -5).unwrap();
+        poll_until_event(&mut client, |event| matches!(event, Event::InventoryChanged));
+        let inventories = client.inventories().unwrap();
+        assert!(inventories.equip.items.is_empty());
+        assert_eq!(inventories.equipped[&-5].id, 1040002);
+    }
+
+    #[test]
+    fn test_stats() {
+        let (mut client, _channel_sent) = in_field();
+        client.distribute_ap(Stat::Str).unwrap();
+        poll_until_event(&mut client, |event| matches!(event, Event::StatsChanged));
+        let data = client.character_data().unwrap();
+        assert_eq!((data.stat.str, data.stat.ap, data.meso), (13, 0, 42));
+    }
+
+    fn poll_until_field<F: Fn(&FieldState) -> bool>(client: &mut Client, expected: F) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !expected(client.field()) {
+            assert!(Instant::now() < deadline, "unexpected field {:?}", client.field());
+            client.poll_timeout(Duration::from_millis(100)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_field() {
+        let (mut client, _channel_sent) = in_field();
+        poll_until_field(&mut client, |field| field.npcs.contains_key(&1) && field.monsters.contains_key(&1000));
+        assert_eq!(client.field().npcs[&1], spawn_npc(1));
+        let monster = &client.field().monsters[&1000];
+        assert_eq!((monster.spawn.id, monster.position, monster.stance), (100100, Point::new(-50, 120), 5));
+        let player = &client.field().players[&7];
+        assert_eq!((player.spawn.name.as_str(), player.position, player.stance), ("bot", Point::new(200, 120), 4));
+
+        let path = MovePath::builder(Point::new(-50, 120), 12).walk_to(0).build();
+        client.move_player(path.clone()).unwrap();
+        poll_until_field(&mut client, |field| field.monsters[&1000].position == Point::new(0, 120));
+        assert_eq!(Some(client.field().monsters[&1000].stance), path.stance());
+
+        // The new map only has what the server sends with it.
+        client.change_channel(1).unwrap();
+        poll_until_field(&mut client, |field| field.npcs.contains_key(&2));
+        assert!(!client.field().npcs.contains_key(&1));
+        assert_eq!(client.field().npcs.len(), 1);
+    }
+
+    #[test]
+    fn test_move_player() {
+        let (mut client, channel_sent) = in_field();
+        assert_eq!(client.field_key, 1);
+        client.move_player(MovePath::builder(Point::new(0, 0), 1).walk_to(100).build()).unwrap();
+        let opcodes = channel_sent.iter().take(2).collect::<Vec<_>>();
+        assert_eq!(opcodes, [0x14, 0x29]);
+    }
+
+    #[test]
+    fn test_account_ids() {
+        let account = account();
+        assert_eq!(account.macs(), "00-E1-FF-FF-FF-FF");
+        assert_eq!(account.hwid(), "00FFFFFFFFFF_00E7891B");
+    }
+
+    #[test]
+    fn test_login_failed() {
+        let (addr, _sent) = serve(4);
+        let mut client = Client::connect(addr, &ConnectOptions::default(), account()).unwrap();
+
+        poll_until(&mut client, ClientState::Disconnected);
+        let failed = |event| matches!(event, Event::LoginFailed(LoginStatus::Failed(LoginError::IncorrectPassword)));
+        assert!(client.events().any(failed));
+    }
+
+    #[test]
+    fn test_rejects_unexpected_packet() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (socket, _) = listener.accept().unwrap();
+            Session::accept_client(socket, proto(), &VersionProfile::v83()).unwrap();
+            std::thread::sleep(Duration::from_millis(200));
+        });
+        let sess = Session::connect_server(addr, &ConnectOptions::default()).unwrap();
+        let mut client = Client::new(sess, ConnectOptions::default(), account());
+
+        let err = client.handle(Bytes::from_static(&[0x0B, 0x00])).unwrap_err();
+        assert!(matches!(err, Error::Protocol { opcode: 0x0B, .. }));
+        assert_eq!(client.state(), ClientState::Handshaking);
+    }
+}
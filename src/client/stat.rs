@@ -0,0 +1,69 @@
+use crate::game::{ForcedStat, Stat};
+use crate::io::Result;
+use crate::net::client_packet::{AutoDistributeAp, DistributeAp, DistributeSp, HealOverTime};
+use crate::net::server_packet::{ForcedStatReset, ForcedStatSet, StatChanged};
+
+use super::{Client, ClientState, Event};
+
+impl Client {
+    /// Stats the server overrides until further notice, whatever `character_data` says.
+    pub fn forced_stats(&self) -> &[(ForcedStat, i64)] {
+        &self.forced_stats
+    }
+
+    /// Spends an AP on `stat`, one of the four base stats, max HP or max MP.
+    pub fn distribute_ap(&mut self, stat: Stat) -> Result<()> {
+        if self.state != ClientState::InField {
+            return Err(self.not_now("distribute AP"));
+        }
+        let tick = self.tick();
+        self.sess.send_packet(DistributeAp { tick, stat: stat.mask() })
+    }
+
+    /// Spends several AP at once, `stats` telling how many go to each stat.
+    pub fn auto_distribute_ap(&mut self, stats: &[(Stat, i32)]) -> Result<()> {
+        if self.state != ClientState::InField {
+            return Err(self.not_now("distribute AP"));
+        }
+        let tick = self.tick();
+        let stats = stats.iter().map(|(stat, amount)| (stat.mask(), *amount)).collect();
+        self.sess.send_packet(AutoDistributeAp { tick, stats })
+    }
+
+    /// Spends an SP on `skill`.
+    pub fn distribute_sp(&mut self, skill: i32) -> Result<()> {
+        if self.state != ClientState::InField {
+            return Err(self.not_now("distribute SP"));
+        }
+        let tick = self.tick();
+        self.sess.send_packet(DistributeSp { tick, skill })
+    }
+
+    /// Reports the HP and MP recovered while resting, which the server checks against the time elapsed.
+    pub fn heal_over_time(&mut self, hp: i16, mp: i16) -> Result<()> {
+        if self.state != ClientState::InField {
+            return Err(self.not_now("heal"));
+        }
+        let tick = self.tick();
+        self.sess.send_packet(HealOverTime::new(tick, hp, mp))
+    }
+
+    pub(super) fn on_stat_changed(&mut self, changed: StatChanged) -> Result<()> {
+        match &mut self.data {
+            Some(data) => data.apply_stats(&changed.stats),
+            None => warn!("{}: stats changed before entering the field", self.account.name),
+        }
+        self.events.push_back(Event::StatsChanged);
+        Ok(())
+    }
+
+    pub(super) fn on_forced_stat_set(&mut self, forced: ForcedStatSet) -> Result<()> {
+        self.forced_stats = forced.stats;
+        Ok(())
+    }
+
+    pub(super) fn on_forced_stat_reset(&mut self, _: ForcedStatReset) -> Result<()> {
+        self.forced_stats.clear();
+        Ok(())
+    }
+}
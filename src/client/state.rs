@@ -0,0 +1,107 @@
+use std::convert::TryFrom;
+
+use crate::net::server_packet::LoginStatus;
+use crate::net::ServerOp;
+
+use super::AuthError;
+
+/// Where a client stands between connecting and playing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClientState {
+    /// Waiting for the server hello.
+    Handshaking,
+    /// Credentials sent, waiting for the login result.
+    LoginPending,
+    /// Logged in, receiving the world list and picking a channel.
+    WorldSelect,
+    /// Channel picked, receiving the character list and picking a character.
+    CharacterSelect,
+    /// Told to reconnect to another server, a channel or the cash shop.
+    Migrating,
+    /// Playing on a channel.
+    InField,
+    /// In the cash shop, which the character leaves through another migration.
+    CashShop,
+    /// The connection is gone or the server refused the login.
+    Disconnected,
+}
+
+impl ClientState {
+    /// Whether the server may send `opcode` in this state, packets it may not are rejected unhandled.
+    pub fn accepts(self, opcode: u16) -> bool {
+        use ServerOp::*;
+
+        let op = match ServerOp::try_from(opcode) {
+            Ok(op) => op,
+            Err(_) => return false,
+        };
+        match self {
+            ClientState::Disconnected => false,
+            _ if op == PING => true,
+            ClientState::Handshaking => false,
+            ClientState::LoginPending => matches!(
+                op,
+                LOGIN_STATUS | GENDER_DONE | CONFIRM_EULA_RESULT | CHECK_PINCODE | UPDATE_PINCODE
+            ),
+            ClientState::WorldSelect => matches!(
+                op,
+                SERVERLIST | LAST_CONNECTED_WORLD | RECOMMENDED_WORLD_MESSAGE | SERVERSTATUS | CHECK_PINCODE
+                    | UPDATE_PINCODE | CHARLIST | VIEW_ALL_CHAR | RELOG_RESPONSE
+            ),
+            ClientState::CharacterSelect => matches!(
+                op,
+                CHARLIST | VIEW_ALL_CHAR | CHAR_NAME_RESPONSE | ADD_NEW_CHAR_ENTRY | DELETE_CHAR_RESPONSE
+                    | SERVER_IP | CHECK_SPW_RESULT | SELECT_CHARACTER_BY_VAC | RELOG_RESPONSE
+            ),
+            ClientState::Migrating => matches!(op, SET_FIELD | SET_CASH_SHOP | SET_ITC),
+            ClientState::InField | ClientState::CashShop => !matches!(
+                op,
+                LOGIN_STATUS | SERVERLIST | CHARLIST | CHAR_NAME_RESPONSE | ADD_NEW_CHAR_ENTRY | DELETE_CHAR_RESPONSE
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for ClientState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+/// What happened to a client, collected until `Client::events` drains them.
+#[derive(Debug)]
+pub enum Event {
+    StateChanged { from: ClientState, to: ClientState },
+    /// The server refused the credentials, the client is `Disconnected` afterwards.
+    LoginFailed(LoginStatus),
+    /// The server list is complete, see `Client::worlds`.
+    WorldsListed,
+    /// The characters of the picked world arrived, see `Client::characters`.
+    CharactersListed,
+    /// The PIN or PIC was refused or missing. The client is `Disconnected` if it happened during the login.
+    AuthFailed(AuthError),
+    /// The server refused to send the selected character to its channel with this status.
+    CharacterSelectFailed(u16),
+    /// Items were added, moved or used up, or an inventory grew, see `Client::inventories`.
+    InventoryChanged,
+    /// Some stats or the meso changed, see `Client::character_data`.
+    StatsChanged,
+}
+
+#[cfg(test)]
+mod test_state {
+    use crate::net::ServerOp;
+
+    use super::ClientState;
+
+    #[test]
+    fn test_accepts() {
+        assert!(ClientState::LoginPending.accepts(ServerOp::LOGIN_STATUS as u16));
+        assert!(!ClientState::LoginPending.accepts(ServerOp::CHARLIST as u16));
+        assert!(ClientState::WorldSelect.accepts(ServerOp::PING as u16));
+        assert!(ClientState::InField.accepts(ServerOp::STAT_CHANGED as u16));
+        assert!(!ClientState::InField.accepts(ServerOp::LOGIN_STATUS as u16));
+        assert!(!ClientState::Disconnected.accepts(ServerOp::PING as u16));
+        assert!(!ClientState::InField.accepts(0xFFFF));
+    }
+}
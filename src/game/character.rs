@@ -0,0 +1,303 @@
+use std::collections::BTreeMap;
+
+use crate::io::{BufRead, BufWrite, Decode, Encode, InPacket, OutPacket, Result};
+
+/// Equip slot closing the equip lists of an `AvatarLook`.
+const EQUIPS_END: u8 = 0xFF;
+
+/// The stats shown on the character selection and loaded with the field (`GW_CharacterStat`).
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct CharacterStat {
+    pub id: i32,
+    #[packet(ascii = 13)]
+    pub name: String,
+    pub gender: u8,
+    pub skin: u8,
+    pub face: i32,
+    pub hair: i32,
+    /// Cash serial numbers of the summoned pets, zero for empty slots.
+    pub pets: [u64; 3],
+    pub level: u8,
+    pub job: i16,
+    pub str: i16,
+    pub dex: i16,
+    pub int: i16,
+    pub luk: i16,
+    pub hp: i16,
+    pub max_hp: i16,
+    pub mp: i16,
+    pub max_mp: i16,
+    pub ap: i16,
+    pub sp: i16,
+    pub exp: i32,
+    pub fame: i16,
+    pub gacha_exp: i32,
+    pub map: i32,
+    pub spawn_point: u8,
+    pub play_time: i32,
+}
+
+/// How a character is drawn: its face, hair and visible equips (`AvatarLook`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AvatarLook {
+    pub gender: u8,
+    pub skin: u8,
+    pub face: i32,
+    /// 0 on megaphone avatars, 1 elsewhere.
+    pub mega: u8,
+    pub hair: i32,
+    /// Item id worn in each equip slot, shown unless covered by a cash item.
+    pub equips: BTreeMap<u8, i32>,
+    /// Items hidden under a cash item of the same slot.
+    pub masked_equips: BTreeMap<u8, i32>,
+    pub cash_weapon: i32,
+    /// Item ids of the summoned pets, zero for empty slots.
+    pub pets: [i32; 3],
+}
+
+fn encode_equips<B: BufWrite>(equips: &BTreeMap<u8, i32>, buf: &mut B) {
+    for (slot, item) in equips {
+        buf.put_u8(*slot);
+        buf.put_i32_le(*item);
+    }
+    buf.put_u8(EQUIPS_END);
+}
+
+fn decode_equips<B: BufRead>(buf: &mut B) -> Result<BTreeMap<u8, i32>> {
+    let mut equips = BTreeMap::new();
+    loop {
+        match buf.try_get_u8()? {
+            EQUIPS_END => return Ok(equips),
+            slot => {
+                equips.insert(slot, buf.try_get_i32_le()?);
+            }
+        }
+    }
+}
+
+impl Encode for AvatarLook {
+    fn encode<B: BufWrite>(&self, buf: &mut B) {
+        buf.put_u8(self.gender);
+        buf.put_u8(self.skin);
+        buf.put_i32_le(self.face);
+        buf.put_u8(self.mega);
+        buf.put_i32_le(self.hair);
+        encode_equips(&self.equips, buf);
+        encode_equips(&self.masked_equips, buf);
+        buf.put_i32_le(self.cash_weapon);
+        self.pets.encode(buf);
+    }
+}
+
+impl Decode for AvatarLook {
+    fn decode<B: BufRead>(buf: &mut B) -> Result<Self> {
+        Ok(Self {
+            gender: buf.try_get_u8()?,
+            skin: buf.try_get_u8()?,
+            face: buf.try_get_i32_le()?,
+            mega: buf.try_get_u8()?,
+            hair: buf.try_get_i32_le()?,
+            equips: decode_equips(buf)?,
+            masked_equips: decode_equips(buf)?,
+            cash_weapon: buf.try_get_i32_le()?,
+            pets: Decode::decode(buf)?,
+        })
+    }
+}
+
+impl InPacket for AvatarLook {
+    fn deserialize<B: BufRead>(buf: &mut B) -> Result<Self> {
+        Self::decode(buf)
+    }
+}
+
+impl OutPacket for AvatarLook {
+    fn serialize(&self) -> Vec<u8> {
+        let mut p = Vec::new();
+        self.encode(&mut p);
+        p
+    }
+}
+
+/// World and job ranking of a character, hidden for GMs.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct Ranking {
+    pub rank: i32,
+    pub rank_move: i32,
+    pub job_rank: i32,
+    pub job_rank_move: i32,
+}
+
+/// A character of the character selection.
+///
+/// `CHARLIST` sends a byte between the look and the ranking that `VIEW_ALL_CHAR` leaves out, the
+/// `Encode`/`Decode` impls are the `CHARLIST` form and `encode_view_all`/`decode_view_all` the other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CharacterEntry {
+    pub stat: CharacterStat,
+    pub look: AvatarLook,
+    pub ranking: Option<Ranking>,
+}
+
+impl CharacterEntry {
+    pub fn encode_view_all<B: BufWrite>(&self, buf: &mut B) {
+        self.encode_with(buf, false)
+    }
+
+    pub fn decode_view_all<B: BufRead>(buf: &mut B) -> Result<Self> {
+        Self::decode_with(buf, false)
+    }
+
+    fn encode_with<B: BufWrite>(&self, buf: &mut B, char_list: bool) {
+        self.stat.encode(buf);
+        self.look.encode(buf);
+        if char_list {
+            buf.put_u8(0);
+        }
+        match &self.ranking {
+            Some(ranking) => {
+                buf.put_u8(1);
+                ranking.encode(buf);
+            }
+            None => buf.put_u8(0),
+        }
+    }
+
+    fn decode_with<B: BufRead>(buf: &mut B, char_list: bool) -> Result<Self> {
+        let stat = CharacterStat::decode(buf)?;
+        let look = AvatarLook::decode(buf)?;
+        if char_list {
+            buf.try_get_u8()?;
+        }
+        let ranking = if buf.try_get_bool()? { Some(Ranking::decode(buf)?) } else { None };
+        Ok(Self { stat, look, ranking })
+    }
+}
+
+impl Encode for CharacterEntry {
+    fn encode<B: BufWrite>(&self, buf: &mut B) {
+        self.encode_with(buf, true)
+    }
+}
+
+impl Decode for CharacterEntry {
+    fn decode<B: BufRead>(buf: &mut B) -> Result<Self> {
+        Self::decode_with(buf, true)
+    }
+}
+
+impl InPacket for CharacterEntry {
+    fn deserialize<B: BufRead>(buf: &mut B) -> Result<Self> {
+        Self::decode(buf)
+    }
+}
+
+impl OutPacket for CharacterEntry {
+    fn serialize(&self) -> Vec<u8> {
+        let mut p = Vec::new();
+        self.encode(&mut p);
+        p
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_character {
+    use std::io::Cursor;
+
+    use crate::io::{BufRead, InPacket, OutPacket};
+
+    use super::{AvatarLook, CharacterEntry, CharacterStat, Ranking};
+
+    pub(crate) fn stat() -> CharacterStat {
+        CharacterStat {
+            id: 1,
+            name: "Admin".to_owned(),
+            gender: 0,
+            skin: 0,
+            face: 20000,
+            hair: 30000,
+            pets: [0; 3],
+            level: 10,
+            job: 100,
+            str: 35,
+            dex: 4,
+            int: 4,
+            luk: 4,
+            hp: 300,
+            max_hp: 300,
+            mp: 50,
+            max_mp: 50,
+            ap: 0,
+            sp: 3,
+            exp: 15,
+            fame: 0,
+            gacha_exp: 0,
+            map: 100000000,
+            spawn_point: 0,
+            play_time: 0,
+        }
+    }
+
+    pub(crate) fn look() -> AvatarLook {
+        AvatarLook {
+            gender: 0,
+            skin: 0,
+            face: 20000,
+            mega: 1,
+            hair: 30000,
+            equips: vec![(5, 1040002), (6, 1060002), (11, 1302000)].into_iter().collect(),
+            masked_equips: vec![(11, 1302001)].into_iter().collect(),
+            cash_weapon: 0,
+            pets: [5000000, 0, 0],
+        }
+    }
+
+    #[test]
+    fn test_stat_layout() {
+        let p = stat().serialize();
+        assert_eq!(p.len(), 4 + 13 + 2 + 8 + 24 + 1 + 2 * 11 + 4 + 2 + 4 + 4 + 1 + 4);
+        assert_eq!(&p[4..17], b"Admin\0\0\0\0\0\0\0\0");
+        assert_eq!(CharacterStat::deserialize(&mut Cursor::new(&p)).unwrap(), stat());
+    }
+
+    #[test]
+    fn test_look() {
+        let p = look().serialize();
+        let mut expected = vec![0x00, 0x00, 0x20, 0x4E, 0x00, 0x00, 0x01, 0x30, 0x75, 0x00, 0x00];
+        expected.extend_from_slice(&[0x05, 0x82, 0xDE, 0x0F, 0x00]);
+        expected.extend_from_slice(&[0x06, 0xA2, 0x2C, 0x10, 0x00]);
+        expected.extend_from_slice(&[0x0B, 0xF0, 0xDD, 0x13, 0x00, 0xFF]);
+        expected.extend_from_slice(&[0x0B, 0xF1, 0xDD, 0x13, 0x00, 0xFF]);
+        expected.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+        expected.extend_from_slice(&[0x40, 0x4B, 0x4C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(p, expected);
+        assert_eq!(AvatarLook::deserialize(&mut Cursor::new(&p)).unwrap(), look());
+    }
+
+    #[test]
+    fn test_entry_forms() {
+        let ranked = CharacterEntry {
+            stat: stat(),
+            look: look(),
+            ranking: Some(Ranking {
+                rank: 1,
+                rank_move: 2,
+                job_rank: 3,
+                job_rank_move: 4,
+            }),
+        };
+        let p = ranked.serialize();
+        let head = stat().serialize().len() + look().serialize().len();
+        assert_eq!(&p[head..head + 3], [0x00, 0x01, 0x01]);
+        assert_eq!(p.len(), head + 2 + 16);
+        assert_eq!(CharacterEntry::deserialize(&mut Cursor::new(&p)).unwrap(), ranked);
+
+        let hidden = CharacterEntry { ranking: None, ..ranked };
+        let mut p = Vec::new();
+        hidden.encode_view_all(&mut p);
+        assert_eq!(p.len(), head + 1);
+        let mut buf = Cursor::new(&p);
+        assert_eq!(CharacterEntry::decode_view_all(&mut buf).unwrap(), hidden);
+        assert_eq!(buf.remaining(), 0);
+    }
+}
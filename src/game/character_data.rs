@@ -0,0 +1,420 @@
+use crate::io::{BufRead, BufWrite, Decode, Encode, InPacket, OutPacket, Result};
+use crate::time::FileTime;
+
+use super::{CharacterStat, Inventories};
+
+/// Map id of unset teleport rock slots.
+pub const EMPTY_ROCK: i32 = 999_999_999;
+
+/// Everything the server tells about the own character when it enters a channel (`CharacterData`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CharacterData {
+    /// Sections the packet carries, -1 for all of them. Every known server sends all.
+    pub flags: i64,
+    pub combat_orders: u8,
+    pub stat: CharacterStat,
+    pub buddy_capacity: u8,
+    /// Name of the linked character, for link skills.
+    pub linked_name: Option<String>,
+    pub meso: i32,
+    pub inventories: Inventories,
+    pub skills: Vec<SkillRecord>,
+    pub cooldowns: Vec<Cooldown>,
+    pub started_quests: Vec<QuestRecord>,
+    pub completed_quests: Vec<CompletedQuest>,
+    pub mini_games: Vec<MiniGameRecord>,
+    pub couple_rings: Vec<CoupleRing>,
+    pub friendship_rings: Vec<FriendshipRing>,
+    pub marriages: Vec<MarriageRecord>,
+    pub teleport_rocks: TeleportRocks,
+    pub monster_book: MonsterBook,
+    pub new_year_cards: Vec<NewYearCard>,
+    /// Quest-like key/value records the client keeps per map area.
+    pub area_info: Vec<AreaInfo>,
+    pub reserved: u16,
+}
+
+/// A learnt skill.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkillRecord {
+    pub id: i32,
+    pub level: i32,
+    pub expiration: FileTime,
+    /// Highest level reachable with the learnt mastery books, only sent for fourth job skills.
+    pub master_level: i32,
+}
+
+/// Whether skill `id` belongs to a fourth job and so has a master level.
+pub fn needs_master_level(id: i32) -> bool {
+    (id / 10000) % 10 == 2
+}
+
+/// A skill that cannot be used again yet.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct Cooldown {
+    pub skill: i32,
+    /// Seconds left.
+    pub remaining: u16,
+}
+
+/// A quest in progress with the progress string the client parses per quest.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct QuestRecord {
+    pub id: u16,
+    #[packet(string)]
+    pub progress: String,
+}
+
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct CompletedQuest {
+    pub id: u16,
+    pub completed: FileTime,
+}
+
+/// Omok or match card results.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct MiniGameRecord {
+    pub kind: i32,
+    pub wins: i32,
+    pub draws: i32,
+    pub losses: i32,
+    pub score: i32,
+}
+
+/// A crush ring shared with another character, by the ring serials of both sides.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct CoupleRing {
+    pub partner: i32,
+    #[packet(ascii = 13)]
+    pub partner_name: String,
+    pub serial: i64,
+    pub partner_serial: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct FriendshipRing {
+    pub partner: i32,
+    #[packet(ascii = 13)]
+    pub partner_name: String,
+    pub serial: i64,
+    pub partner_serial: i64,
+    pub item: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct MarriageRecord {
+    pub id: i32,
+    pub husband: i32,
+    pub wife: i32,
+    /// 1 engaged, 3 married.
+    pub status: u16,
+    pub husband_ring: i32,
+    pub wife_ring: i32,
+    #[packet(ascii = 13)]
+    pub husband_name: String,
+    #[packet(ascii = 13)]
+    pub wife_name: String,
+}
+
+/// Maps saved for the teleport rocks, `EMPTY_ROCK` where unset.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct TeleportRocks {
+    pub regular: [i32; 5],
+    pub vip: [i32; 10],
+}
+
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct MonsterBook {
+    /// Card shown on the cover, 0 for none.
+    pub cover: i32,
+    pub reserved: u8,
+    #[packet(list = u16)]
+    pub cards: Vec<MonsterCard>,
+}
+
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct MonsterCard {
+    /// The card's item id modulo 10000.
+    pub id: u16,
+    /// Copies collected, up to 5.
+    pub level: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct NewYearCard {
+    pub id: i32,
+    pub sender: i32,
+    #[packet(string)]
+    pub sender_name: String,
+    pub sender_discarded: bool,
+    pub sent: FileTime,
+    pub receiver: i32,
+    #[packet(string)]
+    pub receiver_name: String,
+    pub receiver_discarded: bool,
+    pub received: bool,
+    pub received_time: FileTime,
+    #[packet(string)]
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct AreaInfo {
+    pub id: u16,
+    #[packet(string)]
+    pub value: String,
+}
+
+impl Encode for SkillRecord {
+    fn encode<B: BufWrite>(&self, buf: &mut B) {
+        buf.put_i32_le(self.id);
+        buf.put_i32_le(self.level);
+        self.expiration.encode(buf);
+        if needs_master_level(self.id) {
+            buf.put_i32_le(self.master_level);
+        }
+    }
+}
+
+impl Decode for SkillRecord {
+    fn decode<B: BufRead>(buf: &mut B) -> Result<Self> {
+        let id = buf.try_get_i32_le()?;
+        Ok(Self {
+            id,
+            level: buf.try_get_i32_le()?,
+            expiration: FileTime::decode(buf)?,
+            master_level: if needs_master_level(id) { buf.try_get_i32_le()? } else { 0 },
+        })
+    }
+}
+
+fn encode_list<T: Encode, B: BufWrite>(list: &[T], buf: &mut B) {
+    buf.put_u16_le(list.len() as u16);
+    for item in list {
+        item.encode(buf);
+    }
+}
+
+fn decode_list<T: Decode, B: BufRead>(buf: &mut B) -> Result<Vec<T>> {
+    let len = buf.try_get_u16_le()?;
+    (0..len).map(|_| T::decode(buf)).collect()
+}
+
+impl Encode for CharacterData {
+    fn encode<B: BufWrite>(&self, buf: &mut B) {
+        buf.put_i64_le(self.flags);
+        buf.put_u8(self.combat_orders);
+        self.stat.encode(buf);
+        buf.put_u8(self.buddy_capacity);
+        match &self.linked_name {
+            Some(name) => {
+                buf.put_u8(1);
+                buf.put_ascii_string_with_length(name);
+            }
+            None => buf.put_u8(0),
+        }
+        buf.put_i32_le(self.meso);
+        self.inventories.encode(buf);
+        encode_list(&self.skills, buf);
+        encode_list(&self.cooldowns, buf);
+        encode_list(&self.started_quests, buf);
+        encode_list(&self.completed_quests, buf);
+        encode_list(&self.mini_games, buf);
+        encode_list(&self.couple_rings, buf);
+        encode_list(&self.friendship_rings, buf);
+        encode_list(&self.marriages, buf);
+        self.teleport_rocks.encode(buf);
+        self.monster_book.encode(buf);
+        encode_list(&self.new_year_cards, buf);
+        encode_list(&self.area_info, buf);
+        buf.put_u16_le(self.reserved);
+    }
+}
+
+impl Decode for CharacterData {
+    fn decode<B: BufRead>(buf: &mut B) -> Result<Self> {
+        Ok(Self {
+            flags: buf.try_get_i64_le()?,
+            combat_orders: buf.try_get_u8()?,
+            stat: CharacterStat::decode(buf)?,
+            buddy_capacity: buf.try_get_u8()?,
+            linked_name: if buf.try_get_bool()? { Some(buf.try_get_ascii_string()?) } else { None },
+            meso: buf.try_get_i32_le()?,
+            inventories: Inventories::decode(buf)?,
+            skills: decode_list(buf)?,
+            cooldowns: decode_list(buf)?,
+            started_quests: decode_list(buf)?,
+            completed_quests: decode_list(buf)?,
+            mini_games: decode_list(buf)?,
+            couple_rings: decode_list(buf)?,
+            friendship_rings: decode_list(buf)?,
+            marriages: decode_list(buf)?,
+            teleport_rocks: TeleportRocks::decode(buf)?,
+            monster_book: MonsterBook::decode(buf)?,
+            new_year_cards: decode_list(buf)?,
+            area_info: decode_list(buf)?,
+            reserved: buf.try_get_u16_le()?,
+        })
+    }
+}
+
+impl InPacket for CharacterData {
+    fn deserialize<B: BufRead>(buf: &mut B) -> Result<Self> {
+        Self::decode(buf)
+    }
+}
+
+impl OutPacket for CharacterData {
+    fn serialize(&self) -> Vec<u8> {
+        let mut p = Vec::new();
+        self.encode(&mut p);
+        p
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_character_data {
+    use std::io::Cursor;
+
+    use crate::game::inventory::test_inventory::inventories;
+    use crate::game::test_character::stat;
+    use crate::game::{Inventories, Inventory};
+    use crate::io::{BufRead, Decode, Encode, InPacket, OutPacket};
+    use crate::time::FileTime;
+
+    use super::{
+        AreaInfo, CharacterData, CompletedQuest, Cooldown, FriendshipRing, MonsterBook, MonsterCard, QuestRecord,
+        SkillRecord, TeleportRocks, EMPTY_ROCK,
+    };
+
+    pub(crate) fn character_data() -> CharacterData {
+        CharacterData {
+            flags: -1,
+            combat_orders: 0,
+            stat: stat(),
+            buddy_capacity: 20,
+            linked_name: Some("Link".to_owned()),
+            meso: 12345,
+            inventories: inventories(),
+            skills: vec![skill(1000, 1, 0), skill(1121002, 10, 30)],
+            cooldowns: vec![Cooldown {
+                skill: 1121002,
+                remaining: 30,
+            }],
+            started_quests: vec![QuestRecord {
+                id: 1000,
+                progress: "001".to_owned(),
+            }],
+            completed_quests: vec![CompletedQuest {
+                id: 2000,
+                completed: FileTime::from_unix_millis(1_600_000_000_000),
+            }],
+            mini_games: Vec::new(),
+            couple_rings: Vec::new(),
+            friendship_rings: vec![FriendshipRing {
+                partner: 2,
+                partner_name: "Friend".to_owned(),
+                serial: 10,
+                partner_serial: 11,
+                item: 1112800,
+            }],
+            marriages: Vec::new(),
+            teleport_rocks: TeleportRocks {
+                regular: [100000000, EMPTY_ROCK, EMPTY_ROCK, EMPTY_ROCK, EMPTY_ROCK],
+                vip: [EMPTY_ROCK; 10],
+            },
+            monster_book: MonsterBook {
+                cover: 0,
+                reserved: 0,
+                cards: vec![MonsterCard { id: 1, level: 5 }],
+            },
+            new_year_cards: Vec::new(),
+            area_info: vec![AreaInfo {
+                id: 1,
+                value: "1".to_owned(),
+            }],
+            reserved: 0,
+        }
+    }
+
+    fn skill(id: i32, level: i32, master_level: i32) -> SkillRecord {
+        SkillRecord {
+            id,
+            level,
+            expiration: FileTime::NEVER,
+            master_level,
+        }
+    }
+
+    #[test]
+    fn test_skills() {
+        let never = [0x00, 0x80, 0x05, 0xBB, 0x46, 0xE6, 0x17, 0x02];
+        let mut expected = vec![0xE8, 0x03, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00];
+        expected.extend_from_slice(&never);
+        let mut p = Vec::new();
+        skill(1000, 1, 0).encode(&mut p);
+        assert_eq!(p, expected);
+
+        // Fourth job skills end with their master level.
+        let mut p = Vec::new();
+        skill(1121002, 10, 30).encode(&mut p);
+        assert_eq!(p.len(), 20);
+        assert_eq!(&p[16..], [0x1E, 0x00, 0x00, 0x00]);
+        assert_eq!(SkillRecord::decode(&mut Cursor::new(&p)).unwrap(), skill(1121002, 10, 30));
+    }
+
+    #[test]
+    fn test_minimal() {
+        let data = CharacterData {
+            linked_name: None,
+            inventories: Inventories {
+                equip: Inventory {
+                    slot_limit: 24,
+                    ..Inventory::default()
+                },
+                ..Inventories::default()
+            },
+            skills: Vec::new(),
+            cooldowns: Vec::new(),
+            started_quests: Vec::new(),
+            completed_quests: Vec::new(),
+            friendship_rings: Vec::new(),
+            teleport_rocks: TeleportRocks {
+                regular: [EMPTY_ROCK; 5],
+                vip: [EMPTY_ROCK; 10],
+            },
+            monster_book: MonsterBook {
+                cover: 0,
+                reserved: 0,
+                cards: Vec::new(),
+            },
+            area_info: Vec::new(),
+            ..character_data()
+        };
+        let mut expected = vec![0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00];
+        expected.extend_from_slice(&stat().serialize());
+        expected.extend_from_slice(&[0x14, 0x00, 0x39, 0x30, 0x00, 0x00]);
+        expected.extend_from_slice(&[24, 0, 0, 0, 0, 0x00, 0x40, 0xE0, 0xFD, 0x3B, 0x37, 0x4F, 0x01]);
+        expected.extend_from_slice(&[0; 12]);
+        // Skills, cooldowns, quests, mini games and rings.
+        expected.extend_from_slice(&[0; 16]);
+        for _ in 0..15 {
+            expected.extend_from_slice(&[0xFF, 0xC9, 0x9A, 0x3B]);
+        }
+        // Monster book, new year cards, area info and the trailing short.
+        expected.extend_from_slice(&[0; 13]);
+        assert_eq!(data.serialize(), expected);
+        assert_eq!(CharacterData::deserialize(&mut Cursor::new(&expected)).unwrap(), data);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let p = character_data().serialize();
+        let mut buf = Cursor::new(&p);
+        assert_eq!(CharacterData::deserialize(&mut buf).unwrap(), character_data());
+        assert_eq!(buf.remaining(), 0);
+        for len in 0..p.len() {
+            assert!(CharacterData::deserialize(&mut Cursor::new(&p[..len])).is_err(), "len {}", len);
+        }
+    }
+}
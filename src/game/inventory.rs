@@ -0,0 +1,478 @@
+use std::collections::BTreeMap;
+
+use num_traits::FromPrimitive;
+
+use crate::io::{BufRead, BufWrite, Decode, Encode, Error, Result};
+use crate::time::FileTime;
+
+use super::{Item, ItemKind};
+
+/// Worn items from this slot on are cash items, sent in their own list.
+const CASH_EQUIPPED: i16 = -100;
+
+/// The inventories items go to, worn equips being in `Equip` at negative slots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
+pub enum InventoryType {
+    Equip = 1,
+    Use = 2,
+    Setup = 3,
+    Etc = 4,
+    Cash = 5,
+}
+
+impl InventoryType {
+    /// The inventory holding item `id`, from its first digit.
+    pub fn of(id: i32) -> Option<Self> {
+        FromPrimitive::from_i32(id / 1_000_000)
+    }
+}
+
+impl Encode for InventoryType {
+    fn encode<B: BufWrite>(&self, buf: &mut B) {
+        buf.put_u8(*self as u8);
+    }
+}
+
+impl Decode for InventoryType {
+    fn decode<B: BufRead>(buf: &mut B) -> Result<Self> {
+        let remaining = buf.remaining();
+        let inventory = buf.try_get_u8()?;
+        FromPrimitive::from_u8(inventory)
+            .ok_or_else(|| Error::protocol(remaining, format!("Unknown inventory type {}", inventory)))
+    }
+}
+
+/// One change of an `INVENTORY_OPERATION`, applied with `Inventories::apply`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InventoryChange {
+    /// A new item, or one replacing whatever was at `slot`.
+    Add {
+        inventory: InventoryType,
+        slot: i16,
+        item: Item,
+    },
+    /// A stack grew or shrank without emptying.
+    Quantity {
+        inventory: InventoryType,
+        slot: i16,
+        quantity: i16,
+    },
+    /// An item moved, swapping places with the one at `to` if any. Equipping and unequipping are moves too.
+    Move {
+        inventory: InventoryType,
+        from: i16,
+        to: i16,
+    },
+    Remove {
+        inventory: InventoryType,
+        slot: i16,
+    },
+}
+
+impl InventoryChange {
+    pub fn inventory(&self) -> InventoryType {
+        match self {
+            InventoryChange::Add { inventory, .. }
+            | InventoryChange::Quantity { inventory, .. }
+            | InventoryChange::Move { inventory, .. }
+            | InventoryChange::Remove { inventory, .. } => *inventory,
+        }
+    }
+}
+
+impl Encode for InventoryChange {
+    fn encode<B: BufWrite>(&self, buf: &mut B) {
+        let (mode, slot) = match self {
+            InventoryChange::Add { slot, .. } => (0, slot),
+            InventoryChange::Quantity { slot, .. } => (1, slot),
+            InventoryChange::Move { from, .. } => (2, from),
+            InventoryChange::Remove { slot, .. } => (3, slot),
+        };
+        buf.put_u8(mode);
+        self.inventory().encode(buf);
+        buf.put_i16_le(*slot);
+        match self {
+            InventoryChange::Add { item, .. } => item.encode(buf),
+            InventoryChange::Quantity { quantity, .. } => buf.put_i16_le(*quantity),
+            InventoryChange::Move { to, .. } => buf.put_i16_le(*to),
+            InventoryChange::Remove { .. } => {}
+        }
+    }
+}
+
+impl Decode for InventoryChange {
+    fn decode<B: BufRead>(buf: &mut B) -> Result<Self> {
+        let remaining = buf.remaining();
+        let mode = buf.try_get_u8()?;
+        let inventory = InventoryType::decode(buf)?;
+        let slot = buf.try_get_i16_le()?;
+        Ok(match mode {
+            0 => InventoryChange::Add {
+                inventory,
+                slot,
+                item: Item::decode(buf)?,
+            },
+            1 => InventoryChange::Quantity {
+                inventory,
+                slot,
+                quantity: buf.try_get_i16_le()?,
+            },
+            2 => InventoryChange::Move {
+                inventory,
+                from: slot,
+                to: buf.try_get_i16_le()?,
+            },
+            3 => InventoryChange::Remove { inventory, slot },
+            mode => {
+                return Err(Error::protocol(remaining, format!("Unknown inventory operation {}", mode)))
+            }
+        })
+    }
+}
+
+/// The items of one inventory by slot, counted from 1.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Inventory {
+    /// Number of usable slots.
+    pub slot_limit: u8,
+    pub items: BTreeMap<i16, Item>,
+}
+
+/// Everything a character holds, as sent with its `CharacterData`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Inventories {
+    /// Worn items by their negative slot, cash items from -101 on.
+    pub equipped: BTreeMap<i16, Item>,
+    pub equip: Inventory,
+    pub use_: Inventory,
+    pub setup: Inventory,
+    pub etc: Inventory,
+    pub cash: Inventory,
+}
+
+impl Inventories {
+    pub fn get(&self, inventory: InventoryType) -> &Inventory {
+        match inventory {
+            InventoryType::Equip => &self.equip,
+            InventoryType::Use => &self.use_,
+            InventoryType::Setup => &self.setup,
+            InventoryType::Etc => &self.etc,
+            InventoryType::Cash => &self.cash,
+        }
+    }
+
+    pub fn get_mut(&mut self, inventory: InventoryType) -> &mut Inventory {
+        match inventory {
+            InventoryType::Equip => &mut self.equip,
+            InventoryType::Use => &mut self.use_,
+            InventoryType::Setup => &mut self.setup,
+            InventoryType::Etc => &mut self.etc,
+            InventoryType::Cash => &mut self.cash,
+        }
+    }
+
+    /// The item at `slot` of `inventory`, negative slots of `Equip` being worn items.
+    pub fn item(&self, inventory: InventoryType, slot: i16) -> Option<&Item> {
+        match inventory {
+            InventoryType::Equip if slot < 0 => self.equipped.get(&slot),
+            _ => self.get(inventory).items.get(&slot),
+        }
+    }
+
+    fn slots_mut(&mut self, inventory: InventoryType, slot: i16) -> &mut BTreeMap<i16, Item> {
+        match inventory {
+            InventoryType::Equip if slot < 0 => &mut self.equipped,
+            _ => &mut self.get_mut(inventory).items,
+        }
+    }
+
+    /// Applies a change sent by the server, `false` if it doesn't match what is held, which leaves it unchanged.
+    pub fn apply(&mut self, change: &InventoryChange) -> bool {
+        match *change {
+            InventoryChange::Add {
+                inventory,
+                slot,
+                ref item,
+            } => {
+                self.slots_mut(inventory, slot).insert(slot, item.clone());
+                true
+            }
+            InventoryChange::Quantity {
+                inventory,
+                slot,
+                quantity,
+            } => match self.slots_mut(inventory, slot).get_mut(&slot).map(|item| &mut item.kind) {
+                Some(ItemKind::Bundle(bundle)) => {
+                    bundle.quantity = quantity;
+                    true
+                }
+                _ => false,
+            },
+            InventoryChange::Move { inventory, from, to } => {
+                let item = match self.slots_mut(inventory, from).remove(&from) {
+                    Some(item) => item,
+                    None => return false,
+                };
+                if let Some(swapped) = self.slots_mut(inventory, to).insert(to, item) {
+                    self.slots_mut(inventory, from).insert(from, swapped);
+                }
+                true
+            }
+            InventoryChange::Remove { inventory, slot } => self.slots_mut(inventory, slot).remove(&slot).is_some(),
+        }
+    }
+}
+
+/// Writes items followed by the slot 0 ending the list, slots as `u16` for equips and `u8` otherwise.
+fn encode_items<'a, B, I>(items: I, wide: bool, buf: &mut B)
+where
+    B: BufWrite,
+    I: IntoIterator<Item = (i16, &'a Item)>,
+{
+    for (slot, item) in items {
+        if wide {
+            buf.put_i16_le(slot);
+        } else {
+            buf.put_u8(slot as u8);
+        }
+        item.encode(buf);
+    }
+    if wide {
+        buf.put_i16_le(0);
+    } else {
+        buf.put_u8(0);
+    }
+}
+
+fn decode_items<B: BufRead>(buf: &mut B, wide: bool) -> Result<Vec<(i16, Item)>> {
+    let mut items = Vec::new();
+    loop {
+        let slot = if wide { buf.try_get_i16_le()? } else { buf.try_get_u8()? as i16 };
+        if slot == 0 {
+            return Ok(items);
+        }
+        items.push((slot, Item::decode(buf)?));
+    }
+}
+
+impl Encode for Inventories {
+    fn encode<B: BufWrite>(&self, buf: &mut B) {
+        let bags = [&self.equip, &self.use_, &self.setup, &self.etc, &self.cash];
+        for bag in &bags {
+            buf.put_u8(bag.slot_limit);
+        }
+        FileTime::ZERO.encode(buf);
+
+        // Worn items go by their positive slot, minus 100 for cash items.
+        let (cash, normal): (Vec<_>, Vec<_>) = self.equipped.iter().partition(|(slot, _)| **slot < CASH_EQUIPPED);
+        encode_items(normal.into_iter().map(|(slot, item)| (-slot, item)), true, buf);
+        encode_items(cash.into_iter().map(|(slot, item)| (-slot + CASH_EQUIPPED, item)), true, buf);
+        encode_items(self.equip.items.iter().map(|(slot, item)| (*slot, item)), true, buf);
+        // Another list of equips the client reads, always empty.
+        buf.put_i16_le(0);
+        for bag in &bags[1..] {
+            encode_items(bag.items.iter().map(|(slot, item)| (*slot, item)), false, buf);
+        }
+    }
+}
+
+impl Decode for Inventories {
+    fn decode<B: BufRead>(buf: &mut B) -> Result<Self> {
+        let mut inventories = Inventories::default();
+        let slot_limits = <[u8; 5]>::decode(buf)?;
+        FileTime::decode(buf)?;
+
+        for (slot, item) in decode_items(buf, true)? {
+            inventories.equipped.insert(-slot, item);
+        }
+        for (slot, item) in decode_items(buf, true)? {
+            inventories.equipped.insert(-slot + CASH_EQUIPPED, item);
+        }
+        inventories.equip.items.extend(decode_items(buf, true)?);
+        decode_items(buf, true)?;
+
+        let Inventories {
+            equip,
+            use_,
+            setup,
+            etc,
+            cash,
+            ..
+        } = &mut inventories;
+        let mut bags = [equip, use_, setup, etc, cash];
+        for (bag, slot_limit) in bags.iter_mut().zip(slot_limits) {
+            bag.slot_limit = slot_limit;
+        }
+        for bag in &mut bags[1..] {
+            bag.items.extend(decode_items(buf, false)?);
+        }
+        Ok(inventories)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_inventory {
+    use std::io::Cursor;
+
+    use crate::game::item::test_item::{bundle, equip, pet};
+    use crate::game::ItemKind;
+    use crate::io::{BufRead, Decode, Encode, Error, OutPacket};
+
+    use super::{Inventories, Inventory, InventoryChange, InventoryType};
+
+    pub(crate) fn inventories() -> Inventories {
+        let mut hat = equip(1002140, 0);
+        hat.cash_serial = Some(2);
+        if let ItemKind::Equip(equip) = &mut hat.kind {
+            equip.growth = None;
+        }
+        let bag = |slot_limit, items: Vec<_>| Inventory {
+            slot_limit,
+            items: items.into_iter().collect(),
+        };
+        Inventories {
+            equipped: vec![(-11, equip(1302000, 17)), (-101, hat)].into_iter().collect(),
+            equip: bag(24, vec![(1, equip(1040002, 0))]),
+            use_: bag(24, vec![(1, bundle(2000000, 100)), (2, bundle(2070000, 500))]),
+            setup: bag(24, vec![]),
+            etc: bag(24, vec![(1, bundle(4000000, 5))]),
+            cash: bag(96, vec![(1, pet())]),
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let empty = Inventories {
+            equip: Inventory {
+                slot_limit: 24,
+                ..Inventory::default()
+            },
+            ..Inventories::default()
+        };
+        let mut p = Vec::new();
+        empty.encode(&mut p);
+        let mut expected = vec![24, 0, 0, 0, 0, 0x00, 0x40, 0xE0, 0xFD, 0x3B, 0x37, 0x4F, 0x01];
+        expected.extend_from_slice(&[0; 8]);
+        expected.extend_from_slice(&[0; 4]);
+        assert_eq!(p, expected);
+        assert_eq!(Inventories::decode(&mut Cursor::new(&p)).unwrap(), empty);
+    }
+
+    #[test]
+    fn test_slots() {
+        let mut p = Vec::new();
+        inventories().encode(&mut p);
+        // Worn items are sent by their positive slot, cash ones 100 less.
+        assert_eq!(&p[13..16], [0x0B, 0x00, 0x01]);
+        let sword = equip(1302000, 17).serialize().len();
+        assert_eq!(&p[15 + sword..20 + sword], [0x00, 0x00, 0x01, 0x00, 0x01]);
+
+        let mut buf = Cursor::new(&p);
+        assert_eq!(Inventories::decode(&mut buf).unwrap(), inventories());
+        assert_eq!(buf.remaining(), 0);
+    }
+
+    #[test]
+    fn test_inventory_type() {
+        assert_eq!(InventoryType::of(1302000), Some(InventoryType::Equip));
+        assert_eq!(InventoryType::of(2070000), Some(InventoryType::Use));
+        assert_eq!(InventoryType::of(5000000), Some(InventoryType::Cash));
+        assert_eq!(InventoryType::of(9000000), None);
+        let err = InventoryType::decode(&mut Cursor::new(&[6][..])).unwrap_err().locate(1);
+        assert!(matches!(err, Error::Protocol { offset: 0, .. }));
+    }
+
+    #[test]
+    fn test_change() {
+        let moved = InventoryChange::Move {
+            inventory: InventoryType::Equip,
+            from: 1,
+            to: -5,
+        };
+        let mut p = Vec::new();
+        moved.encode(&mut p);
+        assert_eq!(p, [0x02, 0x01, 0x01, 0x00, 0xFB, 0xFF]);
+        assert_eq!(InventoryChange::decode(&mut Cursor::new(&p)).unwrap(), moved);
+
+        let added = InventoryChange::Add {
+            inventory: InventoryType::Etc,
+            slot: 3,
+            item: bundle(4000000, 1),
+        };
+        let mut p = Vec::new();
+        added.encode(&mut p);
+        assert_eq!(&p[..4], [0x00, 0x04, 0x03, 0x00]);
+        assert_eq!(&p[4..], &bundle(4000000, 1).serialize()[..]);
+        assert_eq!(InventoryChange::decode(&mut Cursor::new(&p)).unwrap(), added);
+
+        let err = InventoryChange::decode(&mut Cursor::new(&[0x04, 0x01, 0x01, 0x00][..])).unwrap_err().locate(4);
+        assert!(matches!(err, Error::Protocol { offset: 0, .. }));
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut held = inventories();
+        let quantity = |held: &Inventories, slot| match &held.item(InventoryType::Use, slot).unwrap().kind {
+            ItemKind::Bundle(bundle) => bundle.quantity,
+            other => panic!("{:?}", other),
+        };
+
+        assert!(held.apply(&InventoryChange::Quantity {
+            inventory: InventoryType::Use,
+            slot: 1,
+            quantity: 99,
+        }));
+        assert_eq!(quantity(&held, 1), 99);
+
+        // Equipping swaps the shirt with nothing, then the sword with the shirt.
+        let equip_shirt = InventoryChange::Move {
+            inventory: InventoryType::Equip,
+            from: 1,
+            to: -5,
+        };
+        assert!(held.apply(&equip_shirt));
+        assert_eq!(held.item(InventoryType::Equip, -5).unwrap().id, 1040002);
+        assert!(held.equip.items.is_empty());
+        assert!(held.apply(&InventoryChange::Move {
+            inventory: InventoryType::Equip,
+            from: -11,
+            to: -5,
+        }));
+        assert_eq!(held.equipped[&-5].id, 1302000);
+        assert_eq!(held.equipped[&-11].id, 1040002);
+
+        assert!(held.apply(&InventoryChange::Move {
+            inventory: InventoryType::Use,
+            from: 1,
+            to: 2,
+        }));
+        assert_eq!(quantity(&held, 1), 500);
+        assert_eq!(quantity(&held, 2), 99);
+
+        assert!(held.apply(&InventoryChange::Remove {
+            inventory: InventoryType::Etc,
+            slot: 1,
+        }));
+        assert!(held.etc.items.is_empty());
+        assert!(held.apply(&InventoryChange::Add {
+            inventory: InventoryType::Etc,
+            slot: 4,
+            item: bundle(4000001, 2),
+        }));
+        assert_eq!(held.item(InventoryType::Etc, 4), Some(&bundle(4000001, 2)));
+
+        // Changes to what isn't held are refused.
+        let before = held.clone();
+        assert!(!held.apply(&equip_shirt));
+        assert!(!held.apply(&InventoryChange::Remove {
+            inventory: InventoryType::Etc,
+            slot: 1,
+        }));
+        assert!(!held.apply(&InventoryChange::Quantity {
+            inventory: InventoryType::Cash,
+            slot: 1,
+            quantity: 2,
+        }));
+        assert_eq!(held, before);
+    }
+}
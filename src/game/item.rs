@@ -0,0 +1,377 @@
+use crate::io::{BufRead, BufWrite, Decode, Encode, Error, InPacket, OutPacket, Result};
+use crate::time::FileTime;
+
+/// Filler the client expects in place of the growth fields of cash equips.
+const CASH_EQUIP_FILLER: [u8; 10] = [0x40; 10];
+
+/// An item in an inventory or worn (`GW_ItemSlotBase`), without its slot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub id: i32,
+    /// Serial number of cash items, the pet id for pets and the ring id for rings.
+    pub cash_serial: Option<i64>,
+    pub expiration: FileTime,
+    pub kind: ItemKind,
+}
+
+/// What an `Item` is, tagged on the wire with 1, 2 or 3.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemKind {
+    Equip(Box<Equip>),
+    Bundle(Bundle),
+    Pet(Box<Pet>),
+}
+
+/// An equip with its stats and upgrades (`GW_ItemSlotEquip`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Equip {
+    /// Scrolls that can still be applied.
+    pub upgrade_slots: u8,
+    /// Scrolls applied successfully.
+    pub level: u8,
+    pub str: i16,
+    pub dex: i16,
+    pub int: i16,
+    pub luk: i16,
+    pub hp: i16,
+    pub mp: i16,
+    pub watk: i16,
+    pub matk: i16,
+    pub wdef: i16,
+    pub mdef: i16,
+    pub acc: i16,
+    pub avoid: i16,
+    pub hands: i16,
+    pub speed: i16,
+    pub jump: i16,
+    pub owner: String,
+    pub flag: i16,
+    /// Item level and experience, `None` for cash equips which have none.
+    pub growth: Option<EquipGrowth>,
+    /// When the item was first worn, `FileTime::ZERO` until then.
+    pub equipped: FileTime,
+    pub prev_bonus_exp_rate: i32,
+}
+
+/// Growth of non-cash equips, levelled up by wearing them.
+#[derive(Debug, Clone, Default, PartialEq, InPacket, OutPacket)]
+pub struct EquipGrowth {
+    pub level_up_type: u8,
+    pub level: u8,
+    pub exp: i32,
+    /// Vicious hammers used on the item.
+    pub hammers: i32,
+    pub reserved: i64,
+}
+
+/// A stackable item: use, setup, etc and cash items (`GW_ItemSlotBundle`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bundle {
+    pub quantity: i16,
+    pub owner: String,
+    pub flag: i16,
+    /// Only sent for rechargeable items, stars and bullets.
+    pub recharge_serial: i64,
+}
+
+/// A pet and its condition (`GW_ItemSlotPet`).
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct Pet {
+    #[packet(ascii = 13)]
+    pub name: String,
+    pub level: u8,
+    pub closeness: i16,
+    pub fullness: u8,
+    /// When the pet turns into a doll, `FileTime::PERMANENT` for pets that live forever.
+    pub dead: FileTime,
+    pub attribute: u16,
+    pub skills: u16,
+    pub remaining_life: i32,
+    pub reserved: u16,
+}
+
+/// Stars and bullets, whose stacks are recharged instead of used up.
+pub fn is_rechargeable(id: i32) -> bool {
+    matches!(id / 10000, 207 | 233)
+}
+
+impl Encode for Item {
+    fn encode<B: BufWrite>(&self, buf: &mut B) {
+        let kind = match &self.kind {
+            ItemKind::Equip(_) => 1,
+            ItemKind::Bundle(_) => 2,
+            ItemKind::Pet(_) => 3,
+        };
+        buf.put_u8(kind);
+        buf.put_i32_le(self.id);
+        match self.cash_serial {
+            Some(serial) => {
+                buf.put_u8(1);
+                buf.put_i64_le(serial);
+            }
+            None => buf.put_u8(0),
+        }
+        self.expiration.encode(buf);
+        match &self.kind {
+            ItemKind::Equip(equip) => equip.encode_for(self.cash_serial.is_some(), buf),
+            ItemKind::Bundle(bundle) => {
+                buf.put_i16_le(bundle.quantity);
+                buf.put_ascii_string_with_length(&bundle.owner);
+                buf.put_i16_le(bundle.flag);
+                if is_rechargeable(self.id) {
+                    buf.put_i64_le(bundle.recharge_serial);
+                }
+            }
+            ItemKind::Pet(pet) => pet.encode(buf),
+        }
+    }
+}
+
+impl Decode for Item {
+    fn decode<B: BufRead>(buf: &mut B) -> Result<Self> {
+        let remaining = buf.remaining();
+        let kind = buf.try_get_u8()?;
+        let id = buf.try_get_i32_le()?;
+        let cash_serial = if buf.try_get_bool()? { Some(buf.try_get_i64_le()?) } else { None };
+        let expiration = FileTime::decode(buf)?;
+        let kind = match kind {
+            1 => ItemKind::Equip(Box::new(Equip::decode_for(cash_serial.is_some(), buf)?)),
+            2 => ItemKind::Bundle(Bundle {
+                quantity: buf.try_get_i16_le()?,
+                owner: buf.try_get_ascii_string()?,
+                flag: buf.try_get_i16_le()?,
+                recharge_serial: if is_rechargeable(id) { buf.try_get_i64_le()? } else { 0 },
+            }),
+            3 => ItemKind::Pet(Box::new(Pet::decode(buf)?)),
+            kind => {
+                return Err(Error::protocol(remaining, format!("Unknown item type {}", kind)))
+            }
+        };
+        Ok(Self {
+            id,
+            cash_serial,
+            expiration,
+            kind,
+        })
+    }
+}
+
+impl Equip {
+    fn encode_for<B: BufWrite>(&self, cash: bool, buf: &mut B) {
+        buf.put_u8(self.upgrade_slots);
+        buf.put_u8(self.level);
+        let stats = [
+            self.str, self.dex, self.int, self.luk, self.hp, self.mp, self.watk, self.matk, self.wdef, self.mdef,
+            self.acc, self.avoid, self.hands, self.speed, self.jump,
+        ];
+        stats.encode(buf);
+        buf.put_ascii_string_with_length(&self.owner);
+        buf.put_i16_le(self.flag);
+        match (&self.growth, cash) {
+            (_, true) => buf.put_slice(&CASH_EQUIP_FILLER),
+            (Some(growth), false) => growth.encode(buf),
+            (None, false) => EquipGrowth::default().encode(buf),
+        }
+        self.equipped.encode(buf);
+        buf.put_i32_le(self.prev_bonus_exp_rate);
+    }
+
+    fn decode_for<B: BufRead>(cash: bool, buf: &mut B) -> Result<Self> {
+        let upgrade_slots = buf.try_get_u8()?;
+        let level = buf.try_get_u8()?;
+        let [str, dex, int, luk, hp, mp, watk, matk, wdef, mdef, acc, avoid, hands, speed, jump] =
+            <[i16; 15]>::decode(buf)?;
+        let owner = buf.try_get_ascii_string()?;
+        let flag = buf.try_get_i16_le()?;
+        let growth = if cash {
+            buf.try_get_array::<10>()?;
+            None
+        } else {
+            Some(EquipGrowth::decode(buf)?)
+        };
+        Ok(Self {
+            upgrade_slots,
+            level,
+            str,
+            dex,
+            int,
+            luk,
+            hp,
+            mp,
+            watk,
+            matk,
+            wdef,
+            mdef,
+            acc,
+            avoid,
+            hands,
+            speed,
+            jump,
+            owner,
+            flag,
+            growth,
+            equipped: FileTime::decode(buf)?,
+            prev_bonus_exp_rate: buf.try_get_i32_le()?,
+        })
+    }
+}
+
+impl InPacket for Item {
+    fn deserialize<B: BufRead>(buf: &mut B) -> Result<Self> {
+        Self::decode(buf)
+    }
+}
+
+impl OutPacket for Item {
+    fn serialize(&self) -> Vec<u8> {
+        let mut p = Vec::new();
+        self.encode(&mut p);
+        p
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_item {
+    use std::io::Cursor;
+
+    use crate::io::{Error, InPacket, OutPacket};
+    use crate::time::FileTime;
+
+    use super::{Bundle, Equip, EquipGrowth, Item, ItemKind, Pet};
+
+    const NEVER: [u8; 8] = [0x00, 0x80, 0x05, 0xBB, 0x46, 0xE6, 0x17, 0x02];
+    const ZERO: [u8; 8] = [0x00, 0x40, 0xE0, 0xFD, 0x3B, 0x37, 0x4F, 0x01];
+    const PERMANENT: [u8; 8] = [0x00, 0xC0, 0x9B, 0x90, 0x7D, 0xE5, 0x17, 0x02];
+
+    pub(crate) fn equip(id: i32, watk: i16) -> Item {
+        Item {
+            id,
+            cash_serial: None,
+            expiration: FileTime::NEVER,
+            kind: ItemKind::Equip(Box::new(Equip {
+                upgrade_slots: 7,
+                level: 0,
+                str: 0,
+                dex: 0,
+                int: 0,
+                luk: 0,
+                hp: 0,
+                mp: 0,
+                watk,
+                matk: 0,
+                wdef: 0,
+                mdef: 0,
+                acc: 0,
+                avoid: 0,
+                hands: 0,
+                speed: 0,
+                jump: 0,
+                owner: String::new(),
+                flag: 0,
+                growth: Some(EquipGrowth::default()),
+                equipped: FileTime::ZERO,
+                prev_bonus_exp_rate: -1,
+            })),
+        }
+    }
+
+    pub(crate) fn bundle(id: i32, quantity: i16) -> Item {
+        Item {
+            id,
+            cash_serial: None,
+            expiration: FileTime::NEVER,
+            kind: ItemKind::Bundle(Bundle {
+                quantity,
+                owner: String::new(),
+                flag: 0,
+                recharge_serial: 0,
+            }),
+        }
+    }
+
+    pub(crate) fn pet() -> Item {
+        Item {
+            id: 5000000,
+            cash_serial: Some(1),
+            expiration: FileTime::NEVER,
+            kind: ItemKind::Pet(Box::new(Pet {
+                name: "Kitty".to_owned(),
+                level: 1,
+                closeness: 0,
+                fullness: 100,
+                dead: FileTime::PERMANENT,
+                attribute: 0,
+                skills: 0,
+                remaining_life: 0,
+                reserved: 0,
+            })),
+        }
+    }
+
+    #[test]
+    fn test_equip() {
+        let sword = equip(1302000, 17);
+        let mut expected = vec![0x01, 0xF0, 0xDD, 0x13, 0x00, 0x00];
+        expected.extend_from_slice(&NEVER);
+        expected.extend_from_slice(&[0x07, 0x00]);
+        expected.extend_from_slice(&[0; 12]);
+        expected.extend_from_slice(&[0x11, 0x00]);
+        expected.extend_from_slice(&[0; 16]);
+        expected.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+        expected.extend_from_slice(&[0; 18]);
+        expected.extend_from_slice(&ZERO);
+        expected.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(sword.serialize(), expected);
+        assert_eq!(Item::deserialize(&mut Cursor::new(&expected)).unwrap(), sword);
+
+        // Cash equips carry filler instead of their growth.
+        let mut hat = equip(1002140, 0);
+        hat.cash_serial = Some(2);
+        if let ItemKind::Equip(equip) = &mut hat.kind {
+            equip.growth = None;
+        }
+        let p = hat.serialize();
+        // The cash serial takes 8 more bytes, the filler 8 less.
+        assert_eq!(p.len(), expected.len());
+        assert_eq!(&p[p.len() - 22..p.len() - 12], [0x40; 10]);
+        assert_eq!(Item::deserialize(&mut Cursor::new(&p)).unwrap(), hat);
+    }
+
+    #[test]
+    fn test_bundle() {
+        let potion = bundle(2000000, 100);
+        let mut expected = vec![0x02, 0x80, 0x84, 0x1E, 0x00, 0x00];
+        expected.extend_from_slice(&NEVER);
+        expected.extend_from_slice(&[0x64, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(potion.serialize(), expected);
+
+        // Stars carry a serial after the flag.
+        let mut stars = bundle(2070000, 500);
+        if let ItemKind::Bundle(bundle) = &mut stars.kind {
+            bundle.recharge_serial = 0x3400_0054_0000_0002;
+        }
+        let p = stars.serialize();
+        assert_eq!(&p[p.len() - 14..], [0xF4, 0x01, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x54, 0x00, 0x00, 0x34]);
+        assert_eq!(Item::deserialize(&mut Cursor::new(&p)).unwrap(), stars);
+    }
+
+    #[test]
+    fn test_pet() {
+        let mut expected = vec![0x03, 0x40, 0x4B, 0x4C, 0x00, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        expected.extend_from_slice(&NEVER);
+        expected.extend_from_slice(b"Kitty\0\0\0\0\0\0\0\0");
+        expected.extend_from_slice(&[0x01, 0x00, 0x00, 0x64]);
+        expected.extend_from_slice(&PERMANENT);
+        expected.extend_from_slice(&[0; 10]);
+        assert_eq!(pet().serialize(), expected);
+        assert_eq!(Item::deserialize(&mut Cursor::new(&expected)).unwrap(), pet());
+    }
+
+    #[test]
+    fn test_unknown_kind() {
+        let mut p = bundle(2000000, 1).serialize();
+        p[0] = 4;
+        let err = Item::deserialize(&mut Cursor::new(&p)).unwrap_err().locate(p.len());
+        assert!(matches!(err, Error::Protocol { offset: 0, .. }));
+    }
+}
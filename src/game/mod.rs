@@ -0,0 +1,34 @@
+//! Game data carried by packets of both directions: characters, their looks and stats, what they hold and how
+//! they move.
+
+pub use character::{AvatarLook, CharacterEntry, CharacterStat, Ranking};
+pub use character_data::{
+    needs_master_level, AreaInfo, CharacterData, CompletedQuest, Cooldown, CoupleRing, FriendshipRing,
+    MarriageRecord, MiniGameRecord, MonsterBook, MonsterCard, NewYearCard, QuestRecord, SkillRecord, TeleportRocks,
+    EMPTY_ROCK,
+};
+pub use inventory::{Inventories, Inventory, InventoryChange, InventoryType};
+pub use item::{is_rechargeable, Bundle, Equip, EquipGrowth, Item, ItemKind, Pet};
+pub use movement::{
+    AbsoluteMove, FallStartMove, JumpDownMove, MovePath, MovePathBuilder, Movement, Point, RelativeMove, StanceMove,
+    TeleportMove, STANCE_JUMP, STANCE_STAND, STANCE_WALK,
+};
+pub use stat::{ForcedStat, ForcedStatMask, Stat, StatMask};
+
+mod character;
+mod character_data;
+mod inventory;
+mod item;
+mod movement;
+mod stat;
+
+pub(crate) use stat::{decode_stats, encode_stats};
+
+#[cfg(test)]
+pub(crate) use character::test_character;
+#[cfg(test)]
+pub(crate) use character_data::test_character_data;
+#[cfg(test)]
+pub(crate) use item::test_item;
+#[cfg(test)]
+pub(crate) use movement::test_movement;
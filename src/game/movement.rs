@@ -0,0 +1,431 @@
+use crate::io::{BufRead, BufWrite, Decode, Encode, Error, InPacket, OutPacket, Result};
+
+/// Horizontal speed of a character walking at 100% speed, in pixels per second.
+const WALK_SPEED: i16 = 125;
+/// Initial vertical speed of a jump at 100% jump.
+const JUMP_SPEED: i16 = 555;
+/// Time a jump keeps the character in the air when it lands where it took off, in milliseconds.
+const JUMP_DURATION: i16 = 600;
+/// Longest distance a single walk fragment covers, longer walks are split.
+const MAX_WALK: i32 = 2000;
+
+/// Stances of a character: add 1 to face left.
+pub const STANCE_WALK: u8 = 2;
+pub const STANCE_STAND: u8 = 4;
+pub const STANCE_JUMP: u8 = 6;
+
+/// A position on a map, or a speed in pixels per second.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, InPacket, OutPacket)]
+pub struct Point {
+    pub x: i16,
+    pub y: i16,
+}
+
+impl Point {
+    pub fn new(x: i16, y: i16) -> Self {
+        Self { x, y }
+    }
+}
+
+/// Moves to a position standing on a foothold or in the air, the kind servers track positions from.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct AbsoluteMove {
+    pub position: Point,
+    pub velocity: Point,
+    /// 0 in the air.
+    pub foothold: i16,
+    pub stance: u8,
+    /// Milliseconds.
+    pub duration: i16,
+}
+
+/// Moves by an offset, like a flash jump or a spring.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct RelativeMove {
+    pub offset: Point,
+    pub stance: u8,
+    pub duration: i16,
+}
+
+/// Appears at a position at once, like a teleport or a rush.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct TeleportMove {
+    pub position: Point,
+    pub velocity: Point,
+    pub stance: u8,
+}
+
+/// Drops through a foothold onto the one below.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct JumpDownMove {
+    pub position: Point,
+    pub velocity: Point,
+    pub foothold: i16,
+    /// The foothold dropped through.
+    pub from_foothold: i16,
+    pub stance: u8,
+    pub duration: i16,
+}
+
+/// Starts falling off the edge of a foothold.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct FallStartMove {
+    pub velocity: Point,
+    /// The foothold fallen from.
+    pub foothold: i16,
+    pub stance: u8,
+    pub duration: i16,
+}
+
+/// Changes the stance without moving.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct StanceMove {
+    pub stance: u8,
+    pub duration: i16,
+}
+
+/// One fragment of a `MovePath`, the command it is sent with kept where several share a layout.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Movement {
+    /// Commands 0 (normal), 5 and 17 (floating).
+    Absolute(u8, AbsoluteMove),
+    /// Commands 1, 2, 6 (flash jump), 12, 13, 16, 18, 19 (spring), 20 (Aran combat step) and 22.
+    Relative(u8, RelativeMove),
+    /// Commands 3, 4 (teleport), 7 (assaulter), 8 (assassinate), 9 (rush) and 11 (chair).
+    Teleport(u8, TeleportMove),
+    /// Command 14.
+    FallStart(FallStartMove),
+    /// Command 15.
+    JumpDown(JumpDownMove),
+    /// Command 21.
+    Stance(StanceMove),
+    /// Command 10, the equips or stats the character moves with changed.
+    ChangeEquip(u8),
+}
+
+impl Movement {
+    /// Where the fragment ends, for the kinds that say.
+    pub fn position(&self) -> Option<Point> {
+        match self {
+            Movement::Absolute(_, m) => Some(m.position),
+            Movement::Teleport(_, m) => Some(m.position),
+            Movement::JumpDown(m) => Some(m.position),
+            _ => None,
+        }
+    }
+
+    pub fn stance(&self) -> Option<u8> {
+        match self {
+            Movement::Absolute(_, m) => Some(m.stance),
+            Movement::Relative(_, m) => Some(m.stance),
+            Movement::Teleport(_, m) => Some(m.stance),
+            Movement::FallStart(m) => Some(m.stance),
+            Movement::JumpDown(m) => Some(m.stance),
+            Movement::Stance(m) => Some(m.stance),
+            Movement::ChangeEquip(_) => None,
+        }
+    }
+}
+
+impl Encode for Movement {
+    fn encode<B: BufWrite>(&self, buf: &mut B) {
+        match self {
+            Movement::Absolute(command, m) => {
+                buf.put_u8(*command);
+                m.encode(buf);
+            }
+            Movement::Relative(command, m) => {
+                buf.put_u8(*command);
+                m.encode(buf);
+            }
+            Movement::Teleport(command, m) => {
+                buf.put_u8(*command);
+                m.encode(buf);
+            }
+            Movement::FallStart(m) => {
+                buf.put_u8(14);
+                m.encode(buf);
+            }
+            Movement::JumpDown(m) => {
+                buf.put_u8(15);
+                m.encode(buf);
+            }
+            Movement::Stance(m) => {
+                buf.put_u8(21);
+                m.encode(buf);
+            }
+            Movement::ChangeEquip(stat) => {
+                buf.put_u8(10);
+                buf.put_u8(*stat);
+            }
+        }
+    }
+}
+
+impl Decode for Movement {
+    fn decode<B: BufRead>(buf: &mut B) -> Result<Self> {
+        let remaining = buf.remaining();
+        Ok(match buf.try_get_u8()? {
+            command @ (0 | 5 | 17) => Movement::Absolute(command, AbsoluteMove::decode(buf)?),
+            command @ (1 | 2 | 6 | 12 | 13 | 16 | 18..=20 | 22) => {
+                Movement::Relative(command, RelativeMove::decode(buf)?)
+            }
+            command @ (3 | 4 | 7..=9 | 11) => Movement::Teleport(command, TeleportMove::decode(buf)?),
+            10 => Movement::ChangeEquip(buf.try_get_u8()?),
+            14 => Movement::FallStart(FallStartMove::decode(buf)?),
+            15 => Movement::JumpDown(JumpDownMove::decode(buf)?),
+            21 => Movement::Stance(StanceMove::decode(buf)?),
+            command => {
+                return Err(Error::protocol(remaining, format!("Unknown movement command {}", command)))
+            }
+        })
+    }
+}
+
+/// How a character, monster, pet or summon moved from `start`, as relayed by the server.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct MovePath {
+    pub start: Point,
+    #[packet(list = u8)]
+    pub movements: Vec<Movement>,
+}
+
+impl MovePath {
+    /// Starts a path at `start` on `foothold`, facing right.
+    pub fn builder(start: Point, foothold: i16) -> MovePathBuilder {
+        MovePathBuilder {
+            path: MovePath {
+                start,
+                movements: Vec::new(),
+            },
+            position: start,
+            foothold,
+            left: false,
+        }
+    }
+
+    /// Where the path ends.
+    pub fn position(&self) -> Point {
+        self.movements.iter().rev().find_map(Movement::position).unwrap_or(self.start)
+    }
+
+    /// The stance at the end of the path, if any fragment set one.
+    pub fn stance(&self) -> Option<u8> {
+        self.movements.iter().rev().find_map(Movement::stance)
+    }
+}
+
+/// Builds the walks and jumps of a character at default speed, ending standing.
+#[derive(Debug, Clone)]
+pub struct MovePathBuilder {
+    path: MovePath,
+    position: Point,
+    foothold: i16,
+    left: bool,
+}
+
+impl MovePathBuilder {
+    /// Walks along the current foothold to `x`.
+    pub fn walk_to(mut self, x: i16) -> Self {
+        while self.position.x != x {
+            // In i32, the distance between two i16 positions does not always fit in an i16.
+            let distance = (i32::from(x) - i32::from(self.position.x)).clamp(-MAX_WALK, MAX_WALK);
+            self.left = distance < 0;
+            self.position.x += distance as i16;
+            let speed = if self.left { -WALK_SPEED } else { WALK_SPEED };
+            self.push(STANCE_WALK, Point::new(speed, 0), self.foothold, walk_duration(distance));
+        }
+        self
+    }
+
+    /// Jumps from where the path is to `to`, landing on `foothold`.
+    pub fn jump_to(mut self, to: Point, foothold: i16) -> Self {
+        let distance = i32::from(to.x) - i32::from(self.position.x);
+        if distance != 0 {
+            self.left = distance < 0;
+        }
+        let speed = distance.signum() as i16 * WALK_SPEED;
+        self.push(STANCE_JUMP, Point::new(speed, -JUMP_SPEED), 0, walk_duration(distance).max(JUMP_DURATION));
+        self.position = to;
+        self.foothold = foothold;
+        self.push(STANCE_JUMP, Point::new(speed, 0), foothold, 0);
+        self
+    }
+
+    /// Ends the path standing still.
+    pub fn build(mut self) -> MovePath {
+        self.push(STANCE_STAND, Point::default(), self.foothold, 0);
+        self.path
+    }
+
+    fn push(&mut self, stance: u8, velocity: Point, foothold: i16, duration: i16) {
+        let movement = AbsoluteMove {
+            position: self.position,
+            velocity,
+            foothold,
+            stance: stance + self.left as u8,
+            duration,
+        };
+        self.path.movements.push(Movement::Absolute(0, movement));
+    }
+}
+
+/// Milliseconds to walk `distance` pixels, at most `i16::MAX` as fragments cannot last longer.
+fn walk_duration(distance: i32) -> i16 {
+    let duration = distance.unsigned_abs() * 1000 / WALK_SPEED as u32;
+    duration.min(i16::MAX as u32) as i16
+}
+
+#[cfg(test)]
+pub(crate) mod test_movement {
+    use std::io::Cursor;
+
+    use crate::io::{BufRead, Error, InPacket, OutPacket};
+
+    use super::{
+        AbsoluteMove, FallStartMove, JumpDownMove, MovePath, Movement, Point, RelativeMove, StanceMove, TeleportMove,
+        STANCE_JUMP, STANCE_STAND, STANCE_WALK,
+    };
+
+    /// A path with one fragment of each kind, ending at (150, -20).
+    pub(crate) fn every_kind() -> MovePath {
+        MovePath {
+            start: Point::new(-100, 34),
+            movements: vec![
+                Movement::Absolute(
+                    0,
+                    AbsoluteMove {
+                        position: Point::new(-50, 34),
+                        velocity: Point::new(125, 0),
+                        foothold: 12,
+                        stance: STANCE_WALK,
+                        duration: 400,
+                    },
+                ),
+                Movement::Relative(
+                    6,
+                    RelativeMove {
+                        offset: Point::new(350, -200),
+                        stance: STANCE_JUMP,
+                        duration: 30,
+                    },
+                ),
+                Movement::FallStart(FallStartMove {
+                    velocity: Point::new(0, 100),
+                    foothold: 12,
+                    stance: STANCE_JUMP,
+                    duration: 120,
+                }),
+                Movement::JumpDown(JumpDownMove {
+                    position: Point::new(0, 80),
+                    velocity: Point::new(0, 0),
+                    foothold: 20,
+                    from_foothold: 12,
+                    stance: STANCE_STAND,
+                    duration: 300,
+                }),
+                Movement::ChangeEquip(1),
+                Movement::Stance(StanceMove {
+                    stance: STANCE_STAND + 1,
+                    duration: 10,
+                }),
+                Movement::Teleport(
+                    3,
+                    TeleportMove {
+                        position: Point::new(150, -20),
+                        velocity: Point::new(0, 0),
+                        stance: STANCE_STAND,
+                    },
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_fragments() {
+        let path = every_kind();
+        let p = path.serialize();
+        let mut expected = vec![0x9C, 0xFF, 0x22, 0x00, 0x07];
+        expected.extend_from_slice(&[0x00, 0xCE, 0xFF, 0x22, 0x00, 0x7D, 0x00, 0x00, 0x00]);
+        expected.extend_from_slice(&[0x0C, 0x00, 0x02, 0x90, 0x01]);
+        expected.extend_from_slice(&[0x06, 0x5E, 0x01, 0x38, 0xFF, 0x06, 0x1E, 0x00]);
+        expected.extend_from_slice(&[0x0E, 0x00, 0x00, 0x64, 0x00, 0x0C, 0x00, 0x06, 0x78, 0x00]);
+        expected.extend_from_slice(&[0x0F, 0x00, 0x00, 0x50, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        expected.extend_from_slice(&[0x14, 0x00, 0x0C, 0x00, 0x04, 0x2C, 0x01]);
+        expected.extend_from_slice(&[0x0A, 0x01, 0x15, 0x05, 0x0A, 0x00]);
+        expected.extend_from_slice(&[0x03, 0x96, 0x00, 0xEC, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x04]);
+        assert_eq!(p, expected);
+
+        let mut buf = Cursor::new(&p);
+        assert_eq!(MovePath::deserialize(&mut buf).unwrap(), path);
+        assert_eq!(buf.remaining(), 0);
+        for len in 0..p.len() {
+            assert!(MovePath::deserialize(&mut Cursor::new(&p[..len])).is_err(), "len {}", len);
+        }
+        assert_eq!(path.position(), Point::new(150, -20));
+        assert_eq!(path.stance(), Some(STANCE_STAND));
+    }
+
+    #[test]
+    fn test_unknown_command() {
+        let p = [0x00, 0x00, 0x00, 0x00, 0x01, 0x17];
+        let err = MovePath::deserialize(&mut Cursor::new(&p[..])).unwrap_err().locate(p.len());
+        assert!(matches!(err, Error::Protocol { offset: 5, .. }));
+    }
+
+    #[test]
+    fn test_builder() {
+        let path = MovePath::builder(Point::new(0, 100), 7).walk_to(-250).build();
+        assert_eq!(path.position(), Point::new(-250, 100));
+        assert_eq!(path.stance(), Some(STANCE_STAND + 1));
+        match &path.movements[0] {
+            Movement::Absolute(0, walk) => {
+                assert_eq!(walk.velocity, Point::new(-125, 0));
+                assert_eq!(walk.stance, STANCE_WALK + 1);
+                assert_eq!((walk.foothold, walk.duration), (7, 2000));
+            }
+            other => panic!("{:?}", other),
+        }
+
+        // Long walks are split, jumps leave the foothold until they land.
+        let path = MovePath::builder(Point::new(0, 100), 7).walk_to(4500).jump_to(Point::new(4600, 40), 9).build();
+        let positions = path.movements.iter().filter_map(Movement::position).map(|p| p.x).collect::<Vec<_>>();
+        assert_eq!(positions, [2000, 4000, 4500, 4500, 4600, 4600]);
+        match &path.movements[3] {
+            Movement::Absolute(_, jump) => {
+                assert_eq!((jump.velocity.y, jump.foothold, jump.duration), (-555, 0, 800));
+                assert_eq!(jump.stance, STANCE_JUMP);
+            }
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(path.position(), Point::new(4600, 40));
+        assert_eq!(path.stance(), Some(STANCE_STAND));
+        assert_eq!(MovePath::deserialize(&mut Cursor::new(path.serialize())).unwrap(), path);
+    }
+
+    #[test]
+    fn test_builder_long_walk() {
+        let path = MovePath::builder(Point::new(-30000, 0), 1).walk_to(30000).build();
+        assert_eq!(path.movements.len(), 31);
+        for (i, movement) in path.movements[..30].iter().enumerate() {
+            match movement {
+                Movement::Absolute(_, walk) => {
+                    assert_eq!(i32::from(walk.position.x), -28000 + 2000 * i as i32);
+                    assert_eq!((walk.velocity.x, walk.stance, walk.duration), (125, STANCE_WALK, 16000));
+                }
+                other => panic!("{:?}", other),
+            }
+        }
+        assert_eq!(path.position(), Point::new(30000, 0));
+
+        let path = MovePath::builder(Point::new(30000, 0), 1).jump_to(Point::new(-30000, 0), 2).build();
+        match &path.movements[0] {
+            Movement::Absolute(_, jump) => {
+                assert_eq!((jump.velocity.x, jump.duration), (-125, i16::MAX));
+                assert_eq!(jump.stance, STANCE_JUMP + 1);
+            }
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(path.position(), Point::new(-30000, 0));
+    }
+}
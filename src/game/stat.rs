@@ -0,0 +1,350 @@
+use crate::io::{BufRead, BufWrite, Decode, Encode, Error, Result};
+
+use super::{CharacterData, CharacterStat};
+
+bitflags! {
+    /// Which `Stat`s a stat change carries, their values following in the order of these bits.
+    pub struct StatMask: u32 {
+        const SKIN = 0x1;
+        const FACE = 0x2;
+        const HAIR = 0x4;
+        const PET = 0x8;
+        const LEVEL = 0x10;
+        const JOB = 0x20;
+        const STR = 0x40;
+        const DEX = 0x80;
+        const INT = 0x100;
+        const LUK = 0x200;
+        const HP = 0x400;
+        const MAX_HP = 0x800;
+        const MP = 0x1000;
+        const MAX_MP = 0x2000;
+        const AP = 0x4000;
+        const SP = 0x8000;
+        const EXP = 0x10000;
+        const FAME = 0x20000;
+        const MESO = 0x40000;
+        const PET2 = 0x80000;
+        const PET3 = 0x100000;
+        const GACHA_EXP = 0x200000;
+    }
+}
+
+bitflags! {
+    /// Which `ForcedStat`s a `FORCED_STAT_SET` carries, as for `StatMask`.
+    pub struct ForcedStatMask: u32 {
+        const STR = 0x1;
+        const DEX = 0x2;
+        const INT = 0x4;
+        const LUK = 0x8;
+        const WATK = 0x10;
+        const WDEF = 0x20;
+        const MATK = 0x40;
+        const MDEF = 0x80;
+        const ACC = 0x100;
+        const AVOID = 0x200;
+        const SPEED = 0x400;
+        const JUMP = 0x800;
+        const SPEED_MAX = 0x1000;
+    }
+}
+
+impl Encode for StatMask {
+    fn encode<B: BufWrite>(&self, buf: &mut B) {
+        buf.put_u32_le(self.bits());
+    }
+}
+
+impl Decode for StatMask {
+    fn decode<B: BufRead>(buf: &mut B) -> Result<Self> {
+        let remaining = buf.remaining();
+        let mask = buf.try_get_u32_le()?;
+        StatMask::from_bits(mask)
+            .ok_or_else(|| Error::protocol(remaining, format!("Unknown stats in mask {:#X}", mask)))
+    }
+}
+
+/// A stat of `CharacterStat` a server can change, meso aside which is in `CharacterData`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stat {
+    Skin,
+    Face,
+    Hair,
+    /// Cash serial numbers of the summoned pets.
+    Pet,
+    Level,
+    Job,
+    Str,
+    Dex,
+    Int,
+    Luk,
+    Hp,
+    MaxHp,
+    Mp,
+    MaxMp,
+    Ap,
+    Sp,
+    Exp,
+    Fame,
+    Meso,
+    Pet2,
+    Pet3,
+    GachaExp,
+}
+
+/// A stat the server sets for a while regardless of the character's own, like during the Aran tutorial.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ForcedStat {
+    Str,
+    Dex,
+    Int,
+    Luk,
+    Watk,
+    Wdef,
+    Matk,
+    Mdef,
+    Acc,
+    Avoid,
+    Speed,
+    Jump,
+    SpeedMax,
+}
+
+/// The stats of a mask in the order their values are sent, with their size on the wire.
+pub(crate) trait MaskedStat: Copy + 'static {
+    const ALL: &'static [Self];
+
+    fn bit(self) -> u32;
+
+    fn width(self) -> usize;
+}
+
+impl MaskedStat for Stat {
+    const ALL: &'static [Self] = &[
+        Stat::Skin,
+        Stat::Face,
+        Stat::Hair,
+        Stat::Pet,
+        Stat::Level,
+        Stat::Job,
+        Stat::Str,
+        Stat::Dex,
+        Stat::Int,
+        Stat::Luk,
+        Stat::Hp,
+        Stat::MaxHp,
+        Stat::Mp,
+        Stat::MaxMp,
+        Stat::Ap,
+        Stat::Sp,
+        Stat::Exp,
+        Stat::Fame,
+        Stat::Meso,
+        Stat::Pet2,
+        Stat::Pet3,
+        Stat::GachaExp,
+    ];
+
+    fn bit(self) -> u32 {
+        self.mask().bits()
+    }
+
+    fn width(self) -> usize {
+        match self {
+            Stat::Skin | Stat::Level => 1,
+            Stat::Face | Stat::Hair | Stat::Exp | Stat::Meso | Stat::GachaExp => 4,
+            Stat::Pet | Stat::Pet2 | Stat::Pet3 => 8,
+            _ => 2,
+        }
+    }
+}
+
+impl MaskedStat for ForcedStat {
+    const ALL: &'static [Self] = &[
+        ForcedStat::Str,
+        ForcedStat::Dex,
+        ForcedStat::Int,
+        ForcedStat::Luk,
+        ForcedStat::Watk,
+        ForcedStat::Wdef,
+        ForcedStat::Matk,
+        ForcedStat::Mdef,
+        ForcedStat::Acc,
+        ForcedStat::Avoid,
+        ForcedStat::Speed,
+        ForcedStat::Jump,
+        ForcedStat::SpeedMax,
+    ];
+
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+
+    fn width(self) -> usize {
+        match self {
+            ForcedStat::Speed | ForcedStat::Jump | ForcedStat::SpeedMax => 1,
+            _ => 2,
+        }
+    }
+}
+
+impl Stat {
+    pub fn mask(self) -> StatMask {
+        StatMask::from_bits_truncate(1 << self as u32)
+    }
+}
+
+impl ForcedStat {
+    pub fn mask(self) -> ForcedStatMask {
+        ForcedStatMask::from_bits_truncate(self.bit())
+    }
+}
+
+/// Writes the mask of `stats` then their values in mask order, whatever order they are given in.
+pub(crate) fn encode_stats<S: MaskedStat + PartialEq, B: BufWrite>(stats: &[(S, i64)], buf: &mut B) {
+    buf.put_u32_le(stats.iter().fold(0, |mask, (stat, _)| mask | stat.bit()));
+    for stat in S::ALL {
+        if let Some((_, value)) = stats.iter().find(|(s, _)| s == stat) {
+            match stat.width() {
+                1 => buf.put_u8(*value as u8),
+                2 => buf.put_i16_le(*value as i16),
+                4 => buf.put_i32_le(*value as i32),
+                _ => buf.put_i64_le(*value),
+            }
+        }
+    }
+}
+
+/// Reads a mask and the values it announces, refusing bits of unknown stats whose size isn't known.
+pub(crate) fn decode_stats<S: MaskedStat, B: BufRead>(buf: &mut B) -> Result<Vec<(S, i64)>> {
+    let remaining = buf.remaining();
+    let mask = buf.try_get_u32_le()?;
+    let known = S::ALL.iter().fold(0, |known, stat| known | stat.bit());
+    if mask & !known != 0 {
+        return Err(Error::protocol(remaining, format!("Unknown stats in mask {:#X}", mask)));
+    }
+    let mut stats = Vec::new();
+    for stat in S::ALL.iter().filter(|stat| mask & stat.bit() != 0) {
+        let value = match stat.width() {
+            1 => buf.try_get_u8()? as i64,
+            2 => buf.try_get_i16_le()? as i64,
+            4 => buf.try_get_i32_le()? as i64,
+            _ => buf.try_get_i64_le()?,
+        };
+        stats.push((*stat, value));
+    }
+    Ok(stats)
+}
+
+impl CharacterStat {
+    /// Sets `stat` to `value`, `false` for `Stat::Meso` which is kept in `CharacterData`.
+    pub fn apply(&mut self, stat: Stat, value: i64) -> bool {
+        match stat {
+            Stat::Skin => self.skin = value as u8,
+            Stat::Face => self.face = value as i32,
+            Stat::Hair => self.hair = value as i32,
+            Stat::Pet => self.pets[0] = value as u64,
+            Stat::Level => self.level = value as u8,
+            Stat::Job => self.job = value as i16,
+            Stat::Str => self.str = value as i16,
+            Stat::Dex => self.dex = value as i16,
+            Stat::Int => self.int = value as i16,
+            Stat::Luk => self.luk = value as i16,
+            Stat::Hp => self.hp = value as i16,
+            Stat::MaxHp => self.max_hp = value as i16,
+            Stat::Mp => self.mp = value as i16,
+            Stat::MaxMp => self.max_mp = value as i16,
+            Stat::Ap => self.ap = value as i16,
+            Stat::Sp => self.sp = value as i16,
+            Stat::Exp => self.exp = value as i32,
+            Stat::Fame => self.fame = value as i16,
+            Stat::Meso => return false,
+            Stat::Pet2 => self.pets[1] = value as u64,
+            Stat::Pet3 => self.pets[2] = value as u64,
+            Stat::GachaExp => self.gacha_exp = value as i32,
+        }
+        true
+    }
+}
+
+impl CharacterData {
+    /// Applies stat changes, the meso included.
+    pub fn apply_stats(&mut self, stats: &[(Stat, i64)]) {
+        for &(stat, value) in stats {
+            if !self.stat.apply(stat, value) {
+                self.meso = value as i32;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_stat {
+    use std::io::Cursor;
+
+    use crate::game::test_character_data::character_data;
+    use crate::io::{BufRead, Error};
+
+    use super::{decode_stats, encode_stats, ForcedStat, ForcedStatMask, MaskedStat, Stat, StatMask};
+
+    #[test]
+    fn test_masks() {
+        for stat in Stat::ALL {
+            assert_eq!(stat.mask().bits().count_ones(), 1, "{:?}", stat);
+        }
+        assert_eq!(Stat::ALL.iter().fold(StatMask::empty(), |mask, stat| mask | stat.mask()), StatMask::all());
+        assert_eq!(Stat::MaxHp.mask(), StatMask::MAX_HP);
+        assert_eq!(Stat::GachaExp.mask(), StatMask::GACHA_EXP);
+        assert_eq!(ForcedStat::SpeedMax.mask(), ForcedStatMask::SPEED_MAX);
+    }
+
+    #[test]
+    fn test_mask_order() {
+        // Given out of order, values are still sent by mask bit.
+        let stats = [(Stat::Meso, 1000), (Stat::Level, 11), (Stat::Hp, 300)];
+        let mut p = Vec::new();
+        encode_stats(&stats, &mut p);
+        let expected = [0x10, 0x04, 0x04, 0x00, 0x0B, 0x2C, 0x01, 0xE8, 0x03, 0x00, 0x00];
+        assert_eq!(p, expected);
+
+        let mut buf = Cursor::new(&p);
+        let decoded = decode_stats::<Stat, _>(&mut buf).unwrap();
+        assert_eq!(decoded, [(Stat::Level, 11), (Stat::Hp, 300), (Stat::Meso, 1000)]);
+        assert_eq!(buf.remaining(), 0);
+        for len in 0..p.len() {
+            assert!(decode_stats::<Stat, _>(&mut Cursor::new(&p[..len])).is_err(), "len {}", len);
+        }
+    }
+
+    #[test]
+    fn test_forced_stats() {
+        let p = [
+            0x1F, 0x0F, 0x00, 0x00, 0xE7, 0x03, 0xE7, 0x03, 0xE7, 0x03, 0xE7, 0x03, 0xFF, 0x00, 0xE7, 0x03, 0xE7, 0x03,
+            0x78, 0x8C,
+        ];
+        let stats = decode_stats::<ForcedStat, _>(&mut Cursor::new(&p[..])).unwrap();
+        assert_eq!(stats.len(), 9);
+        assert_eq!(stats[4], (ForcedStat::Watk, 255));
+        assert_eq!(stats[8], (ForcedStat::Jump, 140));
+        let mut encoded = Vec::new();
+        encode_stats(&stats, &mut encoded);
+        assert_eq!(encoded, p);
+    }
+
+    #[test]
+    fn test_unknown_bits() {
+        let err = decode_stats::<Stat, _>(&mut Cursor::new(&[0x00, 0x00, 0x40, 0x00][..])).unwrap_err().locate(4);
+        assert!(matches!(err, Error::Protocol { offset: 0, .. }));
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut data = character_data();
+        data.apply_stats(&[(Stat::Level, 11), (Stat::Exp, 0), (Stat::Meso, 1000), (Stat::Pet3, 9)]);
+        assert_eq!(data.stat.level, 11);
+        assert_eq!(data.stat.exp, 0);
+        assert_eq!(data.stat.pets[2], 9);
+        assert_eq!(data.meso, 1000);
+        assert!(!data.stat.apply(Stat::Meso, 5));
+    }
+}
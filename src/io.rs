@@ -1,46 +1,250 @@
-use bytes::{Buf, BufMut};
+//! Packet serialization: the `InPacket`/`OutPacket` traits and their derives, and the checked
+//! buffer helpers packets are written with.
+
+use bytes::{Buf, BufMut, Bytes};
 use crate::time::SystemTime;
 
+pub use oblivion_derive::{InPacket, OutPacket};
+
+/// Everything that can go wrong on a connection.
+///
+/// `is_fatal` tells whether the session has to be dropped or only the current packet.
 #[derive(Debug)]
-pub struct Error {
-    message: String,
+pub enum Error {
+    /// The socket failed or the connection was closed.
+    Io(std::io::Error),
+    /// The server hello was malformed, not the expected version, or did not arrive in time.
+    Handshake(String),
+    /// A packet header does not match the cipher state, the stream cannot be trusted anymore.
+    HeaderMismatch,
+    /// A packet ended before all of its fields were read, reading `wanted` bytes at `offset`.
+    ///
+    /// A decoder only sees the bytes left in its buffer, it stores their count in `offset` until `Error::locate`
+    /// turns it into the offset in the packet.
+    PacketTooShort { offset: usize, wanted: usize },
+    /// The opcode is unknown to the crate or to the version profile in use.
+    UnknownOpcode(u16),
+    /// A packet was read but its content does not make sense, from `offset` on.
+    ///
+    /// Decoders raise it with `Error::protocol`, the handler of the packet then fills in the opcode and offset.
+    Protocol {
+        opcode: u16,
+        offset: usize,
+        message: String,
+    },
+    /// A version profile or configuration file could not be loaded.
+    Config(String),
+    /// A client request was made in a state that does not allow it, nothing was sent.
+    State(String),
+}
+
+impl Error {
+    /// Whether the connection is unusable after this error.
+    ///
+    /// Non-fatal errors only concern a single packet, which can be skipped.
+    pub fn is_fatal(&self) -> bool {
+        match self {
+            Error::Io(_) | Error::Handshake(_) | Error::HeaderMismatch | Error::Config(_) => true,
+            Error::PacketTooShort { .. } | Error::UnknownOpcode(_) | Error::Protocol { .. } | Error::State(_) => false,
+        }
+    }
+
+    /// A `Protocol` error raised by a decoder with `remaining` bytes left, its opcode is set by `Error::in_opcode`.
+    pub fn protocol(remaining: usize, message: String) -> Self {
+        Error::Protocol {
+            opcode: 0,
+            offset: remaining,
+            message,
+        }
+    }
+
+    /// Turns the bytes left recorded by a decoder into the offset in the packet of `len` bytes it was reading.
+    pub fn locate(self, len: usize) -> Self {
+        match self {
+            Error::PacketTooShort { offset, wanted } => Error::PacketTooShort {
+                offset: len.saturating_sub(offset),
+                wanted,
+            },
+            Error::Protocol { opcode, offset, message } => Error::Protocol {
+                opcode,
+                offset: len.saturating_sub(offset),
+                message,
+            },
+            e => e,
+        }
+    }
+
+    /// Names the opcode of the packet a `Protocol` error was raised in.
+    pub fn in_opcode(self, opcode: u16) -> Self {
+        match self {
+            Error::Protocol { offset, message, .. } => Error::Protocol { opcode, offset, message },
+            e => e,
+        }
+    }
 }
 
-impl<E: ToString> From<E> for Error {
-    fn from(e: E) -> Self {
-        Self {
-            message: e.to_string(),
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+            Error::Handshake(msg) => write!(f, "Handshake failed: {}", msg),
+            Error::HeaderMismatch => write!(f, "Packet header does not match the cipher state"),
+            Error::PacketTooShort { offset, wanted } => {
+                write!(f, "unexpected end of packet at offset {}, wanted {} bytes", offset, wanted)
+            }
+            Error::UnknownOpcode(opcode) => write!(f, "Unknown opcode {:#06X}", opcode),
+            Error::Protocol { opcode, offset, message } => {
+                write!(f, "Protocol error in opcode {:#06X} at offset {}: {}", opcode, offset, message)
+            }
+            Error::Config(msg) => write!(f, "Invalid configuration: {}", msg),
+            Error::State(msg) => write!(f, "Invalid state: {}", msg),
         }
     }
 }
 
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(e: toml::de::Error) -> Self {
+        Error::Config(e.to_string())
+    }
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Reading helpers for incoming packets, implemented for every `Buf`.
+///
+/// Unlike the `get_*` getters of `Buf`, the `try_get_*` ones return an error instead of panicking when the
+/// packet is shorter than expected, see `Error::PacketTooShort`. Integers are little-endian.
+pub trait BufRead: Sized {
+    /// Number of bytes left to read, which locates errors in the packet.
+    fn remaining(&self) -> usize;
+
+    /// The next byte without consuming it, `None` at the end of the packet.
+    fn peek_u8(&self) -> Option<u8>;
+
+    fn try_get_u8(&mut self) -> Result<u8>;
+
+    fn try_get_i8(&mut self) -> Result<i8>;
 
-pub trait BufRead: Buf + Sized {
-    fn get_ascii_string(&mut self) -> String {
-        let str_len = self.get_u16_le() as usize;
-        self.get_ascii_string_fixed(str_len)
+    fn try_get_u16_le(&mut self) -> Result<u16>;
+
+    fn try_get_i16_le(&mut self) -> Result<i16>;
+
+    fn try_get_u32_le(&mut self) -> Result<u32>;
+
+    fn try_get_i32_le(&mut self) -> Result<i32>;
+
+    fn try_get_u64_le(&mut self) -> Result<u64>;
+
+    fn try_get_i64_le(&mut self) -> Result<i64>;
+
+    fn try_copy_to_slice(&mut self, dst: &mut [u8]) -> Result<()>;
+
+    fn try_get_bytes(&mut self, n: usize) -> Result<Bytes>;
+
+    fn try_get_bool(&mut self) -> Result<bool> {
+        Ok(self.try_get_u8()? != 0)
     }
 
-    fn get_ascii_string_fixed(&mut self, n: usize) -> String {
-        if n == 0 {
-            String::new()
-        } else {
-            self.copy_to_bytes(n)
-                .into_iter()
-                .map(|b| std::char::from_u32(b as u32).unwrap_or('?'))
-                .collect::<String>()
-        }
+    fn try_get_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut array = [0u8; N];
+        self.try_copy_to_slice(&mut array)?;
+        Ok(array)
+    }
+
+    fn try_get_ascii_string(&mut self) -> Result<String> {
+        let str_len = self.try_get_u16_le()? as usize;
+        self.try_get_ascii_string_fixed(str_len)
     }
 
-    fn get_time(&mut self) -> SystemTime {
-        SystemTime::deserialize(self).unwrap()
+    fn try_get_ascii_string_fixed(&mut self, n: usize) -> Result<String> {
+        Ok(self.try_get_bytes(n)?
+            .into_iter()
+            .map(|b| std::char::from_u32(b as u32).unwrap_or('?'))
+            .collect::<String>())
+    }
+
+    /// Reads an ascii string stored in a field of `n` bytes, dropping the NUL padding.
+    fn try_get_ascii_string_padded(&mut self, n: usize) -> Result<String> {
+        let mut s = self.try_get_ascii_string_fixed(n)?;
+        s.truncate(s.trim_end_matches('\0').len());
+        Ok(s)
+    }
+
+    fn try_get_time(&mut self) -> Result<SystemTime> {
+        SystemTime::deserialize(self)
+    }
+}
+
+fn ensure_remaining<B: Buf>(buf: &B, wanted: usize) -> Result<()> {
+    if buf.remaining() < wanted {
+        Err(Error::PacketTooShort {
+            offset: buf.remaining(),
+            wanted,
+        })
+    } else {
+        Ok(())
     }
 }
 
-impl<T: Buf + Sized> BufRead for T {}
+macro_rules! impl_checked_getters {
+    ($($try_get:ident => $get:ident, $ty:ty;)*) => {
+        $(
+            fn $try_get(&mut self) -> Result<$ty> {
+                ensure_remaining(self, std::mem::size_of::<$ty>())?;
+                Ok(self.$get())
+            }
+        )*
+    };
+}
+
+impl<T: Buf> BufRead for T {
+    fn remaining(&self) -> usize {
+        Buf::remaining(self)
+    }
+
+    fn peek_u8(&self) -> Option<u8> {
+        self.chunk().first().copied()
+    }
+
+    impl_checked_getters! {
+        try_get_u8 => get_u8, u8;
+        try_get_i8 => get_i8, i8;
+        try_get_u16_le => get_u16_le, u16;
+        try_get_i16_le => get_i16_le, i16;
+        try_get_u32_le => get_u32_le, u32;
+        try_get_i32_le => get_i32_le, i32;
+        try_get_u64_le => get_u64_le, u64;
+        try_get_i64_le => get_i64_le, i64;
+    }
+
+    fn try_copy_to_slice(&mut self, dst: &mut [u8]) -> Result<()> {
+        ensure_remaining(self, dst.len())?;
+        self.copy_to_slice(dst);
+        Ok(())
+    }
 
+    fn try_get_bytes(&mut self, n: usize) -> Result<Bytes> {
+        ensure_remaining(self, n)?;
+        Ok(self.copy_to_bytes(n))
+    }
+}
+
+/// Writing helpers for outgoing packets, on top of the `put_*` methods of `BufMut`.
 pub trait BufWrite: BufMut {
     fn put_ascii_string_with_length(&mut self, s: &str) {
         self.put_u16_le(s.len() as u16);
@@ -51,6 +255,13 @@ pub trait BufWrite: BufMut {
         self.put_slice(s.as_bytes());
     }
 
+    /// Writes `s` into a field of exactly `n` bytes, padded with NULs or cut short.
+    fn put_ascii_string_fixed(&mut self, s: &str, n: usize) {
+        let s = &s.as_bytes()[..s.len().min(n)];
+        self.put_slice(s);
+        self.put_bytes(0, n - s.len());
+    }
+
     fn put_time_now(&mut self) {
         self.put_slice(SystemTime::now().serialize().as_slice())
     }
@@ -62,10 +273,193 @@ pub trait BufWrite: BufMut {
 
 impl<T: BufMut> BufWrite for T {}
 
+/// A packet that can be sent, starting with its opcode.
 pub trait OutPacket {
     fn serialize(&self) -> Vec<u8>;
 }
 
+/// A packet that can be received, read from right after its opcode.
 pub trait InPacket: Sized {
     fn deserialize<B: BufRead>(buf: &mut B) -> Result<Self>;
 }
+
+/// Opcode of an incoming packet type, in the crate's numbering.
+pub trait Opcode {
+    const OPCODE: u16;
+}
+
+/// A value that can be written as a field of a packet, see `#[derive(OutPacket)]`.
+pub trait Encode {
+    fn encode<B: BufWrite>(&self, buf: &mut B);
+}
+
+/// A value that can be read as a field of a packet, see `#[derive(InPacket)]`.
+pub trait Decode: Sized {
+    fn decode<B: BufRead>(buf: &mut B) -> Result<Self>;
+}
+
+macro_rules! impl_integer_fields {
+    ($($ty:ty => $put:ident, $read:ident;)*) => {
+        $(
+            impl Encode for $ty {
+                fn encode<B: BufWrite>(&self, buf: &mut B) {
+                    buf.$put(*self);
+                }
+            }
+
+            impl Decode for $ty {
+                fn decode<B: BufRead>(buf: &mut B) -> Result<Self> {
+                    buf.$read()
+                }
+            }
+        )*
+    };
+}
+
+impl_integer_fields! {
+    u8 => put_u8, try_get_u8;
+    i8 => put_i8, try_get_i8;
+    u16 => put_u16_le, try_get_u16_le;
+    i16 => put_i16_le, try_get_i16_le;
+    u32 => put_u32_le, try_get_u32_le;
+    i32 => put_i32_le, try_get_i32_le;
+    u64 => put_u64_le, try_get_u64_le;
+    i64 => put_i64_le, try_get_i64_le;
+}
+
+impl Encode for bool {
+    fn encode<B: BufWrite>(&self, buf: &mut B) {
+        buf.put_u8(*self as u8);
+    }
+}
+
+impl Decode for bool {
+    fn decode<B: BufRead>(buf: &mut B) -> Result<Self> {
+        buf.try_get_bool()
+    }
+}
+
+impl<T: Encode, const N: usize> Encode for [T; N] {
+    fn encode<B: BufWrite>(&self, buf: &mut B) {
+        for item in self {
+            item.encode(buf);
+        }
+    }
+}
+
+impl<T: Decode + Default + Copy, const N: usize> Decode for [T; N] {
+    fn decode<B: BufRead>(buf: &mut B) -> Result<Self> {
+        let mut array = [T::default(); N];
+        for item in array.iter_mut() {
+            *item = T::decode(buf)?;
+        }
+        Ok(array)
+    }
+}
+
+#[cfg(test)]
+mod test_io {
+    use std::io::Cursor;
+
+    use bytes::Bytes;
+
+    use super::{BufRead, BufWrite, Decode, Error, InPacket, OutPacket};
+
+    #[test]
+    fn test_read() {
+        let mut p = Vec::new();
+        p.push(0x7F);
+        p.extend_from_slice(&0x1234u16.to_le_bytes());
+        p.extend_from_slice(&(-2i32).to_le_bytes());
+        p.put_ascii_string_with_length("admin");
+        p.extend_from_slice(&[1, 2, 3, 4]);
+
+        let mut buf = Cursor::new(p);
+        assert_eq!(buf.try_get_u8().unwrap(), 0x7F);
+        assert_eq!(buf.try_get_u16_le().unwrap(), 0x1234);
+        assert_eq!(buf.try_get_i32_le().unwrap(), -2);
+        assert_eq!(buf.try_get_ascii_string().unwrap(), "admin");
+        assert_eq!(buf.try_get_array::<4>().unwrap(), [1, 2, 3, 4]);
+        assert!(buf.try_get_u8().is_err());
+    }
+
+    #[test]
+    fn test_short_packet_error() {
+        let mut buf = Cursor::new(vec![0x01, 0x02, 0x03]);
+        buf.try_get_u16_le().unwrap();
+
+        let err = buf.try_get_u32_le().unwrap_err();
+        assert!(matches!(err, Error::PacketTooShort { offset: 1, wanted: 4 }));
+        let err = err.locate(3);
+        assert_eq!(err.to_string(), "unexpected end of packet at offset 2, wanted 4 bytes");
+        assert!(!err.is_fatal());
+        assert_eq!(buf.remaining(), 1);
+    }
+
+    #[test]
+    fn test_any_buf() {
+        let mut bytes = Bytes::from_static(&[0x34, 0x12, 0xFF]);
+        assert_eq!(bytes.try_get_u16_le().unwrap(), 0x1234);
+        assert_eq!(bytes.peek_u8(), Some(0xFF));
+        let mut slice = &[0x01, 0x02][..];
+        let err = slice.try_get_i32_le().unwrap_err().locate(2);
+        assert!(matches!(err, Error::PacketTooShort { offset: 0, wanted: 4 }));
+        assert_eq!(slice.try_get_array::<2>().unwrap(), [0x01, 0x02]);
+    }
+
+    #[test]
+    fn test_short_string() {
+        let mut buf = Cursor::new(vec![0x05, 0x00, b'a', b'b']);
+        assert!(buf.try_get_ascii_string().is_err());
+    }
+
+    #[derive(Debug, PartialEq, InPacket, OutPacket)]
+    struct Sample {
+        flag: bool,
+        #[packet(ascii = 6)]
+        name: String,
+        #[packet(list = u8)]
+        ids: Vec<u32>,
+        hash: [u8; 2],
+    }
+
+    #[test]
+    fn test_derive_round_trip() {
+        let sample = Sample {
+            flag: true,
+            name: "Ann".to_owned(),
+            ids: vec![7, 0x01020304],
+            hash: [0xAB, 0xCD],
+        };
+        let p = sample.serialize();
+        assert_eq!(
+            p,
+            [0x01, b'A', b'n', b'n', 0, 0, 0, 0x02, 0x07, 0, 0, 0, 0x04, 0x03, 0x02, 0x01, 0xAB, 0xCD]
+        );
+        assert_eq!(Sample::deserialize(&mut Cursor::new(&p)).unwrap(), sample);
+        assert!(Sample::deserialize(&mut Cursor::new(&p[..p.len() - 1])).is_err());
+    }
+
+    #[test]
+    fn test_decode_array() {
+        let p = [0x01, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF];
+        assert_eq!(<[i32; 2]>::decode(&mut &p[..]).unwrap(), [1, -1]);
+        let err = <[i32; 2]>::decode(&mut &p[..6]).unwrap_err().locate(6);
+        assert!(matches!(err, Error::PacketTooShort { offset: 4, wanted: 4 }));
+    }
+
+    #[test]
+    fn test_error_source() {
+        use std::error::Error as _;
+
+        let err = Error::from(std::io::Error::from(std::io::ErrorKind::ConnectionReset));
+        assert!(err.is_fatal());
+        assert!(err.source().unwrap().downcast_ref::<std::io::Error>().is_some());
+
+        let err = Error::protocol(4, "Unknown login result".to_owned()).locate(10).in_opcode(0x00);
+        assert!(matches!(err, Error::Protocol { opcode: 0x00, offset: 6, .. }));
+        assert!(!err.is_fatal());
+        assert!(err.source().is_none());
+        assert_eq!(err.to_string(), "Protocol error in opcode 0x0000 at offset 6: Unknown login result");
+    }
+}
@@ -0,0 +1,32 @@
+//! A MapleStory client library.
+//!
+//! - [`net`] connects to a server ([`net::Session`], [`net::AsyncSession`]), encrypts the traffic
+//!   ([`net::codec`]) and names the opcodes of every supported version ([`net::ClientOp`],
+//!   [`net::ServerOp`], [`net::VersionProfile`]).
+//! - [`net::client_packet`] and [`net::server_packet`] hold the typed packets.
+//! - [`game`] has the data those packets carry, like characters, their looks, stats and items.
+//! - [`client`] drives a [`client::Client`] through the login flow and reports its progress.
+//! - [`io`] has the traits packets are built on, and the `InPacket`/`OutPacket` derives.
+//!
+//! ```no_run
+//! use oblivion::net::{client_packet, ConnectOptions, Session};
+//!
+//! let mut sess = Session::connect_server("127.0.0.1:8484", &ConnectOptions::default())?;
+//! sess.send_packet(client_packet::ClientStart)?;
+//! # Ok::<(), oblivion::io::Error>(())
+//! ```
+
+#[macro_use]
+extern crate bitflags;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate num_derive;
+// Lets the packet derives refer to `::oblivion` from inside this crate too.
+extern crate self as oblivion;
+
+pub mod client;
+pub mod game;
+pub mod io;
+pub mod net;
+pub mod time;
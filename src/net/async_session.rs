@@ -0,0 +1,255 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use futures::{SinkExt, Stream, StreamExt};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio::time::timeout;
+use tokio_util::codec::{Framed, FramedParts};
+
+use crate::io::{Error, OutPacket, Result};
+
+use super::codec::MapleCodec;
+use super::handshake::{ConnectOptions, HandshakeReader};
+use super::profile::{OpcodeTable, VersionProfile};
+use super::session::Proto;
+
+/// Asynchronous counterpart of `Session`, driven by tokio.
+///
+/// Decoded packets are yielded through the `Stream` implementation, each one starting with its opcode.
+pub struct AsyncSession {
+    framed: Framed<TcpStream, MapleCodec>,
+    proto: Proto,
+    outgoing: OpcodeTable,
+    incoming: OpcodeTable,
+}
+
+impl std::fmt::Debug for AsyncSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("AsyncSession")
+            .field("socket", self.framed.get_ref())
+            .field("proto", &self.proto)
+            .finish()
+    }
+}
+
+impl AsyncSession {
+    pub async fn connect_server<A: ToSocketAddrs>(addr: A, options: &ConnectOptions) -> Result<Self> {
+        let mut socket = timeout(options.timeout, TcpStream::connect(addr))
+            .await
+            .map_err(|_| Error::Io(std::io::ErrorKind::TimedOut.into()))??;
+
+        let mut handshake = HandshakeReader::new();
+        let proto = timeout(options.timeout, async {
+            loop {
+                if let Some(proto) = handshake.parse()? {
+                    return Ok(proto);
+                }
+                if socket.read_buf(handshake.buffer_mut()).await? == 0 {
+                    return Err(Error::Handshake("Connection closed during handshake".to_owned()));
+                }
+            }
+        })
+        .await
+        .map_err(|_| Error::Handshake("Timed out waiting for handshake".to_owned()))??;
+        debug!("{:#?}", proto);
+        options.verify(&proto)?;
+
+        let mut parts = FramedParts::new::<Bytes>(socket, MapleCodec::client(&proto));
+        parts.read_buf = handshake.into_remaining();
+        let framed = Framed::from_parts(parts);
+
+        Ok(AsyncSession {
+            framed,
+            proto,
+            outgoing: options.profile.client.clone(),
+            incoming: options.profile.server.clone(),
+        })
+    }
+
+    pub async fn accept_client(mut socket: TcpStream, proto: Proto, profile: &VersionProfile) -> Result<Self> {
+        socket.write_all(proto.remote().serialize().as_ref()).await?;
+        let framed = Framed::new(socket, MapleCodec::server(&proto));
+
+        Ok(AsyncSession {
+            framed,
+            proto,
+            outgoing: profile.server.clone(),
+            incoming: profile.client.clone(),
+        })
+    }
+
+    pub fn proto(&self) -> &Proto {
+        &self.proto
+    }
+
+    pub fn into_framed(self) -> Framed<TcpStream, MapleCodec> {
+        self.framed
+    }
+
+    pub async fn send_packet<P: OutPacket>(&mut self, b: P) -> Result<()> {
+        self.send_serialized(b.serialize()).await
+    }
+
+    pub async fn send<B: AsRef<[u8]>>(&mut self, b: B) -> Result<()> {
+        self.send_serialized(b.as_ref().to_vec()).await
+    }
+
+    async fn send_serialized(&mut self, mut data: Vec<u8>) -> Result<()> {
+        self.outgoing.encode(&mut data)?;
+        self.framed.send(data).await
+    }
+
+    /// Waits for the next packet, returns `None` once the remote side closed the connection.
+    pub async fn recv(&mut self) -> Result<Option<Bytes>> {
+        self.next().await.transpose()
+    }
+}
+
+impl Stream for AsyncSession {
+    type Item = Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match futures::ready!(Pin::new(&mut self.framed).poll_next(cx)) {
+                Some(Ok(packet)) => {
+                    let wire = packet.slice(..2);
+                    match self.incoming.decode(packet) {
+                        Some(packet) => return Poll::Ready(Some(Ok(packet))),
+                        None => warn!(
+                            "Dropping message with opcode {:02X?} unknown to version {}",
+                            wire.as_ref(),
+                            self.proto.version
+                        ),
+                    }
+                }
+                other => return Poll::Ready(other),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_async_session {
+    use bytes::{Buf, BufMut};
+    use futures::{SinkExt, StreamExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_util::codec::Framed;
+
+    use crate::io::OutPacket;
+    use crate::net::codec::{Encoder, MapleCodec};
+    use crate::net::handshake::ConnectOptions;
+    use crate::net::profile::VersionProfile;
+    use crate::net::test_session::proto;
+
+    use super::AsyncSession;
+
+    async fn loopback() -> (AsyncSession, AsyncSession) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let accept = async {
+            let (socket, _) = listener.accept().await.unwrap();
+            AsyncSession::accept_client(socket, proto(), &VersionProfile::v83()).await.unwrap()
+        };
+        let connect = async { AsyncSession::connect_server(addr, &ConnectOptions::default()).await.unwrap() };
+
+        tokio::join!(accept, connect)
+    }
+
+    #[tokio::test]
+    async fn test_trailing_packet_after_hello() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let accept = async {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut wire = proto().remote().serialize();
+            wire.extend(Encoder::server(83, proto().send_iv).encode(&[0x11, 0x00]));
+            tokio::io::AsyncWriteExt::write_all(&mut socket, &wire).await.unwrap();
+            socket
+        };
+        let connect = async { AsyncSession::connect_server(addr, &ConnectOptions::default()).await.unwrap() };
+        let (_socket, mut client) = tokio::join!(accept, connect);
+
+        assert_eq!(client.recv().await.unwrap().unwrap().as_ref(), [0x11, 0x00]);
+    }
+
+    #[tokio::test]
+    async fn test_handshake_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let options = ConnectOptions {
+            timeout: std::time::Duration::from_millis(50),
+            ..Default::default()
+        };
+        let accept = async { listener.accept().await.unwrap() };
+        let connect = AsyncSession::connect_server(addr, &options);
+        let (_socket, result) = tokio::join!(accept, connect);
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_handshake() {
+        let (server, client) = loopback().await;
+        let expected = proto().remote();
+
+        assert_eq!(client.proto().version, expected.version);
+        assert_eq!(client.proto().patch, expected.patch);
+        assert_eq!(client.proto().send_iv, expected.send_iv);
+        assert_eq!(client.proto().recv_iv, expected.recv_iv);
+        assert_eq!(client.proto().locale, expected.locale);
+        assert_eq!(server.proto().send_iv, client.proto().recv_iv);
+    }
+
+    #[tokio::test]
+    async fn test_packets_both_ways() {
+        let (mut server, mut client) = loopback().await;
+
+        for i in 0..64u16 {
+            let mut p = Vec::new();
+            p.put_u16_le(0x11);
+            p.put_u16_le(i);
+            server.send(p).await.unwrap();
+        }
+        for i in 0..64u16 {
+            let mut packet = client.next().await.unwrap().unwrap();
+            assert_eq!(packet.get_u16_le(), 0x11);
+            assert_eq!(packet.get_u16_le(), i);
+        }
+
+        client.send([0x18u8, 0x00]).await.unwrap();
+        assert_eq!(server.recv().await.unwrap().unwrap().as_ref(), [0x18, 0x00]);
+
+        drop(server);
+        assert!(client.recv().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_raw_framed() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let accept = async {
+            let (socket, _) = listener.accept().await.unwrap();
+            AsyncSession::accept_client(socket, proto(), &VersionProfile::v83()).await.unwrap()
+        };
+        let connect = async {
+            let mut socket = TcpStream::connect(addr).await.unwrap();
+            let mut hello = vec![0u8; 2 + 2 + 3 + 4 + 4 + 1];
+            tokio::io::AsyncReadExt::read_exact(&mut socket, &mut hello).await.unwrap();
+            Framed::new(socket, MapleCodec::client(&proto().remote()))
+        };
+        let (server, mut client) = tokio::join!(accept, connect);
+        let mut server = server.into_framed();
+
+        server.send(&[0x00u8, 0x00, 0xAA][..]).await.unwrap();
+        assert_eq!(client.next().await.unwrap().unwrap().as_ref(), [0x00, 0x00, 0xAA]);
+
+        client.send(&[0x01u8, 0x00][..]).await.unwrap();
+        assert_eq!(server.next().await.unwrap().unwrap().as_ref(), [0x01, 0x00]);
+    }
+}
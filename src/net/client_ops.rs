@@ -1,179 +1,207 @@
-pub const CUSTOM_PACKET: u16 = 0x3713; //13 37 lol
+opcodes! {
+    /// Opcodes of the messages sent by the client.
+    pub enum ClientOp {
+        CUSTOM_PACKET = 0x3713, //13 37 lol
 
-pub const LOGIN_PASSWORD: u16 = 0x01;
-pub const GUEST_LOGIN: u16 = 0x02;
-pub const SERVERLIST_REREQUEST: u16 = 0x04;
-pub const CHARLIST_REQUEST: u16 = 0x05;
-pub const SERVERSTATUS_REQUEST: u16 = 0x06;
-pub const ACCEPT_TOS: u16 = 0x07;
-pub const SET_GENDER: u16 = 0x08;
-pub const AFTER_LOGIN: u16 = 0x09;
-pub const REGISTER_PIN: u16 = 0x0A;
-pub const SERVERLIST_REQUEST: u16 = 0x0B;
-pub const PLAYER_DC: u16 = 0x0C;
-pub const VIEW_ALL_CHAR: u16 = 0x0D;
-pub const PICK_ALL_CHAR: u16 = 0x0E;
-pub const NAME_TRANSFER: u16 = 0x10;
-pub const WORLD_TRANSFER: u16 = 0x12;
-pub const CHAR_SELECT: u16 = 0x13;
-pub const PLAYER_LOGGEDIN: u16 = 0x14;
-pub const CHECK_CHAR_NAME: u16 = 0x15;
-pub const CREATE_CHAR: u16 = 0x16;
-pub const DELETE_CHAR: u16 = 0x17;
-pub const PONG: u16 = 0x18;
-pub const CLIENT_START_ERROR: u16 = 0x19;
-pub const CLIENT_ERROR: u16 = 0x1A;
-pub const STRANGE_DATA: u16 = 0x1B;
-pub const RELOG: u16 = 0x1C;
-pub const REGISTER_PIC: u16 = 0x1D;
-pub const CHAR_SELECT_WITH_PIC: u16 = 0x1E;
-pub const VIEW_ALL_PIC_REGISTER: u16 = 0x1F;
-pub const VIEW_ALL_WITH_PIC: u16 = 0x20;
-pub const CLIENT_START: u16 = 0x23;
-pub const CHANGE_MAP: u16 = 0x26;
-pub const CHANGE_CHANNEL: u16 = 0x27;
-pub const ENTER_CASHSHOP: u16 = 0x28;
-pub const MOVE_PLAYER: u16 = 0x29;
-pub const CANCEL_CHAIR: u16 = 0x2A;
-pub const USE_CHAIR: u16 = 0x2B;
-pub const CLOSE_RANGE_ATTACK: u16 = 0x2C;
-pub const RANGED_ATTACK: u16 = 0x2D;
-pub const MAGIC_ATTACK: u16 = 0x2E;
-pub const TOUCH_MONSTER_ATTACK: u16 = 0x2F;
-pub const TAKE_DAMAGE: u16 = 0x30;
-pub const GENERAL_CHAT: u16 = 0x31;
-pub const CLOSE_CHALKBOARD: u16 = 0x32;
-pub const FACE_EXPRESSION: u16 = 0x33;
-pub const USE_ITEMEFFECT: u16 = 0x34;
-pub const USE_DEATHITEM: u16 = 0x35;
-pub const MOB_BANISH_PLAYER: u16 = 0x38;
-pub const MONSTER_BOOK_COVER: u16 = 0x39;
-pub const NPC_TALK: u16 = 0x3A;
-pub const REMOTE_STORE: u16 = 0x3B;
-pub const NPC_TALK_MORE: u16 = 0x3C;
-pub const NPC_SHOP: u16 = 0x3D;
-pub const STORAGE: u16 = 0x3E;
-pub const HIRED_MERCHANT_REQUEST: u16 = 0x3F;
-pub const FREDRICK_ACTION: u16 = 0x40;
-pub const DUEY_ACTION: u16 = 0x41;
-pub const OWL_ACTION: u16 = 0x42;
-//sends most searched info to client
-pub const OWL_WARP: u16 = 0x43;
-//handles player warp to store
-pub const ADMIN_SHOP: u16 = 0x44;
-pub const ITEM_SORT: u16 = 0x45;
-pub const ITEM_SORT2: u16 = 0x46;
-pub const ITEM_MOVE: u16 = 0x47;
-pub const USE_ITEM: u16 = 0x48;
-pub const CANCEL_ITEM_EFFECT: u16 = 0x49;
-pub const USE_SUMMON_BAG: u16 = 0x4B;
-pub const PET_FOOD: u16 = 0x4C;
-pub const USE_MOUNT_FOOD: u16 = 0x4D;
-pub const SCRIPTED_ITEM: u16 = 0x4E;
-pub const USE_CASH_ITEM: u16 = 0x4F;
-//USE_OWL_ITEM:u16 =0x50; ... no idea
-pub const USE_CATCH_ITEM: u16 = 0x51;
-pub const USE_SKILL_BOOK: u16 = 0x52;
-pub const USE_TELEPORT_ROCK: u16 = 0x54;
-pub const USE_RETURN_SCROLL: u16 = 0x55;
-pub const USE_UPGRADE_SCROLL: u16 = 0x56;
-pub const DISTRIBUTE_AP: u16 = 0x57;
-pub const AUTO_DISTRIBUTE_AP: u16 = 0x58;
-pub const HEAL_OVER_TIME: u16 = 0x59;
-pub const DISTRIBUTE_SP: u16 = 0x5A;
-pub const SPECIAL_MOVE: u16 = 0x5B;
-pub const CANCEL_BUFF: u16 = 0x5C;
-pub const SKILL_EFFECT: u16 = 0x5D;
-pub const MESO_DROP: u16 = 0x5E;
-pub const GIVE_FAME: u16 = 0x5F;
-pub const CHAR_INFO_REQUEST: u16 = 0x61;
-pub const SPAWN_PET: u16 = 0x62;
-pub const CANCEL_DEBUFF: u16 = 0x63;
-pub const CHANGE_MAP_SPECIAL: u16 = 0x64;
-pub const USE_INNER_PORTAL: u16 = 0x65;
-pub const TROCK_ADD_MAP: u16 = 0x66;
-pub const REPORT: u16 = 0x6A;
-pub const QUEST_ACTION: u16 = 0x6B;
-//USER_CALC_DAMAGE_STAT_SET_REQUEST:u16 =0x6C;
-pub const GRENADE_EFFECT: u16 = 0x6D;
-pub const SKILL_MACRO: u16 = 0x6E;
-pub const USE_ITEM_REWARD: u16 = 0x70;
-pub const MAKER_SKILL: u16 = 0x71;
-pub const USE_REMOTE: u16 = 0x74;
-pub const WATER_OF_LIFE: u16 = 0x75;
-pub const ADMIN_CHAT: u16 = 0x76;
-pub const MULTI_CHAT: u16 = 0x77;
-pub const WHISPER: u16 = 0x78;
-pub const SPOUSE_CHAT: u16 = 0x79;
-pub const MESSENGER: u16 = 0x7A;
-pub const PLAYER_INTERACTION: u16 = 0x7B;
-pub const PARTY_OPERATION: u16 = 0x7C;
-pub const DENY_PARTY_REQUEST: u16 = 0x7D;
-pub const GUILD_OPERATION: u16 = 0x7E;
-pub const DENY_GUILD_REQUEST: u16 = 0x7F;
-pub const ADMIN_COMMAND: u16 = 0x80;
-pub const ADMIN_LOG: u16 = 0x81;
-pub const BUDDYLIST_MODIFY: u16 = 0x82;
-pub const NOTE_ACTION: u16 = 0x83;
-pub const USE_DOOR: u16 = 0x85;
-pub const CHANGE_KEYMAP: u16 = 0x87;
-pub const RPS_ACTION: u16 = 0x88;
-pub const RING_ACTION: u16 = 0x89;
-pub const WEDDING_ACTION: u16 = 0x8A;
-pub const WEDDING_TALK: u16 = 0x8B;
-pub const WEDDING_TALK_MORE: u16 = 0x8B;
-pub const ALLIANCE_OPERATION: u16 = 0x8F;
-pub const DENY_ALLIANCE_REQUEST: u16 = 0x90;
-pub const OPEN_FAMILY: u16 = 0x92;
-pub const ADD_FAMILY: u16 = 0x93;
-pub const ACCEPT_FAMILY: u16 = 0x96;
-pub const USE_FAMILY: u16 = 0x97;
-pub const BBS_OPERATION: u16 = 0x9B;
-pub const ENTER_MTS: u16 = 0x9C;
-pub const USE_SOLOMON_ITEM: u16 = 0x9D;
-pub const USE_GACHA_EXP: u16 = 0x9E;
-pub const NEW_YEAR_CARD_REQUEST: u16 = 0x9F;
-pub const CASHSHOP_SURPRISE: u16 = 0xA1;
-pub const CLICK_GUIDE: u16 = 0xA2;
-pub const ARAN_COMBO_COUNTER: u16 = 0xA3;
-pub const MOVE_PET: u16 = 0xA7;
-pub const PET_CHAT: u16 = 0xA8;
-pub const PET_COMMAND: u16 = 0xA9;
-pub const PET_LOOT: u16 = 0xAA;
-pub const PET_AUTO_POT: u16 = 0xAB;
-pub const PET_EXCLUDE_ITEMS: u16 = 0xAC;
-pub const MOVE_SUMMON: u16 = 0xAF;
-pub const SUMMON_ATTACK: u16 = 0xB0;
-pub const DAMAGE_SUMMON: u16 = 0xB1;
-pub const BEHOLDER: u16 = 0xB2;
-pub const MOVE_DRAGON: u16 = 0xB5;
-pub const MOVE_LIFE: u16 = 0xBC;
-pub const AUTO_AGGRO: u16 = 0xBD;
-pub const MOB_DAMAGE_MOB_FRIENDLY: u16 = 0xC0;
-pub const MONSTER_BOMB: u16 = 0xC1;
-pub const MOB_DAMAGE_MOB: u16 = 0xC2;
-pub const NPC_ACTION: u16 = 0xC5;
-pub const ITEM_PICKUP: u16 = 0xCA;
-pub const DAMAGE_REACTOR: u16 = 0xCD;
-pub const TOUCHING_REACTOR: u16 = 0xCE;
-pub const PLAYER_MAP_TRANSFER: u16 = 0xCF;
-pub const MAPLETV: u16 = 0xFFFE;
-//Don't know
-pub const SNOWBALL: u16 = 0xD3;
-pub const LEFT_KNOCKBACK: u16 = 0xD4;
-pub const COCONUT: u16 = 0xD5;
-pub const MATCH_TABLE: u16 = 0xD6;
-//Would be cool if I ever get it to work :)
-pub const MONSTER_CARNIVAL: u16 = 0xDA;
-pub const PARTY_SEARCH_REGISTER: u16 = 0xDC;
-pub const PARTY_SEARCH_START: u16 = 0xDE;
-pub const PARTY_SEARCH_UPDATE: u16 = 0xDF;
-pub const CHECK_CASH: u16 = 0xE4;
-pub const CASHSHOP_OPERATION: u16 = 0xE5;
-pub const COUPON_CODE: u16 = 0xE6;
-pub const OPEN_ITEMUI: u16 = 0xEC;
-pub const CLOSE_ITEMUI: u16 = 0xED;
-pub const USE_ITEMUI: u16 = 0xEE;
-pub const MTS_OPERATION: u16 = 0xFD;
-pub const USE_MAPLELIFE: u16 = 0x100;
-pub const USE_HAMMER: u16 = 0x104;
+        LOGIN_PASSWORD = 0x01,
+        GUEST_LOGIN = 0x02,
+        SERVERLIST_REREQUEST = 0x04,
+        CHARLIST_REQUEST = 0x05,
+        SERVERSTATUS_REQUEST = 0x06,
+        ACCEPT_TOS = 0x07,
+        SET_GENDER = 0x08,
+        AFTER_LOGIN = 0x09,
+        REGISTER_PIN = 0x0A,
+        SERVERLIST_REQUEST = 0x0B,
+        PLAYER_DC = 0x0C,
+        VIEW_ALL_CHAR = 0x0D,
+        PICK_ALL_CHAR = 0x0E,
+        NAME_TRANSFER = 0x10,
+        WORLD_TRANSFER = 0x12,
+        CHAR_SELECT = 0x13,
+        PLAYER_LOGGEDIN = 0x14,
+        CHECK_CHAR_NAME = 0x15,
+        CREATE_CHAR = 0x16,
+        DELETE_CHAR = 0x17,
+        PONG = 0x18,
+        CLIENT_START_ERROR = 0x19,
+        CLIENT_ERROR = 0x1A,
+        STRANGE_DATA = 0x1B,
+        RELOG = 0x1C,
+        REGISTER_PIC = 0x1D,
+        CHAR_SELECT_WITH_PIC = 0x1E,
+        VIEW_ALL_PIC_REGISTER = 0x1F,
+        VIEW_ALL_WITH_PIC = 0x20,
+        CLIENT_START = 0x23,
+        CHANGE_MAP = 0x26,
+        CHANGE_CHANNEL = 0x27,
+        ENTER_CASHSHOP = 0x28,
+        MOVE_PLAYER = 0x29,
+        CANCEL_CHAIR = 0x2A,
+        USE_CHAIR = 0x2B,
+        CLOSE_RANGE_ATTACK = 0x2C,
+        RANGED_ATTACK = 0x2D,
+        MAGIC_ATTACK = 0x2E,
+        TOUCH_MONSTER_ATTACK = 0x2F,
+        TAKE_DAMAGE = 0x30,
+        GENERAL_CHAT = 0x31,
+        CLOSE_CHALKBOARD = 0x32,
+        FACE_EXPRESSION = 0x33,
+        USE_ITEMEFFECT = 0x34,
+        USE_DEATHITEM = 0x35,
+        MOB_BANISH_PLAYER = 0x38,
+        MONSTER_BOOK_COVER = 0x39,
+        NPC_TALK = 0x3A,
+        REMOTE_STORE = 0x3B,
+        NPC_TALK_MORE = 0x3C,
+        NPC_SHOP = 0x3D,
+        STORAGE = 0x3E,
+        HIRED_MERCHANT_REQUEST = 0x3F,
+        FREDRICK_ACTION = 0x40,
+        DUEY_ACTION = 0x41,
+        OWL_ACTION = 0x42,
+        //sends most searched info to client
+        OWL_WARP = 0x43,
+        //handles player warp to store
+        ADMIN_SHOP = 0x44,
+        ITEM_SORT = 0x45,
+        ITEM_SORT2 = 0x46,
+        ITEM_MOVE = 0x47,
+        USE_ITEM = 0x48,
+        CANCEL_ITEM_EFFECT = 0x49,
+        USE_SUMMON_BAG = 0x4B,
+        PET_FOOD = 0x4C,
+        USE_MOUNT_FOOD = 0x4D,
+        SCRIPTED_ITEM = 0x4E,
+        USE_CASH_ITEM = 0x4F,
+        //USE_OWL_ITEM:u16 =0x50; ... no idea
+        USE_CATCH_ITEM = 0x51,
+        USE_SKILL_BOOK = 0x52,
+        USE_TELEPORT_ROCK = 0x54,
+        USE_RETURN_SCROLL = 0x55,
+        USE_UPGRADE_SCROLL = 0x56,
+        DISTRIBUTE_AP = 0x57,
+        AUTO_DISTRIBUTE_AP = 0x58,
+        HEAL_OVER_TIME = 0x59,
+        DISTRIBUTE_SP = 0x5A,
+        SPECIAL_MOVE = 0x5B,
+        CANCEL_BUFF = 0x5C,
+        SKILL_EFFECT = 0x5D,
+        MESO_DROP = 0x5E,
+        GIVE_FAME = 0x5F,
+        CHAR_INFO_REQUEST = 0x61,
+        SPAWN_PET = 0x62,
+        CANCEL_DEBUFF = 0x63,
+        CHANGE_MAP_SPECIAL = 0x64,
+        USE_INNER_PORTAL = 0x65,
+        TROCK_ADD_MAP = 0x66,
+        REPORT = 0x6A,
+        QUEST_ACTION = 0x6B,
+        //USER_CALC_DAMAGE_STAT_SET_REQUEST:u16 =0x6C;
+        GRENADE_EFFECT = 0x6D,
+        SKILL_MACRO = 0x6E,
+        USE_ITEM_REWARD = 0x70,
+        MAKER_SKILL = 0x71,
+        USE_REMOTE = 0x74,
+        WATER_OF_LIFE = 0x75,
+        ADMIN_CHAT = 0x76,
+        MULTI_CHAT = 0x77,
+        WHISPER = 0x78,
+        SPOUSE_CHAT = 0x79,
+        MESSENGER = 0x7A,
+        PLAYER_INTERACTION = 0x7B,
+        PARTY_OPERATION = 0x7C,
+        DENY_PARTY_REQUEST = 0x7D,
+        GUILD_OPERATION = 0x7E,
+        DENY_GUILD_REQUEST = 0x7F,
+        ADMIN_COMMAND = 0x80,
+        ADMIN_LOG = 0x81,
+        BUDDYLIST_MODIFY = 0x82,
+        NOTE_ACTION = 0x83,
+        USE_DOOR = 0x85,
+        CHANGE_KEYMAP = 0x87,
+        RPS_ACTION = 0x88,
+        RING_ACTION = 0x89,
+        WEDDING_ACTION = 0x8A,
+        WEDDING_TALK = 0x8B, // WEDDING_TALK_MORE shares this opcode
+        ALLIANCE_OPERATION = 0x8F,
+        DENY_ALLIANCE_REQUEST = 0x90,
+        OPEN_FAMILY = 0x92,
+        ADD_FAMILY = 0x93,
+        ACCEPT_FAMILY = 0x96,
+        USE_FAMILY = 0x97,
+        BBS_OPERATION = 0x9B,
+        ENTER_MTS = 0x9C,
+        USE_SOLOMON_ITEM = 0x9D,
+        USE_GACHA_EXP = 0x9E,
+        NEW_YEAR_CARD_REQUEST = 0x9F,
+        CASHSHOP_SURPRISE = 0xA1,
+        CLICK_GUIDE = 0xA2,
+        ARAN_COMBO_COUNTER = 0xA3,
+        MOVE_PET = 0xA7,
+        PET_CHAT = 0xA8,
+        PET_COMMAND = 0xA9,
+        PET_LOOT = 0xAA,
+        PET_AUTO_POT = 0xAB,
+        PET_EXCLUDE_ITEMS = 0xAC,
+        MOVE_SUMMON = 0xAF,
+        SUMMON_ATTACK = 0xB0,
+        DAMAGE_SUMMON = 0xB1,
+        BEHOLDER = 0xB2,
+        MOVE_DRAGON = 0xB5,
+        MOVE_LIFE = 0xBC,
+        AUTO_AGGRO = 0xBD,
+        MOB_DAMAGE_MOB_FRIENDLY = 0xC0,
+        MONSTER_BOMB = 0xC1,
+        MOB_DAMAGE_MOB = 0xC2,
+        NPC_ACTION = 0xC5,
+        ITEM_PICKUP = 0xCA,
+        DAMAGE_REACTOR = 0xCD,
+        TOUCHING_REACTOR = 0xCE,
+        PLAYER_MAP_TRANSFER = 0xCF,
+        MAPLETV = 0xFFFE,
+        //Don't know
+        SNOWBALL = 0xD3,
+        LEFT_KNOCKBACK = 0xD4,
+        COCONUT = 0xD5,
+        MATCH_TABLE = 0xD6,
+        //Would be cool if I ever get it to work :)
+        MONSTER_CARNIVAL = 0xDA,
+        PARTY_SEARCH_REGISTER = 0xDC,
+        PARTY_SEARCH_START = 0xDE,
+        PARTY_SEARCH_UPDATE = 0xDF,
+        CHECK_CASH = 0xE4,
+        CASHSHOP_OPERATION = 0xE5,
+        COUPON_CODE = 0xE6,
+        OPEN_ITEMUI = 0xEC,
+        CLOSE_ITEMUI = 0xED,
+        USE_ITEMUI = 0xEE,
+        MTS_OPERATION = 0xFD,
+        USE_MAPLELIFE = 0x100,
+        USE_HAMMER = 0x104,
+    }
+}
+
+#[cfg(test)]
+mod test_client_ops {
+    use std::collections::HashSet;
+    use std::convert::TryFrom;
+
+    use super::ClientOp;
+
+    #[test]
+    fn test_round_trip() {
+        for op in ClientOp::ALL {
+            assert_eq!(ClientOp::try_from(u16::from(*op)).unwrap(), *op);
+            assert_eq!(ClientOp::from_name(op.name()), Some(*op));
+        }
+        assert_eq!(ClientOp::try_from(0x01).unwrap().to_string(), "LOGIN_PASSWORD");
+        assert!(ClientOp::try_from(0xFFFF).is_err());
+    }
+
+    #[test]
+    fn test_values_are_unique() {
+        let values = ClientOp::ALL.iter().map(|op| u16::from(*op)).collect::<HashSet<_>>();
+        assert_eq!(values.len(), ClientOp::ALL.len());
+    }
+}
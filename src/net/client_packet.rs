@@ -1,50 +1,579 @@
+//! Packets sent by the client.
+
 use bytes::BufMut;
 
-use crate::io::{BufWrite, Error, OutPacket, Result};
-use crate::net::client_ops;
+use crate::game::{InventoryType, MovePath, StatMask};
+use crate::io::{BufWrite, OutPacket};
+use crate::net::ClientOp;
 
+#[derive(OutPacket)]
+#[opcode(ClientOp::PONG)]
 pub struct Pong;
 
-impl OutPacket for Pong {
+#[derive(OutPacket)]
+#[opcode(ClientOp::CLIENT_START)]
+pub struct ClientStart;
+
+/// Accepts the terms of service after a `LoginError::LicenseAgreement`.
+#[derive(OutPacket)]
+#[opcode(ClientOp::ACCEPT_TOS)]
+pub struct AcceptTos {
+    pub accepted: bool,
+}
+
+/// Picks the gender of an account that has none, 0 male and 1 female.
+#[derive(OutPacket)]
+#[opcode(ClientOp::SET_GENDER)]
+pub struct SetGender {
+    /// `false` cancels, which ends the login.
+    pub confirmed: bool,
+    pub gender: u8,
+}
+
+/// Goes back from the world or character selection to the login.
+#[derive(OutPacket)]
+#[opcode(ClientOp::RELOG)]
+pub struct Relog;
+
+#[derive(OutPacket)]
+#[opcode(ClientOp::SERVERLIST_REQUEST)]
+pub struct ServerListRequest;
+
+/// Asks for the server list again, from the world selection.
+#[derive(OutPacket)]
+#[opcode(ClientOp::SERVERLIST_REREQUEST)]
+pub struct ServerListRerequest;
+
+/// Asks how populated a world is before picking one of its channels.
+#[derive(OutPacket)]
+#[opcode(ClientOp::SERVERSTATUS_REQUEST)]
+pub struct ServerStatusRequest {
+    pub world: u16,
+}
+
+/// Picks a channel, answered with the characters of its world.
+#[derive(OutPacket)]
+#[opcode(ClientOp::CHARLIST_REQUEST)]
+pub struct CharlistRequest {
+    /// Always 2 from the world selection.
+    pub mode: u8,
+    pub world: u8,
+    /// Zero-based.
+    pub channel: u8,
+}
+
+impl CharlistRequest {
+    pub fn new(world: u8, channel: u8) -> Self {
+        Self { mode: 2, world, channel }
+    }
+}
+
+/// Asks for the characters of every world at once.
+#[derive(OutPacket)]
+#[opcode(ClientOp::VIEW_ALL_CHAR)]
+pub struct ViewAllChar;
+
+/// Picks a character from the view-all list, without a PIC.
+#[derive(OutPacket)]
+#[opcode(ClientOp::PICK_ALL_CHAR)]
+pub struct PickAllChar {
+    pub character: i32,
+    pub world: i32,
+    #[packet(string)]
+    pub macs: String,
+    #[packet(string)]
+    pub hwid: String,
+}
+
+/// Asks whether a name is free for a new character.
+#[derive(OutPacket)]
+#[opcode(ClientOp::CHECK_CHAR_NAME)]
+pub struct CheckCharName {
+    #[packet(string)]
+    pub name: String,
+}
+
+/// Creates a character on the world picked with `CharlistRequest`.
+///
+/// The items are the ids of the starter equips offered by the creation screen.
+#[derive(Debug, Clone, OutPacket)]
+#[opcode(ClientOp::CREATE_CHAR)]
+pub struct CreateChar {
+    #[packet(string)]
+    pub name: String,
+    /// 0 Cygnus Knight, 1 adventurer, 2 Aran.
+    pub job: i32,
+    pub face: i32,
+    pub hair: i32,
+    pub hair_color: i32,
+    pub skin: i32,
+    pub top: i32,
+    pub bottom: i32,
+    pub shoes: i32,
+    pub weapon: i32,
+    pub gender: u8,
+}
+
+/// Deletes a character of the account, confirmed with the PIC.
+#[derive(OutPacket)]
+#[opcode(ClientOp::DELETE_CHAR)]
+pub struct DeleteChar {
+    #[packet(string)]
+    pub pic: String,
+    pub character: i32,
+}
+
+/// Answers the PIN prompts following a successful login.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AfterLogin {
+    /// Asks the server whether a PIN is needed.
+    RequestPin,
+    CheckPin(String),
+    /// Checks the current PIN before registering a new one.
+    ChangePin(String),
+    /// Leaves the PIN prompt, which logs out.
+    Cancel,
+}
+
+impl OutPacket for AfterLogin {
     fn serialize(&self) -> Vec<u8> {
         let mut p = Vec::new();
-        p.put_u16_le(client_ops::PONG);
+        p.put_u16_le(ClientOp::AFTER_LOGIN.into());
+        match self {
+            AfterLogin::RequestPin => p.put_slice(&[1, 1]),
+            AfterLogin::CheckPin(pin) => {
+                p.put_slice(&[1, 0]);
+                p.put_ascii_string_with_length(pin);
+            }
+            AfterLogin::ChangePin(pin) => {
+                p.put_slice(&[2, 0]);
+                p.put_ascii_string_with_length(pin);
+            }
+            AfterLogin::Cancel => p.put_u8(0),
+        }
         p
     }
 }
 
-pub struct ClientStart;
+/// Registers a PIN for the account, `None` cancels the registration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegisterPin {
+    pub pin: Option<String>,
+}
+
+impl OutPacket for RegisterPin {
+    fn serialize(&self) -> Vec<u8> {
+        let mut p = Vec::new();
+        p.put_u16_le(ClientOp::REGISTER_PIN.into());
+        match &self.pin {
+            Some(pin) => {
+                p.put_u8(1);
+                p.put_ascii_string_with_length(pin);
+            }
+            None => p.put_u8(0),
+        }
+        p
+    }
+}
+
+/// Picks a character when the server has PICs disabled.
+///
+/// Like the other selections it is answered with `SERVER_IP`. `macs` lists the MAC addresses of the
+/// machine and `hwid` identifies its hardware, see `Account::macs` and `Account::hwid`.
+#[derive(OutPacket)]
+#[opcode(ClientOp::CHAR_SELECT)]
+pub struct CharSelect {
+    pub character: i32,
+    #[packet(string)]
+    pub macs: String,
+    #[packet(string)]
+    pub hwid: String,
+}
+
+/// Picks a character and registers the account's first PIC.
+#[derive(OutPacket)]
+#[opcode(ClientOp::REGISTER_PIC)]
+pub struct RegisterPic {
+    /// Always 1.
+    pub mode: u8,
+    pub character: i32,
+    #[packet(string)]
+    pub macs: String,
+    #[packet(string)]
+    pub hwid: String,
+    #[packet(string)]
+    pub pic: String,
+}
+
+/// Picks a character, confirmed with the PIC.
+#[derive(OutPacket)]
+#[opcode(ClientOp::CHAR_SELECT_WITH_PIC)]
+pub struct CharSelectWithPic {
+    #[packet(string)]
+    pub pic: String,
+    pub character: i32,
+    #[packet(string)]
+    pub macs: String,
+    #[packet(string)]
+    pub hwid: String,
+}
+
+/// `RegisterPic` from the view-all list.
+#[derive(OutPacket)]
+#[opcode(ClientOp::VIEW_ALL_PIC_REGISTER)]
+pub struct ViewAllPicRegister {
+    /// Always 1.
+    pub mode: u8,
+    pub character: i32,
+    pub world: i32,
+    #[packet(string)]
+    pub macs: String,
+    #[packet(string)]
+    pub hwid: String,
+    #[packet(string)]
+    pub pic: String,
+}
+
+/// `CharSelectWithPic` from the view-all list.
+#[derive(OutPacket)]
+#[opcode(ClientOp::VIEW_ALL_WITH_PIC)]
+pub struct ViewAllWithPic {
+    #[packet(string)]
+    pub pic: String,
+    pub character: i32,
+    pub world: i32,
+    #[packet(string)]
+    pub macs: String,
+    #[packet(string)]
+    pub hwid: String,
+}
+
+/// First packet on a channel or cash shop connection, naming the character that migrates in.
+#[derive(OutPacket)]
+#[opcode(ClientOp::PLAYER_LOGGEDIN)]
+pub struct PlayerLoggedin {
+    pub character: i32,
+}
+
+/// Asks to move to another channel of the world, answered with `CHANGE_CHANNEL`.
+#[derive(OutPacket)]
+#[opcode(ClientOp::CHANGE_CHANNEL)]
+pub struct ChangeChannel {
+    /// Zero-based.
+    pub channel: u8,
+    pub tick: i32,
+}
+
+/// Asks to enter the cash shop, answered with `CHANGE_CHANNEL`.
+#[derive(OutPacket)]
+#[opcode(ClientOp::ENTER_CASHSHOP)]
+pub struct EnterCashShop;
 
-impl OutPacket for ClientStart {
+/// Asks to go back to the channel from the cash shop, an empty `CHANGE_MAP`.
+#[derive(OutPacket)]
+#[opcode(ClientOp::CHANGE_MAP)]
+pub struct LeaveCashShop;
+
+/// Moves the character, relayed to the others on the map as `MOVE_PLAYER`.
+#[derive(OutPacket)]
+#[opcode(ClientOp::MOVE_PLAYER)]
+pub struct MovePlayer {
+    /// The `field_key` of the last `SET_FIELD`, moves from before a map change are dropped.
+    pub field_key: u8,
+    pub crc: i32,
+    pub path: MovePath,
+}
+
+/// Moves, equips, unequips or drops an item, answered with `INVENTORY_OPERATION`.
+#[derive(OutPacket)]
+#[opcode(ClientOp::ITEM_MOVE)]
+pub struct ItemMove {
+    pub tick: i32,
+    pub inventory: InventoryType,
+    pub from: i16,
+    /// Negative to equip, 0 to drop.
+    pub to: i16,
+    /// How many of a stack to drop, -1 for anything else.
+    pub quantity: i16,
+}
+
+/// Merges the stacks of an inventory and fills its gaps.
+#[derive(OutPacket)]
+#[opcode(ClientOp::ITEM_SORT)]
+pub struct ItemSort {
+    pub tick: i32,
+    pub inventory: InventoryType,
+}
+
+/// Sorts an inventory by item id.
+#[derive(OutPacket)]
+#[opcode(ClientOp::ITEM_SORT2)]
+pub struct ItemSort2 {
+    pub tick: i32,
+    pub inventory: InventoryType,
+}
+
+/// Spends an AP on one stat, answered with `STAT_CHANGED`.
+#[derive(OutPacket)]
+#[opcode(ClientOp::DISTRIBUTE_AP)]
+pub struct DistributeAp {
+    pub tick: i32,
+    pub stat: StatMask,
+}
+
+/// Spends several AP at once, as the client's auto-assign button does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutoDistributeAp {
+    pub tick: i32,
+    /// How many AP go to each stat.
+    pub stats: Vec<(StatMask, i32)>,
+}
+
+impl OutPacket for AutoDistributeAp {
     fn serialize(&self) -> Vec<u8> {
         let mut p = Vec::new();
-        p.put_u16_le(client_ops::CLIENT_START);
+        p.put_u16_le(ClientOp::AUTO_DISTRIBUTE_AP.into());
+        p.put_i32_le(self.tick);
+        p.put_i32_le(self.stats.len() as i32);
+        for (stat, amount) in &self.stats {
+            p.put_u32_le(stat.bits());
+            p.put_i32_le(*amount);
+        }
         p
     }
 }
 
+/// Spends an SP on a skill, answered with `UPDATE_SKILLS`.
+#[derive(OutPacket)]
+#[opcode(ClientOp::DISTRIBUTE_SP)]
+pub struct DistributeSp {
+    pub tick: i32,
+    pub skill: i32,
+}
+
+/// The HP and MP the character recovered over time, the client sends it every 10 seconds while resting.
+#[derive(OutPacket)]
+#[opcode(ClientOp::HEAL_OVER_TIME)]
+pub struct HealOverTime {
+    pub tick: i32,
+    /// Always `HP | MP`.
+    pub stats: StatMask,
+    pub hp: i16,
+    pub mp: i16,
+}
+
+impl HealOverTime {
+    pub fn new(tick: i32, hp: i16, mp: i16) -> Self {
+        Self {
+            tick,
+            stats: StatMask::HP | StatMask::MP,
+            hp,
+            mp,
+        }
+    }
+}
+
+#[derive(OutPacket)]
+#[opcode(ClientOp::LOGIN_PASSWORD)]
 pub struct LoginPassword {
+    #[packet(string)]
     pub name: String,
+    #[packet(string)]
     pub password: String,
     pub mac1: [u8; 6],
     pub hdd_id: [u8; 4],
-    pub mac2: [u8; 6],
+    pub mac2: [u8; 6],          // crc mac2: 00 00 00 00 67 79
+    pub game_room_client_id: i32,
+    pub client_type: u8,
+    pub reserved: [u8; 6],
 }
 
-impl OutPacket for LoginPassword {
-    fn serialize(&self) -> Vec<u8> {
-        let mut p = Vec::new();
-        p.put_u16_le(client_ops::LOGIN_PASSWORD);
-        p.put_ascii_string_with_length(self.name.as_str());
-        p.put_ascii_string_with_length(self.password.as_str());
-        p.put_slice(&self.mac1);
-        p.put_slice(&self.hdd_id);
-        p.put_slice(&self.mac2);                // crc mac2: 00 00 00 00 67 79
-        p.put_i32_le(0);                         // game room client Id
-        p.put_u8(2);                             // client type
-        p.put_u8(0);                             // reversed
-        p.put_u8(0);                             // reversed
-        p.put_i32_le(0);                         // reversed
-        p
+impl LoginPassword {
+    pub fn new(name: &str, password: &str, mac1: [u8; 6], hdd_id: [u8; 4], mac2: [u8; 6]) -> Self {
+        Self {
+            name: name.to_owned(),
+            password: password.to_owned(),
+            mac1,
+            hdd_id,
+            mac2,
+            game_room_client_id: 0,
+            client_type: 2,
+            reserved: [0; 6],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_client_packet {
+    use crate::game::{InventoryType, MovePath, Point, StatMask};
+    use crate::io::OutPacket;
+
+    use super::{
+        AcceptTos, AfterLogin, AutoDistributeAp, ChangeChannel, CharSelectWithPic, CharlistRequest, ClientStart,
+        CreateChar, DeleteChar, DistributeAp, DistributeSp, EnterCashShop, HealOverTime, ItemMove, ItemSort, ItemSort2,
+        LoginPassword, MovePlayer, PickAllChar, PlayerLoggedin, Pong, RegisterPin, Relog, ServerListRequest,
+        ServerListRerequest, ServerStatusRequest, SetGender, ViewAllChar,
+    };
+
+    #[test]
+    fn test_empty_packets() {
+        assert_eq!(Pong.serialize(), [0x18, 0x00]);
+        assert_eq!(ClientStart.serialize(), [0x23, 0x00]);
+        assert_eq!(ServerListRequest.serialize(), [0x0B, 0x00]);
+        assert_eq!(ServerListRerequest.serialize(), [0x04, 0x00]);
+        assert_eq!(ViewAllChar.serialize(), [0x0D, 0x00]);
+        assert_eq!(EnterCashShop.serialize(), [0x28, 0x00]);
+        assert_eq!(Relog.serialize(), [0x1C, 0x00]);
+    }
+
+    #[test]
+    fn test_new_account() {
+        assert_eq!(AcceptTos { accepted: true }.serialize(), [0x07, 0x00, 0x01]);
+        assert_eq!(SetGender { confirmed: true, gender: 1 }.serialize(), [0x08, 0x00, 0x01, 0x01]);
+    }
+
+    #[test]
+    fn test_migration() {
+        assert_eq!(PlayerLoggedin { character: 7 }.serialize(), [0x14, 0x00, 0x07, 0x00, 0x00, 0x00]);
+        let change = ChangeChannel { channel: 2, tick: 1 };
+        assert_eq!(change.serialize(), [0x27, 0x00, 0x02, 0x01, 0x00, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_items() {
+        let equip = ItemMove {
+            tick: 1,
+            inventory: InventoryType::Equip,
+            from: 3,
+            to: -11,
+            quantity: -1,
+        };
+        assert_eq!(equip.serialize(), [0x47, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x03, 0x00, 0xF5, 0xFF, 0xFF, 0xFF]);
+        let sort = ItemSort {
+            tick: 2,
+            inventory: InventoryType::Use,
+        };
+        assert_eq!(sort.serialize(), [0x45, 0x00, 0x02, 0x00, 0x00, 0x00, 0x02]);
+        let sort = ItemSort2 {
+            tick: 2,
+            inventory: InventoryType::Etc,
+        };
+        assert_eq!(sort.serialize(), [0x46, 0x00, 0x02, 0x00, 0x00, 0x00, 0x04]);
+    }
+
+    #[test]
+    fn test_move_player() {
+        let path = MovePath::builder(Point::new(10, 20), 3).walk_to(30).build();
+        let mut expected = vec![0x29, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00];
+        expected.extend_from_slice(&path.serialize());
+        let moved = MovePlayer { field_key: 2, crc: 0, path };
+        assert_eq!(moved.serialize(), expected);
+    }
+
+    #[test]
+    fn test_stats() {
+        let ap = DistributeAp {
+            tick: 1,
+            stat: StatMask::STR,
+        };
+        assert_eq!(ap.serialize(), [0x57, 0x00, 0x01, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00]);
+        let auto = AutoDistributeAp {
+            tick: 1,
+            stats: vec![(StatMask::DEX, 3), (StatMask::LUK, 2)],
+        };
+        let mut expected = vec![0x58, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00];
+        expected.extend_from_slice(&[0x80, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00]);
+        expected.extend_from_slice(&[0x00, 0x02, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00]);
+        assert_eq!(auto.serialize(), expected);
+        let sp = DistributeSp { tick: 1, skill: 1001004 };
+        assert_eq!(sp.serialize(), [0x5A, 0x00, 0x01, 0x00, 0x00, 0x00, 0x2C, 0x46, 0x0F, 0x00]);
+        let heal = HealOverTime::new(1, 10, 3).serialize();
+        assert_eq!(heal, [0x59, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x14, 0x00, 0x00, 0x0A, 0x00, 0x03, 0x00]);
+    }
+
+    #[test]
+    fn test_character_selection() {
+        assert_eq!(CharlistRequest::new(1, 4).serialize(), [0x05, 0x00, 0x02, 0x01, 0x04]);
+        let pick = PickAllChar {
+            character: 7,
+            world: 1,
+            macs: "AB".to_owned(),
+            hwid: "C".to_owned(),
+        };
+        let expected = [0x0E, 0x00, 7, 0, 0, 0, 1, 0, 0, 0, 2, 0, b'A', b'B', 1, 0, b'C'];
+        assert_eq!(pick.serialize(), expected);
+    }
+
+    #[test]
+    fn test_pin() {
+        assert_eq!(AfterLogin::RequestPin.serialize(), [0x09, 0x00, 0x01, 0x01]);
+        let check = [0x09, 0x00, 0x01, 0x00, 0x04, 0x00, b'1', b'2', b'3', b'4'];
+        assert_eq!(AfterLogin::CheckPin("1234".to_owned()).serialize(), check);
+        assert_eq!(AfterLogin::Cancel.serialize(), [0x09, 0x00, 0x00]);
+
+        let register = [0x0A, 0x00, 0x01, 0x04, 0x00, b'1', b'2', b'3', b'4'];
+        assert_eq!(RegisterPin { pin: Some("1234".to_owned()) }.serialize(), register);
+        assert_eq!(RegisterPin { pin: None }.serialize(), [0x0A, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_pic() {
+        let select = CharSelectWithPic {
+            pic: "pic".to_owned(),
+            character: 7,
+            macs: "m".to_owned(),
+            hwid: "h".to_owned(),
+        };
+        let expected = [0x1E, 0x00, 3, 0, b'p', b'i', b'c', 7, 0, 0, 0, 1, 0, b'm', 1, 0, b'h'];
+        assert_eq!(select.serialize(), expected);
+    }
+
+    #[test]
+    fn test_character_creation() {
+        let create = CreateChar {
+            name: "Bob".to_owned(),
+            job: 1,
+            face: 20000,
+            hair: 30030,
+            hair_color: 0,
+            skin: 0,
+            top: 1040002,
+            bottom: 1060002,
+            shoes: 1072001,
+            weapon: 1302000,
+            gender: 0,
+        };
+        let p = create.serialize();
+        assert_eq!(&p[..9], [0x16, 0x00, 0x03, 0x00, b'B', b'o', b'b', 0x01, 0x00]);
+        assert_eq!(p.len(), 2 + 5 + 4 * 9 + 1);
+        assert_eq!(&p[p.len() - 5..], [0xF0, 0xDD, 0x13, 0x00, 0x00]);
+
+        let delete = DeleteChar {
+            pic: "1234".to_owned(),
+            character: 7,
+        };
+        assert_eq!(delete.serialize(), [0x17, 0x00, 0x04, 0x00, b'1', b'2', b'3', b'4', 0x07, 0x00, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_server_status_request() {
+        assert_eq!(ServerStatusRequest { world: 1 }.serialize(), [0x06, 0x00, 0x01, 0x00]);
+    }
+
+    #[test]
+    fn test_login_password() {
+        let mac1 = [0x00, 0xE1, 0xFF, 0xFF, 0xFF, 0xFF];
+        let hdd_id = [0x00, 0xE7, 0x89, 0x1B];
+        let mac2 = [0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+        let p = LoginPassword::new("admin", "pass", mac1, hdd_id, mac2).serialize();
+
+        let mut expected = vec![0x01, 0x00];
+        expected.extend_from_slice(&[0x05, 0x00, b'a', b'd', b'm', b'i', b'n']);
+        expected.extend_from_slice(&[0x04, 0x00, b'p', b'a', b's', b's']);
+        expected.extend_from_slice(&mac1);
+        expected.extend_from_slice(&hdd_id);
+        expected.extend_from_slice(&mac2);
+        expected.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(p, expected);
     }
 }
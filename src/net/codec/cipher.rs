@@ -40,9 +40,10 @@ pub struct MapleAES {
 }
 
 impl std::fmt::Debug for MapleAES {
+    #[allow(clippy::match_single_binding)]
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            MapleAES { build, iv, aes_ecb } => {
+            MapleAES { build, iv, .. } => {
                 f.debug_struct("MapleAES")
                     .field("build", build)
                     .field("iv", iv)
@@ -59,6 +60,7 @@ impl MapleAES {
         MapleAES { build, iv, aes_ecb }
     }
 
+    #[allow(clippy::needless_range_loop)]
     pub fn apply_key_stream(&mut self, data: &mut [u8]) {
         let mut remaining: usize = data.len();
         let mut llength: usize = 0x5B0;
@@ -108,6 +110,7 @@ impl MapleAES {
         header
     }
 
+    #[allow(clippy::identity_op, clippy::needless_return)]
     pub fn confirm_header(&self, packet: &[u8]) -> bool {
         return ((packet[0] ^ self.iv[2]) & 0xFF) == ((self.build >> 8) & 0xFF) as u8
             && ((packet[1] ^ self.iv[3]) & 0xFF) == (self.build & 0xFF) as u8;
@@ -132,6 +135,7 @@ impl MapleAES {
     }
 }
 
+#[allow(clippy::identity_op)]
 fn shuffle(input: u8, init_iv: &mut [u8; 4]) {
     let mut elina = init_iv[1];
     let anna = input;
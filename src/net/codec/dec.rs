@@ -1,28 +1,34 @@
-use bytes::Bytes;
+use std::io::Read;
+
+use bytes::{Buf, Bytes, BytesMut};
 
 use crate::io::{Error, Result};
 
 use super::cipher::MapleAES;
 use super::shanda;
 
+/// Initial capacity of the receive buffer, packets of this size never reallocate it.
 pub const MAX_PACKET_SIZE: usize = 64 * 1024;
 
+/// Most bytes `read_from` takes from the reader at once.
+const READ_CHUNK_SIZE: usize = 16 * 1024;
+
+/// Splits received bytes into packets and decrypts them.
 pub struct Decoder {
     cipher: MapleAES,
     state: DecodeState,
-    buf: Vec<u8>,
+    buf: BytesMut,
+    /// Where `read_from` reads to, allocated once rather than zero-filled again on every read.
+    scratch: Box<[u8]>,
 }
 
 impl std::fmt::Debug for Decoder {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match self {
-            Decoder { cipher, state, buf } => {
-                f.debug_struct("Decoder")
-                    .field("cipher", cipher)
-                    .field("state", state)
-                    .finish()
-            }
-        }
+        f.debug_struct("Decoder")
+            .field("cipher", &self.cipher)
+            .field("state", &self.state)
+            .field("buffered", &self.buf.len())
+            .finish()
     }
 }
 
@@ -32,14 +38,15 @@ enum DecodeState {
     Body(usize),
 }
 
-impl<'a> Decoder {
+impl Decoder {
     pub fn server(version: u16, remote_iv: [u8; 4]) -> Self {
         let cipher = MapleAES::new(version, remote_iv);
         let state = DecodeState::Header;
         Self {
             cipher,
             state,
-            buf: Vec::with_capacity(MAX_PACKET_SIZE),
+            buf: BytesMut::with_capacity(MAX_PACKET_SIZE),
+            scratch: vec![0; READ_CHUNK_SIZE].into_boxed_slice(),
         }
     }
 
@@ -49,7 +56,8 @@ impl<'a> Decoder {
         Self {
             cipher,
             state,
-            buf: Vec::with_capacity(MAX_PACKET_SIZE),
+            buf: BytesMut::with_capacity(MAX_PACKET_SIZE),
+            scratch: vec![0; READ_CHUNK_SIZE].into_boxed_slice(),
         }
     }
 
@@ -57,74 +65,234 @@ impl<'a> Decoder {
         self.buf.extend_from_slice(data);
     }
 
+    /// Reads once from `reader` into the decoder's buffer.
+    ///
+    /// Returns the number of bytes read, `0` meaning the reader reached end of stream.
+    pub fn read_from<R: Read>(&mut self, reader: &mut R) -> std::io::Result<usize> {
+        let read = reader.read(&mut self.scratch)?;
+        self.buf.extend_from_slice(&self.scratch[..read]);
+        Ok(read)
+    }
+
+    /// Number of received bytes not yet consumed by `decode`.
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Whether the next byte to decode starts a new packet, i.e. no header is waiting for its body.
+    pub fn at_packet_boundary(&self) -> bool {
+        matches!(self.state, DecodeState::Header)
+    }
+
+    /// Decodes the next buffered packet, `None` if it is not complete yet.
     pub fn decode(&mut self) -> Result<Option<Bytes>> {
-        match self.state {
-            DecodeState::Header => match self.decode_header()? {
+        let mut buf = std::mem::take(&mut self.buf);
+        let result = self.decode_from(&mut buf);
+        self.buf = buf;
+        result
+    }
+
+    /// Decodes the next packet out of an external buffer instead of the decoder's own one.
+    ///
+    /// The decrypted body is split off `src` without copying.
+    pub fn decode_from(&mut self, src: &mut BytesMut) -> Result<Option<Bytes>> {
+        if let DecodeState::Header = self.state {
+            match self.decode_header(src)? {
                 Some(body_size) => {
                     //debug!("Header decoded, body size={}", body_size);
                     self.state = DecodeState::Body(body_size);
-                    Ok(None)
                 }
-                None => Ok(None),
-            },
-            DecodeState::Body(body_size) => Ok(self.decode_body(body_size)),
+                None => return Ok(None),
+            }
+        }
+
+        match self.state {
+            DecodeState::Body(body_size) => Ok(self.decode_body(src, body_size)),
+            DecodeState::Header => Ok(None),
         }
     }
 
-    fn decode_header(&mut self) -> Result<Option<usize>> {
-        let body_size = {
-            if self.buf.len() < 4 {
-                return Ok(None);
-            }
+    /// Iterates over every complete packet currently buffered.
+    ///
+    /// A trailing partial packet is kept for the next `append`. The iterator ends after the first error.
+    pub fn packets(&mut self) -> Packets<'_> {
+        Packets { dec: self, failed: false }
+    }
 
-            if !self.cipher.confirm_header(&self.buf) {
-                return decode_failed("Confirm Header failed!");
-            }
+    pub fn decode_all(&mut self) -> Result<Vec<Bytes>> {
+        self.packets().collect()
+    }
 
-            decrypt_body_size(&self.buf)
-        };
+    fn decode_header(&mut self, src: &mut BytesMut) -> Result<Option<usize>> {
+        if src.len() < 4 {
+            return Ok(None);
+        }
 
+        if !self.cipher.confirm_header(src) {
+            return Err(Error::HeaderMismatch);
+        }
+
+        let body_size = decrypt_body_size(src);
         if body_size < 2 {
-            return decode_failed("Body too small");
+            return Err(Error::HeaderMismatch); // Body too small to hold an opcode
         }
 
+        src.advance(4);
         Ok(Some(body_size))
     }
 
-    fn decode_body(&mut self, body_size: usize) -> Option<Bytes> {
-        if self.buf.len() >= body_size {
-            let body = &mut self.buf[4..4 + body_size];
-            self.cipher.apply_key_stream(body);
-            shanda::decrypt(body);
-
-            let mut data = Vec::with_capacity(body_size);
-            data.extend_from_slice(body);
-            let remaining = self.buf.len() - body_size - 4;
-            if remaining > 0 {
-                unsafe {
-                    std::ptr::copy_nonoverlapping(
-                        self.buf.as_ptr().offset(4 + body_size as isize),
-                        self.buf.as_mut_ptr(),
-                        remaining,
-                    );
-                }
-            }
-            self.buf.truncate(remaining);
-            //debug!("Body decoded, remaining={}", remaining);
+    fn decode_body(&mut self, src: &mut BytesMut, body_size: usize) -> Option<Bytes> {
+        if src.len() < body_size {
+            return None; // Need more data
+        }
 
-            self.state = DecodeState::Header;
-            Some(Bytes::from(data))
-        } else {
-            None // Need more data
+        let mut body = src.split_to(body_size);
+        self.cipher.apply_key_stream(&mut body);
+        shanda::decrypt(&mut body);
+        //debug!("Body decoded, remaining={}", src.len());
+
+        self.state = DecodeState::Header;
+        Some(body.freeze())
+    }
+}
+
+/// Iterator returned by `Decoder::packets`.
+pub struct Packets<'a> {
+    dec: &'a mut Decoder,
+    failed: bool,
+}
+
+impl<'a> Iterator for Packets<'a> {
+    type Item = Result<Bytes>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
         }
+
+        let next = self.dec.decode().transpose();
+        self.failed = matches!(next, Some(Err(_)));
+        next
     }
 }
 
 fn decrypt_body_size(data: &[u8]) -> usize {
-    (((data[0] ^ data[2]) & 0xFFu8) as u32
-        | ((((data[1] ^ data[3]) as u16) << 8) & 0xFF00u16) as u32) as usize
+    u16::from_le_bytes([data[0] ^ data[2], data[1] ^ data[3]]) as usize
 }
 
-fn decode_failed<T>(msg: &str) -> Result<T> {
-    Err(Error::from(std::io::Error::new(std::io::ErrorKind::InvalidData, msg)))
+#[cfg(test)]
+mod test_decoder {
+    use bytes::{Bytes, BytesMut};
+
+    use crate::io::Error;
+    use crate::net::codec::Encoder;
+
+    use super::Decoder;
+
+    const IV: [u8; 4] = [0x46, 0x72, 0x7A, 0x21];
+
+    fn packets() -> Vec<Vec<u8>> {
+        vec![
+            vec![0x11, 0x00],
+            vec![0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x02],
+            (0..40u8).collect(),
+            vec![0x0A, 0x00, 0xFF],
+        ]
+    }
+
+    fn encoded() -> Vec<u8> {
+        let mut enc = Encoder::server(83, IV);
+        packets().iter().flat_map(|p| enc.encode(p)).collect()
+    }
+
+    fn feed(chunks: &[&[u8]]) -> Vec<Bytes> {
+        let mut dec = Decoder::client(83, IV);
+        let mut out = Vec::new();
+        for chunk in chunks {
+            dec.append(chunk);
+            out.extend(dec.decode_all().unwrap());
+        }
+        assert_eq!(dec.buffered(), 0);
+        out
+    }
+
+    #[test]
+    fn test_coalesced_packets() {
+        let wire = encoded();
+        assert_eq!(feed(&[&wire]), packets());
+    }
+
+    #[test]
+    fn test_split_at_every_boundary() {
+        let wire = encoded();
+        for at in 0..=wire.len() {
+            let (a, b) = wire.split_at(at);
+            assert_eq!(feed(&[a, b]), packets(), "split at {}", at);
+        }
+    }
+
+    #[test]
+    fn test_split_at_every_pair_of_boundaries() {
+        let wire = encoded();
+        for i in 0..=wire.len() {
+            for j in i..=wire.len() {
+                let chunks = [&wire[..i], &wire[i..j], &wire[j..]];
+                assert_eq!(feed(&chunks), packets(), "split at {} and {}", i, j);
+            }
+        }
+    }
+
+    #[test]
+    fn test_byte_by_byte() {
+        let wire = encoded();
+        let chunks = wire.chunks(1).collect::<Vec<_>>();
+        assert_eq!(feed(&chunks), packets());
+    }
+
+    #[test]
+    fn test_partial_packet_is_kept() {
+        let wire = encoded();
+        let mut dec = Decoder::client(83, IV);
+        dec.append(&wire[..wire.len() - 1]);
+
+        assert_eq!(dec.decode_all().unwrap(), packets()[..3].to_vec());
+        assert!(dec.buffered() > 0);
+
+        dec.append(&wire[wire.len() - 1..]);
+        assert_eq!(dec.decode_all().unwrap(), packets()[3..].to_vec());
+    }
+
+    #[test]
+    fn test_read_from() {
+        let wire = encoded();
+        let mut reader = std::io::Cursor::new(wire.clone());
+        let mut dec = Decoder::client(83, IV);
+
+        while dec.read_from(&mut reader).unwrap() > 0 {}
+        assert_eq!(dec.buffered(), wire.len());
+        assert_eq!(dec.decode_all().unwrap(), packets());
+    }
+
+    #[test]
+    fn test_decode_from_external_buffer() {
+        let mut wire = BytesMut::from(&encoded()[..]);
+        let mut dec = Decoder::client(83, IV);
+
+        let mut out = Vec::new();
+        while let Some(packet) = dec.decode_from(&mut wire).unwrap() {
+            out.push(packet);
+        }
+        assert_eq!(out, packets());
+        assert!(wire.is_empty());
+    }
+
+    #[test]
+    fn test_bad_header_stops_iteration() {
+        let mut dec = Decoder::client(83, IV);
+        dec.append(&[0xDE, 0xAD, 0xBE, 0xEF, 0x00, 0x00]);
+
+        let mut packets = dec.packets();
+        assert!(matches!(packets.next(), Some(Err(Error::HeaderMismatch))));
+        assert!(packets.next().is_none());
+    }
 }
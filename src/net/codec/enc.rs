@@ -1,6 +1,7 @@
 use super::cipher::MapleAES;
 use super::shanda;
 
+/// Encrypts outgoing packets and prefixes them with their header.
 #[derive(Debug)]
 pub struct Encoder {
     cipher: MapleAES,
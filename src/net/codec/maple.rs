@@ -0,0 +1,106 @@
+use bytes::{Bytes, BytesMut};
+use tokio_util::codec;
+
+use crate::io::{Error, Result};
+use crate::net::session::Proto;
+
+use super::{Decoder, Encoder};
+
+/// Frames a byte stream into decrypted MapleStory packets.
+///
+/// Wraps the crate's own `Encoder`/`Decoder` pair so that it can be plugged into
+/// `tokio_util::codec::Framed`.
+#[derive(Debug)]
+pub struct MapleCodec {
+    enc: Encoder,
+    dec: Decoder,
+}
+
+impl MapleCodec {
+    /// Codec for the client side of a connection, built from the handshake sent by the server.
+    pub fn client(proto: &Proto) -> Self {
+        Self {
+            enc: Encoder::client(proto.version, proto.send_iv),
+            dec: Decoder::client(proto.version, proto.recv_iv),
+        }
+    }
+
+    /// Codec for the server side of a connection, built from the server's own view of the handshake.
+    pub fn server(proto: &Proto) -> Self {
+        Self {
+            enc: Encoder::server(proto.version, proto.send_iv),
+            dec: Decoder::server(proto.version, proto.recv_iv),
+        }
+    }
+}
+
+impl codec::Decoder for MapleCodec {
+    type Item = Bytes;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Bytes>> {
+        self.dec.decode_from(src)
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Bytes>> {
+        match self.decode(src)? {
+            Some(packet) => Ok(Some(packet)),
+            None if src.is_empty() && self.dec.at_packet_boundary() => Ok(None),
+            None => Err(Error::from(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "Connection closed in the middle of a packet",
+            ))),
+        }
+    }
+}
+
+impl<B: AsRef<[u8]>> codec::Encoder<B> for MapleCodec {
+    type Error = Error;
+
+    fn encode(&mut self, item: B, dst: &mut BytesMut) -> Result<()> {
+        dst.extend_from_slice(&self.enc.encode(item.as_ref()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_maple_codec {
+    use bytes::BytesMut;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    use crate::net::test_session::proto;
+
+    use super::MapleCodec;
+
+    #[test]
+    fn test_round_trip() {
+        let server_proto = proto();
+        let mut server = MapleCodec::server(&server_proto);
+        let mut client = MapleCodec::client(&server_proto.remote());
+
+        let mut wire = BytesMut::new();
+        server.encode(&[0x11u8, 0x00][..], &mut wire).unwrap();
+        server.encode(&[0x00u8, 0x00, 1, 2, 3, 4, 5][..], &mut wire).unwrap();
+
+        assert_eq!(client.decode(&mut wire).unwrap().unwrap().as_ref(), [0x11, 0x00]);
+        assert_eq!(client.decode(&mut wire).unwrap().unwrap().as_ref(), [0x00, 0x00, 1, 2, 3, 4, 5]);
+        assert!(client.decode(&mut wire).unwrap().is_none());
+
+        client.encode(&[0x18u8, 0x00][..], &mut wire).unwrap();
+        assert_eq!(server.decode(&mut wire).unwrap().unwrap().as_ref(), [0x18, 0x00]);
+    }
+
+    #[test]
+    fn test_truncated_stream() {
+        let server_proto = proto();
+        let mut server = MapleCodec::server(&server_proto);
+        let mut client = MapleCodec::client(&server_proto.remote());
+
+        let mut wire = BytesMut::new();
+        server.encode(&[0x11u8, 0x00, 0xAB][..], &mut wire).unwrap();
+        wire.truncate(wire.len() - 1);
+
+        assert!(client.decode(&mut wire).unwrap().is_none());
+        assert!(client.decode_eof(&mut wire).is_err());
+    }
+}
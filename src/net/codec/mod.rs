@@ -1,7 +1,15 @@
+//! The MapleStory transport encryption: a 4-byte header followed by a body scrambled with
+//! Shanda and AES-OFB, whose IV changes after every packet.
+//!
+//! `cipher` and `shanda` are kept line for line with the reference implementation they were ported from,
+//! masks included, so they allow the clippy lints that style trips.
+
 mod enc;
 mod dec;
 mod cipher;
 mod shanda;
+mod maple;
 
 pub use enc::Encoder;
-pub use dec::{MAX_PACKET_SIZE, Decoder};
+pub use dec::{MAX_PACKET_SIZE, Decoder, Packets};
+pub use maple::MapleCodec;
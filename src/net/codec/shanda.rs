@@ -1,4 +1,5 @@
 #[inline]
+#[allow(clippy::identity_op, clippy::needless_return)]
 fn roll_left(value: u8, count: u32) -> u8 {
     let mut tmp = (value & 0xFF) as u32;
     tmp = tmp.wrapping_shl(count % 8);
@@ -6,12 +7,14 @@ fn roll_left(value: u8, count: u32) -> u8 {
 }
 
 #[inline]
+#[allow(clippy::identity_op, clippy::needless_return)]
 fn roll_right(value: u8, count: u32) -> u8 {
     let mut tmp = (value & 0xFF) as u32;
     tmp = tmp.wrapping_shl(8).wrapping_shr(count % 8);
     return ((tmp & 0xFF) | tmp.wrapping_shr(8)) as u8;
 }
 
+#[allow(clippy::identity_op, clippy::needless_range_loop)]
 pub fn encrypt(data: &mut [u8]) {
     for j in 0..6 {
         let mut remember = 0u8;
@@ -45,6 +48,7 @@ pub fn encrypt(data: &mut [u8]) {
     }
 }
 
+#[allow(clippy::identity_op, clippy::needless_range_loop)]
 pub fn decrypt(data: &mut [u8]) {
     for j in 1..=6 {
         let mut remember = 0u8;
@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::marker::PhantomData;
+
+use bytes::Bytes;
+
+use crate::io::{BufRead, InPacket, Opcode, Result};
+
+use super::session::Session;
+
+/// Handles the packets of one opcode.
+///
+/// `buf` is positioned right after the opcode. Implemented for closures taking the raw buffer,
+/// `Dispatcher::on` registers closures taking a deserialized packet instead. Both locate their decoding
+/// errors in the packet, see `Error::locate`, and the latter names its opcode in `Protocol` errors.
+pub trait Handler<S> {
+    fn handle(&mut self, sess: &mut S, buf: &mut Cursor<Bytes>) -> Result<()>;
+}
+
+impl<S, F> Handler<S> for F
+where
+    F: FnMut(&mut S, &mut Cursor<Bytes>) -> Result<()>,
+{
+    fn handle(&mut self, sess: &mut S, buf: &mut Cursor<Bytes>) -> Result<()> {
+        self(sess, buf).map_err(|e| e.locate(buf.get_ref().len()))
+    }
+}
+
+struct Typed<P, F> {
+    f: F,
+    packet: PhantomData<fn() -> P>,
+}
+
+impl<S, P, F> Handler<S> for Typed<P, F>
+where
+    P: InPacket + Opcode,
+    F: FnMut(&mut S, P) -> Result<()>,
+{
+    fn handle(&mut self, sess: &mut S, buf: &mut Cursor<Bytes>) -> Result<()> {
+        let packet = P::deserialize(buf).map_err(|e| e.locate(buf.get_ref().len()).in_opcode(P::OPCODE))?;
+        (self.f)(sess, packet)
+    }
+}
+
+type Fallback<S> = Box<dyn FnMut(&mut S, u16, &mut Cursor<Bytes>) -> Result<()>>;
+
+/// Routes received packets to the handler registered for their opcode.
+///
+/// A failing handler only loses its own packet: errors that leave the connection usable are logged
+/// and `dispatch` carries on, fatal ones (see `Error::is_fatal`) are returned to the caller.
+///
+/// ```no_run
+/// use oblivion::net::{client_packet, server_packet, Dispatcher, Session};
+///
+/// let mut dispatcher = Dispatcher::<Session>::new();
+/// dispatcher.on(|sess, _: server_packet::Ping| sess.send_packet(client_packet::Pong));
+/// ```
+pub struct Dispatcher<S = Session> {
+    handlers: HashMap<u16, Box<dyn Handler<S>>>,
+    fallback: Option<Fallback<S>>,
+}
+
+impl<S> Default for Dispatcher<S> {
+    fn default() -> Self {
+        Self {
+            handlers: HashMap::new(),
+            fallback: None,
+        }
+    }
+}
+
+impl<S> std::fmt::Debug for Dispatcher<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut opcodes = self.handlers.keys().collect::<Vec<_>>();
+        opcodes.sort();
+        f.debug_struct("Dispatcher")
+            .field("opcodes", &opcodes)
+            .field("fallback", &self.fallback.is_some())
+            .finish()
+    }
+}
+
+impl<S> Dispatcher<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `f` for the packet type `P`, replacing and returning the previous handler of its opcode.
+    pub fn on<P, F>(&mut self, f: F) -> Option<Box<dyn Handler<S>>>
+    where
+        P: InPacket + Opcode + 'static,
+        F: FnMut(&mut S, P) -> Result<()> + 'static,
+        S: 'static,
+    {
+        let typed = Typed {
+            f,
+            packet: PhantomData,
+        };
+        self.insert(P::OPCODE, Box::new(typed))
+    }
+
+    /// Registers a handler working on the raw packet, for opcodes without a packet type.
+    pub fn insert(&mut self, opcode: u16, handler: Box<dyn Handler<S>>) -> Option<Box<dyn Handler<S>>> {
+        self.handlers.insert(opcode, handler)
+    }
+
+    pub fn remove(&mut self, opcode: u16) -> Option<Box<dyn Handler<S>>> {
+        self.handlers.remove(&opcode)
+    }
+
+    pub fn contains(&self, opcode: u16) -> bool {
+        self.handlers.contains_key(&opcode)
+    }
+
+    /// Called for the opcodes without a handler, which are otherwise only logged.
+    pub fn set_fallback<F>(&mut self, f: F)
+    where
+        F: FnMut(&mut S, u16, &mut Cursor<Bytes>) -> Result<()> + 'static,
+    {
+        self.fallback = Some(Box::new(f));
+    }
+
+    /// Passes a received packet, starting with its opcode, to its handler.
+    pub fn dispatch(&mut self, sess: &mut S, packet: Bytes) -> Result<()> {
+        let len = packet.len();
+        let mut buf = Cursor::new(packet);
+        let opcode = buf.try_get_u16_le().map_err(|e| e.locate(len))?;
+
+        let result = match (self.handlers.get_mut(&opcode), self.fallback.as_mut()) {
+            (Some(handler), _) => handler.handle(sess, &mut buf),
+            (None, Some(fallback)) => fallback(sess, opcode, &mut buf).map_err(|e| e.locate(len)),
+            (None, None) => {
+                debug!("Unhandled opcode {:#06X}, len={}", opcode, buf.get_ref().len());
+                Ok(())
+            }
+        };
+
+        match result.map_err(|e| e.in_opcode(opcode)) {
+            Err(e) if !e.is_fatal() => {
+                warn!("Handler of opcode {:#06X} failed: {}", opcode, e);
+                Ok(())
+            }
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_dispatcher {
+    use std::io::Cursor;
+
+    use bytes::Bytes;
+
+    use crate::io::{BufRead, Error, Opcode};
+    use crate::net::server_packet::{InventoryOperation, Ping};
+    use crate::net::ServerOp;
+
+    use super::{Dispatcher, Handler};
+
+    #[derive(Default)]
+    struct Log(Vec<String>);
+
+    fn packet(bytes: &'static [u8]) -> Bytes {
+        Bytes::from_static(bytes)
+    }
+
+    #[test]
+    fn test_typed_and_raw_handlers() {
+        let mut dispatcher = Dispatcher::<Log>::new();
+        dispatcher.on(|log: &mut Log, _: Ping| {
+            log.0.push("ping".to_owned());
+            Ok(())
+        });
+        let raw = |log: &mut Log, buf: &mut Cursor<Bytes>| {
+            log.0.push(format!("raw {}", buf.get_ref().len()));
+            Ok(())
+        };
+        dispatcher.insert(0x0A, Box::new(raw));
+
+        let mut log = Log::default();
+        dispatcher.dispatch(&mut log, packet(&[0x11, 0x00])).unwrap();
+        dispatcher.dispatch(&mut log, packet(&[0x0A, 0x00, 0xFF])).unwrap();
+        dispatcher.dispatch(&mut log, packet(&[0x0B, 0x00])).unwrap();
+        assert_eq!(log.0, ["ping", "raw 3"]);
+        assert_eq!(Ping::OPCODE, ServerOp::PING as u16);
+    }
+
+    #[test]
+    fn test_fallback_and_remove() {
+        let mut dispatcher = Dispatcher::<Log>::new();
+        dispatcher.on(|log: &mut Log, _: Ping| {
+            log.0.push("ping".to_owned());
+            Ok(())
+        });
+        dispatcher.set_fallback(|log: &mut Log, opcode, _| {
+            log.0.push(format!("fallback {:#X}", opcode));
+            Ok(())
+        });
+
+        let mut log = Log::default();
+        dispatcher.dispatch(&mut log, packet(&[0x11, 0x00])).unwrap();
+        let mut removed = dispatcher.remove(ServerOp::PING as u16).unwrap();
+        assert!(!dispatcher.contains(ServerOp::PING as u16));
+        dispatcher.dispatch(&mut log, packet(&[0x11, 0x00])).unwrap();
+        removed.handle(&mut log, &mut Cursor::new(Bytes::new())).unwrap();
+        assert_eq!(log.0, ["ping", "fallback 0x11", "ping"]);
+    }
+
+    #[test]
+    fn test_error_isolation() {
+        let mut dispatcher = Dispatcher::<Log>::new();
+        let short = |_: &mut Log, _: &mut Cursor<Bytes>| Err(Error::PacketTooShort { offset: 2, wanted: 4 });
+        let closed = |_: &mut Log, _: &mut Cursor<Bytes>| Err(Error::Io(std::io::ErrorKind::UnexpectedEof.into()));
+        dispatcher.insert(0x01, Box::new(short));
+        dispatcher.insert(0x02, Box::new(closed));
+
+        let mut log = Log::default();
+        assert!(dispatcher.dispatch(&mut log, packet(&[0x01, 0x00])).is_ok());
+        assert!(matches!(dispatcher.dispatch(&mut log, packet(&[0x02, 0x00])), Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn test_error_offset() {
+        let mut read = |_: &mut Log, buf: &mut Cursor<Bytes>| buf.try_get_i32_le().map(drop);
+        let mut buf = Cursor::new(packet(&[0x03, 0x00, 0xAA]));
+        buf.set_position(2);
+
+        let err = Handler::handle(&mut read, &mut Log::default(), &mut buf).unwrap_err();
+        assert!(matches!(err, Error::PacketTooShort { offset: 2, wanted: 4 }));
+    }
+
+    #[test]
+    fn test_protocol_error_opcode() {
+        let mut dispatcher = Dispatcher::<Log>::new();
+        dispatcher.on(|_: &mut Log, _: InventoryOperation| Ok(()));
+        let mut handler = dispatcher.remove(InventoryOperation::OPCODE).unwrap();
+        // A change of unknown mode 4.
+        let mut buf = Cursor::new(packet(&[0x1D, 0x00, 0x01, 0x01, 0x04, 0x01, 0x01, 0x00]));
+        buf.set_position(2);
+
+        let err = handler.handle(&mut Log::default(), &mut buf).unwrap_err();
+        assert!(matches!(err, Error::Protocol { opcode, offset: 4, .. } if opcode == InventoryOperation::OPCODE));
+        assert_eq!(err.to_string(), "Protocol error in opcode 0x001D at offset 4: Unknown inventory operation 4");
+    }
+}
@@ -0,0 +1,160 @@
+use std::io::Cursor;
+use std::time::Duration;
+
+use bytes::{Buf, BytesMut};
+
+use crate::io::{Error, InPacket, Result};
+
+use super::profile::VersionProfile;
+use super::session::Proto;
+
+/// Upper bound for the body of the server hello, anything larger is not a MapleStory server.
+const MAX_HANDSHAKE_SIZE: usize = 0x100;
+
+/// How a client connects to a server, and which version it expects to find there.
+#[derive(Debug, Clone)]
+pub struct ConnectOptions {
+    pub profile: VersionProfile,
+    /// Limit for establishing the connection and receiving the server hello, each.
+    pub timeout: Duration,
+}
+
+impl Default for ConnectOptions {
+    fn default() -> Self {
+        Self {
+            profile: VersionProfile::default(),
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl ConnectOptions {
+    /// Checks the server hello against the expected version profile.
+    pub fn verify(&self, proto: &Proto) -> Result<()> {
+        let profile = &self.profile;
+        if proto.version != profile.version {
+            return handshake_failed(format!("Server version {} does not match expected {}", proto.version, profile.version));
+        }
+        if proto.patch != profile.patch {
+            return handshake_failed(format!("Server patch {:?} does not match expected {:?}", proto.patch, profile.patch));
+        }
+        if proto.locale != profile.locale {
+            return handshake_failed(format!("Server locale {} does not match expected {}", proto.locale, profile.locale));
+        }
+        Ok(())
+    }
+}
+
+/// Accumulates bytes until the length-prefixed server hello is complete.
+///
+/// Whatever follows the hello stays buffered and belongs to the packet decoder.
+#[derive(Debug, Default)]
+pub struct HandshakeReader {
+    buf: BytesMut,
+}
+
+impl HandshakeReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn buffer_mut(&mut self) -> &mut BytesMut {
+        &mut self.buf
+    }
+
+    pub fn append(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Returns the hello once it is complete, `None` if more bytes are needed.
+    pub fn parse(&mut self) -> Result<Option<Proto>> {
+        if self.buf.len() < 2 {
+            return Ok(None);
+        }
+
+        let body_size = (&self.buf[..2]).get_u16_le() as usize;
+        if body_size > MAX_HANDSHAKE_SIZE {
+            return handshake_failed(format!("Handshake of {} bytes is too large", body_size));
+        }
+        if self.buf.len() < 2 + body_size {
+            return Ok(None);
+        }
+
+        self.buf.advance(2);
+        let mut body = Cursor::new(self.buf.split_to(body_size).freeze());
+        let proto = Proto::deserialize(&mut body).map_err(|e| e.locate(body_size))?;
+        if body.has_remaining() {
+            return handshake_failed(format!("Handshake has {} unexpected trailing bytes", body.remaining()));
+        }
+
+        Ok(Some(proto))
+    }
+
+    /// Bytes received after the hello.
+    pub fn into_remaining(self) -> BytesMut {
+        self.buf
+    }
+}
+
+fn handshake_failed<T>(msg: String) -> Result<T> {
+    Err(Error::Handshake(msg))
+}
+
+#[cfg(test)]
+mod test_handshake {
+    use crate::io::OutPacket;
+    use crate::net::test_session::proto;
+
+    use super::{ConnectOptions, HandshakeReader};
+
+    #[test]
+    fn test_fragmented_hello() {
+        let hello = proto().serialize();
+        let mut reader = HandshakeReader::new();
+
+        for (i, b) in hello.iter().enumerate() {
+            assert!(reader.parse().unwrap().is_none(), "complete after {} bytes", i);
+            reader.append(&[*b]);
+        }
+
+        let parsed = reader.parse().unwrap().unwrap();
+        assert_eq!(parsed.patch, "1");
+        assert_eq!(parsed.send_iv, proto().send_iv);
+        assert!(reader.into_remaining().is_empty());
+    }
+
+    #[test]
+    fn test_trailing_bytes_are_kept() {
+        let mut reader = HandshakeReader::new();
+        reader.append(&proto().serialize());
+        reader.append(&[0xDE, 0xAD]);
+
+        assert!(reader.parse().unwrap().is_some());
+        assert_eq!(reader.into_remaining().as_ref(), [0xDE, 0xAD]);
+    }
+
+    #[test]
+    fn test_oversized_hello() {
+        let mut reader = HandshakeReader::new();
+        reader.append(&[0xFF, 0xFF, 0x53, 0x00]);
+        assert!(reader.parse().is_err());
+    }
+
+    #[test]
+    fn test_verify() {
+        let proto = proto();
+        assert!(ConnectOptions::default().verify(&proto).is_ok());
+
+        let mut options = ConnectOptions::default();
+        options.profile.version = 95;
+        assert!(options.verify(&proto).is_err());
+
+        let mut options = ConnectOptions::default();
+        options.profile.patch = "2".to_owned();
+        assert!(options.verify(&proto).is_err());
+
+        let mut options = ConnectOptions::default();
+        options.profile.locale = 7;
+        assert!(options.verify(&proto).is_err());
+    }
+}
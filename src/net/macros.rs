@@ -0,0 +1,52 @@
+/// Declares an opcode enum.
+///
+/// The discriminants are the v83 wire values, so two opcodes sharing a value fail to compile.
+/// Variants keep the upper-case names used by server sources and version profile files.
+macro_rules! opcodes {
+    ($(#[$meta:meta])* pub enum $ty:ident { $($name:ident = $value:expr,)* }) => {
+        $(#[$meta])*
+        #[allow(non_camel_case_types)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
+        #[repr(u16)]
+        pub enum $ty {
+            $($name = $value,)*
+        }
+
+        impl $ty {
+            pub const ALL: &'static [$ty] = &[$($ty::$name,)*];
+
+            pub fn name(self) -> &'static str {
+                match self {
+                    $($ty::$name => stringify!($name),)*
+                }
+            }
+
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $(stringify!($name) => Some($ty::$name),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl std::fmt::Display for $ty {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+
+        impl From<$ty> for u16 {
+            fn from(op: $ty) -> u16 {
+                num_traits::ToPrimitive::to_u16(&op).unwrap()
+            }
+        }
+
+        impl std::convert::TryFrom<u16> for $ty {
+            type Error = crate::io::Error;
+
+            fn try_from(value: u16) -> crate::io::Result<Self> {
+                num_traits::FromPrimitive::from_u16(value).ok_or(crate::io::Error::UnknownOpcode(value))
+            }
+        }
+    };
+}
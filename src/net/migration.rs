@@ -0,0 +1,46 @@
+use std::net::SocketAddr;
+
+use crate::io::Result;
+
+use super::client_packet::PlayerLoggedin;
+use super::handshake::ConnectOptions;
+use super::server_packet::{ChangeChannel, ServerIp};
+use super::session::Session;
+
+/// Where the server sends a character next: its channel after the selection, another channel,
+/// or the cash shop.
+///
+/// Each of them is a new server, reached with a new connection and handshake. The character logs in
+/// there with `PLAYER_LOGGEDIN`, the old connection is done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Migration {
+    pub addr: SocketAddr,
+    pub character: i32,
+}
+
+impl Migration {
+    pub fn from_server_ip(server_ip: &ServerIp) -> Self {
+        Self {
+            addr: server_ip.addr(),
+            character: server_ip.character,
+        }
+    }
+
+    /// `CHANGE_CHANNEL` only names the server, the character stays the one already playing.
+    pub fn from_change_channel(change: &ChangeChannel, character: i32) -> Self {
+        Self {
+            addr: change.addr(),
+            character,
+        }
+    }
+
+    /// Connects to the new server and logs the character in.
+    pub fn connect(&self, options: &ConnectOptions) -> Result<Session> {
+        debug!("Migrating character {} to {}", self.character, self.addr);
+        let mut sess = Session::connect_server(self.addr, options)?;
+        sess.send_packet(PlayerLoggedin {
+            character: self.character,
+        })?;
+        Ok(sess)
+    }
+}
@@ -1,9 +1,29 @@
-pub use session::Session;
+//! Connections to a server: sessions, the packet codec, opcodes and packets.
 
-mod codec;
+pub use session::{Proto, Session};
+pub use async_session::AsyncSession;
+pub use dispatcher::{Dispatcher, Handler};
+pub use poller::{PollEvent, Poller};
+pub use mio::Token;
+pub use handshake::ConnectOptions;
+pub use migration::Migration;
+pub use profile::{OpcodeTable, VersionProfile};
+pub use client_ops::ClientOp;
+pub use server_ops::ServerOp;
+#[cfg(test)]
+pub(crate) use session::test_session;
+
+#[macro_use]
+mod macros;
+pub mod codec;
 mod session;
+mod async_session;
+mod handshake;
+mod migration;
+mod dispatcher;
+mod poller;
+mod profile;
 pub mod client_packet;
 pub mod server_packet;
-pub mod client_ops;
-pub mod server_ops;
-
+mod client_ops;
+mod server_ops;
@@ -0,0 +1,340 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bytes::Bytes;
+use mio::net::TcpStream;
+use mio::{Events, Interest, Poll, Token};
+
+use crate::io::{Error, Result};
+
+use super::session::Session;
+
+/// What happened to one of the sessions of a `Poller`.
+#[derive(Debug)]
+pub enum PollEvent {
+    /// A packet was received, starting with its opcode.
+    Packet(Token, Bytes),
+    /// Bytes queued because the socket was full have all been sent.
+    Writable(Token),
+    /// The session failed and is no longer polled, it is dropped with the event.
+    Closed(Token, Error),
+}
+
+/// Waits on many sessions from a single thread, sleeping until one of them has something to do.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use oblivion::net::{ConnectOptions, PollEvent, Poller, Session};
+///
+/// let mut poller = Poller::new()?;
+/// let token = poller.add(Session::connect_server("127.0.0.1:8484", &ConnectOptions::default())?)?;
+/// loop {
+///     for event in poller.poll(Some(Duration::from_secs(1)))? {
+///         if let PollEvent::Packet(token, packet) = event {
+///             println!("{:?}: {:02X?}", token, packet.as_ref());
+///         }
+///     }
+/// }
+/// # Ok::<(), oblivion::io::Error>(())
+/// ```
+pub struct Poller {
+    poll: Poll,
+    events: Events,
+    sessions: HashMap<Token, Session<TcpStream>>,
+    /// Sessions that may hold packets decoded before they were registered.
+    fresh: Vec<Token>,
+    next_token: usize,
+}
+
+impl std::fmt::Debug for Poller {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Poller").field("sessions", &self.sessions.len()).finish()
+    }
+}
+
+impl Poller {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            poll: Poll::new()?,
+            events: Events::with_capacity(256),
+            sessions: HashMap::new(),
+            fresh: Vec::new(),
+            next_token: 0,
+        })
+    }
+
+    /// Starts polling `sess`, identified by the returned token in events.
+    pub fn add(&mut self, sess: Session) -> Result<Token> {
+        let mut sess = sess.into_mio();
+        let token = Token(self.next_token);
+        self.next_token += 1;
+
+        self.poll
+            .registry()
+            .register(sess.socket_mut(), token, Interest::READABLE | Interest::WRITABLE)?;
+        self.sessions.insert(token, sess);
+        self.fresh.push(token);
+        Ok(token)
+    }
+
+    /// Stops polling a session and hands it back.
+    pub fn remove(&mut self, token: Token) -> Option<Session<TcpStream>> {
+        let mut sess = self.sessions.remove(&token)?;
+        if let Err(e) = self.poll.registry().deregister(sess.socket_mut()) {
+            warn!("Failed to deregister {:?}: {}", token, e);
+        }
+        Some(sess)
+    }
+
+    pub fn get_mut(&mut self, token: Token) -> Option<&mut Session<TcpStream>> {
+        self.sessions.get_mut(&token)
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Sleeps until a session is ready or `timeout` elapses, then returns what happened.
+    ///
+    /// Returns an empty list on timeout. A `None` timeout waits until something happens.
+    pub fn poll(&mut self, timeout: Option<Duration>) -> Result<Vec<PollEvent>> {
+        let mut out = Vec::new();
+        for token in std::mem::take(&mut self.fresh) {
+            self.read(token, &mut out);
+        }
+
+        let timeout = if out.is_empty() { timeout } else { Some(Duration::from_secs(0)) };
+        match self.poll.poll(&mut self.events, timeout) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => return Ok(out),
+            Err(e) => return Err(Error::from(e)),
+        }
+
+        let ready = self
+            .events
+            .iter()
+            .map(|event| (event.token(), event.is_readable() || event.is_read_closed(), event.is_writable()))
+            .collect::<Vec<_>>();
+        for (token, readable, writable) in ready {
+            if writable {
+                self.write(token, &mut out);
+            }
+            if readable {
+                self.read(token, &mut out);
+            }
+        }
+        Ok(out)
+    }
+
+    fn read(&mut self, token: Token, out: &mut Vec<PollEvent>) {
+        let sess = match self.sessions.get_mut(&token) {
+            Some(sess) => sess,
+            None => return,
+        };
+        match sess.recv_all() {
+            Ok(packets) => {
+                out.extend(packets.into_iter().map(|packet| PollEvent::Packet(token, packet)));
+                if sess.is_closed() {
+                    self.close(token, Error::Io(std::io::ErrorKind::UnexpectedEof.into()), out);
+                }
+            }
+            Err(e) => self.close(token, e, out),
+        }
+    }
+
+    fn write(&mut self, token: Token, out: &mut Vec<PollEvent>) {
+        let sess = match self.sessions.get_mut(&token) {
+            Some(sess) => sess,
+            None => return,
+        };
+        if !sess.has_pending_writes() {
+            return;
+        }
+        match sess.flush() {
+            Ok(()) if !sess.has_pending_writes() => out.push(PollEvent::Writable(token)),
+            Ok(()) => {}
+            Err(e) => self.close(token, e, out),
+        }
+    }
+
+    fn close(&mut self, token: Token, e: Error, out: &mut Vec<PollEvent>) {
+        self.remove(token);
+        out.push(PollEvent::Closed(token, e));
+    }
+}
+
+#[cfg(test)]
+mod test_poller {
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    use crate::io::OutPacket;
+    use crate::net::codec::Encoder;
+    use crate::net::test_session::proto;
+    use crate::net::{ConnectOptions, Session};
+
+    use super::{PollEvent, Poller};
+
+    const SESSIONS: usize = 16;
+
+    /// CPU time consumed by the calling thread.
+    #[cfg(unix)]
+    fn thread_cpu_time() -> Duration {
+        let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+        assert_eq!(unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut ts) }, 0);
+        Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+    }
+
+    #[test]
+    fn test_idle_sessions_do_not_spin() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (ping_tx, ping_rx) = mpsc::channel::<()>();
+        std::thread::spawn(move || {
+            let mut sockets = Vec::new();
+            for _ in 0..SESSIONS {
+                let (mut socket, _) = listener.accept().unwrap();
+                socket.write_all(&proto().remote().serialize()).unwrap();
+                sockets.push(socket);
+            }
+            ping_rx.recv().unwrap();
+            let mut enc = Encoder::server(83, proto().send_iv);
+            sockets[3].write_all(&enc.encode(&[0x11, 0x00])).unwrap();
+            ping_rx.recv().ok();
+        });
+
+        let mut poller = Poller::new().unwrap();
+        let mut tokens = Vec::new();
+        for _ in 0..SESSIONS {
+            let sess = Session::connect_server(addr, &ConnectOptions::default()).unwrap();
+            tokens.push(poller.add(sess).unwrap());
+        }
+
+        #[cfg(unix)]
+        let cpu = thread_cpu_time();
+        let start = Instant::now();
+        let mut wakeups = 0;
+        while start.elapsed() < Duration::from_secs(1) {
+            assert!(poller.poll(Some(Duration::from_millis(250))).unwrap().is_empty());
+            wakeups += 1;
+        }
+        assert!(wakeups <= 5, "woke up {} times", wakeups);
+        #[cfg(unix)]
+        {
+            let used = thread_cpu_time() - cpu;
+            assert!(used < Duration::from_millis(50), "used {:?} of CPU while idle", used);
+        }
+
+        ping_tx.send(()).unwrap();
+        let events = poller.poll(Some(Duration::from_secs(5))).unwrap();
+        match events.as_slice() {
+            [PollEvent::Packet(token, packet)] => {
+                assert_eq!(*token, tokens[3]);
+                assert_eq!(packet.as_ref(), [0x11, 0x00]);
+            }
+            other => panic!("{:?}", other),
+        }
+        ping_tx.send(()).unwrap();
+    }
+
+    /// Makes a socket fill up after a few kilobytes instead of megabytes.
+    #[cfg(unix)]
+    fn shrink_buffers<S: std::os::unix::io::AsRawFd>(socket: &S) {
+        let size: libc::c_int = 4096;
+        for option in [libc::SO_SNDBUF, libc::SO_RCVBUF] {
+            let ret = unsafe {
+                libc::setsockopt(
+                    socket.as_raw_fd(),
+                    libc::SOL_SOCKET,
+                    option,
+                    &size as *const _ as *const libc::c_void,
+                    std::mem::size_of_val(&size) as libc::socklen_t,
+                )
+            };
+            assert_eq!(ret, 0);
+        }
+    }
+
+    #[test]
+    fn test_write_readiness() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        #[cfg(unix)]
+        shrink_buffers(&listener);
+        let addr = listener.local_addr().unwrap();
+        let (drain_tx, drain_rx) = mpsc::channel::<()>();
+        std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            socket.write_all(&proto().remote().serialize()).unwrap();
+            drain_rx.recv().unwrap();
+            std::io::copy(&mut socket, &mut std::io::sink()).unwrap();
+        });
+
+        let mut poller = Poller::new().unwrap();
+        let sess = Session::connect_server(addr, &ConnectOptions::default()).unwrap();
+        let token = poller.add(sess).unwrap();
+        assert!(poller.poll(Some(Duration::from_millis(50))).unwrap().is_empty());
+
+        let sess = poller.get_mut(token).unwrap();
+        #[cfg(unix)]
+        shrink_buffers(sess.socket());
+        let mut payload = vec![0u8; 16 * 1024];
+        payload[0] = 0x18; // PONG
+        while !sess.has_pending_writes() {
+            sess.send(&payload).unwrap();
+        }
+
+        drain_tx.send(()).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            assert!(Instant::now() < deadline);
+            let events = poller.poll(Some(Duration::from_millis(100))).unwrap();
+            if events.iter().any(|e| matches!(e, PollEvent::Writable(t) if *t == token)) {
+                break;
+            }
+        }
+        assert!(!poller.get_mut(token).unwrap().has_pending_writes());
+    }
+
+    #[test]
+    fn test_closed_session_is_removed() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let mut wire = proto().remote().serialize();
+            wire.extend(Encoder::server(83, proto().send_iv).encode(&[0x11, 0x00]));
+            socket.write_all(&wire).unwrap();
+        });
+
+        let mut poller = Poller::new().unwrap();
+        let sess = Session::connect_server(addr, &ConnectOptions::default()).unwrap();
+        let token = poller.add(sess).unwrap();
+
+        let mut packets = 0;
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !poller.is_empty() {
+            assert!(Instant::now() < deadline);
+            for event in poller.poll(Some(Duration::from_millis(100))).unwrap() {
+                match event {
+                    PollEvent::Packet(t, _) => {
+                        assert_eq!(t, token);
+                        packets += 1;
+                    }
+                    PollEvent::Closed(t, e) => {
+                        assert_eq!(t, token);
+                        assert!(e.is_fatal());
+                    }
+                    PollEvent::Writable(_) => {}
+                }
+            }
+        }
+        assert_eq!(packets, 1);
+    }
+}
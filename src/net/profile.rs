@@ -0,0 +1,262 @@
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::path::Path;
+
+use bytes::{BufMut, Bytes, BytesMut};
+use serde::Deserialize;
+
+use crate::io::{Error, Result};
+
+use super::{ClientOp, ServerOp};
+
+/// Everything that differs between game versions on the wire.
+///
+/// Packet types always use the opcodes of `ClientOp` and `ServerOp` (v83). A profile maps them
+/// to what a given version actually sends, so the same `Session` and packets work against it.
+///
+/// Profiles are loaded from TOML files listing the opcodes by name, any opcode missing from a table
+/// is treated as unsupported by that version. `profiles/v83.toml` is the complete built-in table:
+///
+/// ```toml
+/// version = 83
+/// patch = "1"
+/// locale = 8
+///
+/// [client]
+/// LOGIN_PASSWORD = 0x01
+/// PONG = 0x18
+///
+/// [server]
+/// LOGIN_STATUS = 0x00
+/// PING = 0x11
+/// ```
+#[derive(Debug, Clone)]
+pub struct VersionProfile {
+    pub version: u16,
+    pub patch: String,
+    pub locale: u8,
+    /// Opcodes sent by the client.
+    pub client: OpcodeTable,
+    /// Opcodes sent by the server.
+    pub server: OpcodeTable,
+}
+
+#[derive(Deserialize)]
+struct ProfileFile {
+    version: u16,
+    patch: String,
+    locale: u8,
+    client: BTreeMap<String, u16>,
+    server: BTreeMap<String, u16>,
+}
+
+impl Default for VersionProfile {
+    fn default() -> Self {
+        Self::v83()
+    }
+}
+
+impl VersionProfile {
+    /// The built-in profile, matching the crate's own opcode tables.
+    pub fn v83() -> Self {
+        Self {
+            version: 83,
+            patch: "1".to_owned(),
+            locale: 8,
+            client: OpcodeTable::native(ClientOp::ALL.iter().map(|op| u16::from(*op)), client_op_name),
+            server: OpcodeTable::native(ServerOp::ALL.iter().map(|op| u16::from(*op)), server_op_name),
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn from_toml(s: &str) -> Result<Self> {
+        let file: ProfileFile = toml::from_str(s)?;
+        Ok(Self {
+            version: file.version,
+            patch: file.patch,
+            locale: file.locale,
+            client: OpcodeTable::from_names(&file.client, |name| ClientOp::from_name(name).map(u16::from), client_op_name)?,
+            server: OpcodeTable::from_names(&file.server, |name| ServerOp::from_name(name).map(u16::from), server_op_name)?,
+        })
+    }
+}
+
+fn client_op_name(op: u16) -> Option<&'static str> {
+    ClientOp::try_from(op).ok().map(ClientOp::name)
+}
+
+fn server_op_name(op: u16) -> Option<&'static str> {
+    ServerOp::try_from(op).ok().map(ServerOp::name)
+}
+
+/// Translates opcodes between the crate's numbering and the wire numbering of one direction.
+#[derive(Debug, Clone)]
+pub struct OpcodeTable {
+    to_wire: HashMap<u16, u16>,
+    from_wire: HashMap<u16, u16>,
+    names: fn(u16) -> Option<&'static str>,
+}
+
+impl OpcodeTable {
+    fn native<I: Iterator<Item = u16>>(ops: I, names: fn(u16) -> Option<&'static str>) -> Self {
+        let to_wire = ops.map(|op| (op, op)).collect::<HashMap<_, _>>();
+        let from_wire = to_wire.clone();
+        Self { to_wire, from_wire, names }
+    }
+
+    fn from_names(
+        file: &BTreeMap<String, u16>,
+        lookup: fn(&str) -> Option<u16>,
+        names: fn(u16) -> Option<&'static str>,
+    ) -> Result<Self> {
+        let mut table = Self {
+            to_wire: HashMap::new(),
+            from_wire: HashMap::new(),
+            names,
+        };
+        for (name, &wire) in file {
+            let op = match lookup(name) {
+                Some(op) => op,
+                None => return profile_failed(format!("Unknown opcode name {}", name)),
+            };
+            if let Some(other) = table.from_wire.insert(wire, op) {
+                if other != op {
+                    return profile_failed(format!("Wire opcode {:#X} of {} is already in use", wire, name));
+                }
+            }
+            table.to_wire.insert(op, wire);
+        }
+        Ok(table)
+    }
+
+    pub fn wire_opcode(&self, op: u16) -> Option<u16> {
+        self.to_wire.get(&op).copied()
+    }
+
+    pub fn local_opcode(&self, wire: u16) -> Option<u16> {
+        self.from_wire.get(&wire).copied()
+    }
+
+    /// Symbolic name of an opcode in the crate's numbering.
+    pub fn name(&self, op: u16) -> Option<&'static str> {
+        (self.names)(op)
+    }
+
+    /// Rewrites the opcode at the start of an outgoing packet to its wire value.
+    pub fn encode(&self, packet: &mut [u8]) -> Result<()> {
+        let op = read_opcode(packet)?;
+        match self.wire_opcode(op) {
+            Some(wire) => {
+                packet[..2].copy_from_slice(&wire.to_le_bytes());
+                Ok(())
+            }
+            None => Err(Error::UnknownOpcode(op)),
+        }
+    }
+
+    /// Rewrites the opcode at the start of an incoming packet from its wire value,
+    /// `None` if this version's table does not know it.
+    pub fn decode(&self, packet: Bytes) -> Option<Bytes> {
+        let wire = read_opcode(&packet).ok()?;
+        let op = self.local_opcode(wire)?;
+        if op == wire {
+            return Some(packet);
+        }
+
+        let mut translated = BytesMut::with_capacity(packet.len());
+        translated.put_u16_le(op);
+        translated.extend_from_slice(&packet[2..]);
+        Some(translated.freeze())
+    }
+}
+
+fn read_opcode(packet: &[u8]) -> Result<u16> {
+    match packet {
+        [lo, hi, ..] => Ok(u16::from_le_bytes([*lo, *hi])),
+        _ => Err(Error::PacketTooShort {
+            offset: 0,
+            wanted: 2,
+        }),
+    }
+}
+
+fn profile_failed<T>(msg: String) -> Result<T> {
+    Err(Error::Config(msg))
+}
+
+#[cfg(test)]
+mod test_profile {
+    use bytes::Bytes;
+
+    use crate::io::Error;
+    use crate::net::{ClientOp, ServerOp};
+
+    use super::VersionProfile;
+
+    const SAMPLE: &str = r#"
+        version = 95
+        patch = "1"
+        locale = 8
+
+        [client]
+        LOGIN_PASSWORD = 0x01
+        PONG = 0x19
+
+        [server]
+        LOGIN_STATUS = 0x00
+        PING = 0x13
+        SERVERLIST = 0x0A
+    "#;
+
+    #[test]
+    fn test_builtin_v83_is_native() {
+        let profile = VersionProfile::v83();
+        for op in ClientOp::ALL {
+            assert_eq!(profile.client.wire_opcode(*op as u16), Some(*op as u16));
+        }
+        for op in ServerOp::ALL {
+            assert_eq!(profile.server.local_opcode(*op as u16), Some(*op as u16));
+        }
+        assert_eq!(profile.server.name(0x11), Some("PING"));
+    }
+
+    #[test]
+    fn test_shipped_v83_file_matches_builtin() {
+        let file = VersionProfile::from_toml(include_str!("../../profiles/v83.toml")).unwrap();
+        let builtin = VersionProfile::v83();
+
+        assert_eq!(file.version, builtin.version);
+        assert_eq!(file.patch, builtin.patch);
+        assert_eq!(file.locale, builtin.locale);
+        assert_eq!(file.client.to_wire, builtin.client.to_wire);
+        assert_eq!(file.server.from_wire, builtin.server.from_wire);
+    }
+
+    #[test]
+    fn test_translation() {
+        let profile = VersionProfile::from_toml(SAMPLE).unwrap();
+
+        let mut pong = vec![0x18, 0x00];
+        profile.client.encode(&mut pong).unwrap();
+        assert_eq!(pong, [0x19, 0x00]);
+
+        let mut unsupported = vec![0x14, 0x00];
+        assert!(matches!(profile.client.encode(&mut unsupported), Err(Error::UnknownOpcode(0x14))));
+
+        let ping = profile.server.decode(Bytes::from_static(&[0x13, 0x00, 0xAB])).unwrap();
+        assert_eq!(ping.as_ref(), [0x11, 0x00, 0xAB]);
+        assert!(profile.server.decode(Bytes::from_static(&[0x12, 0x00])).is_none());
+    }
+
+    #[test]
+    fn test_rejects_bad_tables() {
+        let unknown = SAMPLE.replace("PONG", "NOT_AN_OPCODE");
+        assert!(matches!(VersionProfile::from_toml(&unknown), Err(Error::Config(_))));
+
+        let clash = SAMPLE.replace("SERVERLIST = 0x0A", "SERVERLIST = 0x13");
+        assert!(VersionProfile::from_toml(&clash).is_err());
+    }
+}
@@ -1,330 +1,359 @@
-pub const LOGIN_STATUS:u16=0x00;
-pub const GUEST_ID_LOGIN:u16=0x01;
-pub const ACCOUNT_INFO:u16=0x02;//I guess this was in v83 too :)
-pub const SERVERSTATUS:u16=0x03;//CHECK_USER_LIMIT_RESULT
-pub const GENDER_DONE:u16=0x04;//SET_ACCOUNT_RESULT
-pub const CONFIRM_EULA_RESULT:u16=0x05;
-pub const CHECK_PINCODE:u16=0x06;
-pub const UPDATE_PINCODE:u16=0x07;
+opcodes! {
+    /// Opcodes of the messages sent by the server.
+    pub enum ServerOp {
+        LOGIN_STATUS = 0x00,
+        GUEST_ID_LOGIN = 0x01,
+        ACCOUNT_INFO = 0x02,//I guess this was in v83 too :)
+        SERVERSTATUS = 0x03,//CHECK_USER_LIMIT_RESULT
+        GENDER_DONE = 0x04,//SET_ACCOUNT_RESULT
+        CONFIRM_EULA_RESULT = 0x05,
+        CHECK_PINCODE = 0x06,
+        UPDATE_PINCODE = 0x07,
 
-pub const VIEW_ALL_CHAR:u16=0x08;
-pub const SELECT_CHARACTER_BY_VAC:u16=0x09;
+        VIEW_ALL_CHAR = 0x08,
+        SELECT_CHARACTER_BY_VAC = 0x09,
 
-pub const SERVERLIST:u16=0x0A;
-pub const CHARLIST:u16=0x0B;
-pub const SERVER_IP:u16=0x0C;
-pub const CHAR_NAME_RESPONSE:u16=0x0D;
-pub const ADD_NEW_CHAR_ENTRY:u16=0x0E;
-pub const DELETE_CHAR_RESPONSE:u16=0x0F;
-pub const CHANGE_CHANNEL:u16=0x10;
-pub const PING:u16=0x11;
-pub const KOREAN_INTERNET_CAFE_SHIT:u16=0x12;//Useless ignore it.
-pub const CHANNEL_SELECTED:u16=0x14;
-pub const HACKSHIELD_REQUEST:u16=0x15;//maybe this is RELOG_RESPONSE, can't care less
-pub const RELOG_RESPONSE:u16=0x16;
-pub const CHECK_CRC_RESULT:u16=0x19;
-pub const LAST_CONNECTED_WORLD:u16=0x1A;
-pub const RECOMMENDED_WORLD_MESSAGE:u16=0x1B;
-pub const CHECK_SPW_RESULT:u16=0x1C;
+        SERVERLIST = 0x0A,
+        CHARLIST = 0x0B,
+        SERVER_IP = 0x0C,
+        CHAR_NAME_RESPONSE = 0x0D,
+        ADD_NEW_CHAR_ENTRY = 0x0E,
+        DELETE_CHAR_RESPONSE = 0x0F,
+        CHANGE_CHANNEL = 0x10,
+        PING = 0x11,
+        KOREAN_INTERNET_CAFE_SHIT = 0x12,//Useless ignore it.
+        CHANNEL_SELECTED = 0x14,
+        HACKSHIELD_REQUEST = 0x15,//maybe this is RELOG_RESPONSE, can't care less
+        RELOG_RESPONSE = 0x16,
+        CHECK_CRC_RESULT = 0x19,
+        LAST_CONNECTED_WORLD = 0x1A,
+        RECOMMENDED_WORLD_MESSAGE = 0x1B,
+        CHECK_SPW_RESULT = 0x1C,
 
-/*CWvsContext::OnPacket*/
-pub const INVENTORY_OPERATION:u16=0x1D;
-pub const INVENTORY_GROW:u16=0x1E;
-pub const STAT_CHANGED:u16=0x1F;
-pub const GIVE_BUFF:u16=0x20;
-pub const CANCEL_BUFF:u16=0x21;
-pub const FORCED_STAT_SET:u16=0x22;
-pub const FORCED_STAT_RESET:u16=0x23;
-pub const UPDATE_SKILLS:u16=0x24;
-pub const SKILL_USE_RESULT:u16=0x25;
-pub const FAME_RESPONSE:u16=0x26;
-pub const SHOW_STATUS_INFO:u16=0x27;
-pub const OPEN_FULL_CLIENT_DOWNLOAD_LINK:u16=0x28;
-pub const MEMO_RESULT:u16=0x29;
-pub const MAP_TRANSFER_RESULT:u16=0x2A;
-pub const WEDDING_PHOTO:u16=0x2B; //ANTI_MACRO_RESULT:u16=0x2B;
-pub const CLAIM_RESULT:u16=0x2D;
-pub const CLAIM_AVAILABLE_TIME:u16=0x2E;
-pub const CLAIM_STATUS_CHANGED:u16=0x2F;
-pub const SET_TAMING_MOB_INFO:u16=0x30;
-pub const QUEST_CLEAR:u16=0x31;
-pub const ENTRUSTED_SHOP_CHECK_RESULT:u16=0x32;
-pub const SKILL_LEARN_ITEM_RESULT:u16=0x33;
-pub const GATHER_ITEM_RESULT:u16=0x34;
-pub const SORT_ITEM_RESULT:u16=0x35;
-pub const SUE_CHARACTER_RESULT:u16=0x37;
-pub const TRADE_MONEY_LIMIT:u16=0x39;
-pub const SET_GENDER:u16=0x3A;
-pub const GUILD_BBS_PACKET:u16=0x3B;
-pub const CHAR_INFO:u16=0x3D;
-pub const PARTY_OPERATION:u16=0x3E;
-pub const BUDDYLIST:u16=0x3F;
-pub const GUILD_OPERATION:u16=0x41;
-pub const ALLIANCE_OPERATION:u16=0x42;
-pub const SPAWN_PORTAL:u16=0x43;
-pub const SERVERMESSAGE:u16=0x44;
-pub const INCUBATOR_RESULT:u16=0x45;
-pub const SHOP_SCANNER_RESULT:u16=0x46;
-pub const SHOP_LINK_RESULT:u16=0x47;
+        /*CWvsContext::OnPacket*/
+        INVENTORY_OPERATION = 0x1D,
+        INVENTORY_GROW = 0x1E,
+        STAT_CHANGED = 0x1F,
+        GIVE_BUFF = 0x20,
+        CANCEL_BUFF = 0x21,
+        FORCED_STAT_SET = 0x22,
+        FORCED_STAT_RESET = 0x23,
+        UPDATE_SKILLS = 0x24,
+        SKILL_USE_RESULT = 0x25,
+        FAME_RESPONSE = 0x26,
+        SHOW_STATUS_INFO = 0x27,
+        OPEN_FULL_CLIENT_DOWNLOAD_LINK = 0x28,
+        MEMO_RESULT = 0x29,
+        MAP_TRANSFER_RESULT = 0x2A,
+        WEDDING_PHOTO = 0x2B, //ANTI_MACRO_RESULT:u16=0x2B;
+        CLAIM_RESULT = 0x2D,
+        CLAIM_AVAILABLE_TIME = 0x2E,
+        CLAIM_STATUS_CHANGED = 0x2F,
+        SET_TAMING_MOB_INFO = 0x30,
+        QUEST_CLEAR = 0x31,
+        ENTRUSTED_SHOP_CHECK_RESULT = 0x32,
+        SKILL_LEARN_ITEM_RESULT = 0x33,
+        GATHER_ITEM_RESULT = 0x34,
+        SORT_ITEM_RESULT = 0x35,
+        SUE_CHARACTER_RESULT = 0x37,
+        TRADE_MONEY_LIMIT = 0x39,
+        SET_GENDER = 0x3A,
+        GUILD_BBS_PACKET = 0x3B,
+        CHAR_INFO = 0x3D,
+        PARTY_OPERATION = 0x3E,
+        BUDDYLIST = 0x3F,
+        GUILD_OPERATION = 0x41,
+        ALLIANCE_OPERATION = 0x42,
+        SPAWN_PORTAL = 0x43,
+        SERVERMESSAGE = 0x44,
+        INCUBATOR_RESULT = 0x45,
+        SHOP_SCANNER_RESULT = 0x46,
+        SHOP_LINK_RESULT = 0x47,
 
-pub const MARRIAGE_REQUEST:u16=0x48;
-pub const MARRIAGE_RESULT:u16=0x49;
-pub const WEDDING_GIFT_RESULT:u16=0x4A;
-pub const NOTIFY_MARRIED_PARTNER_MAP_TRANSFER:u16=0x4B;
+        MARRIAGE_REQUEST = 0x48,
+        MARRIAGE_RESULT = 0x49,
+        WEDDING_GIFT_RESULT = 0x4A,
+        NOTIFY_MARRIED_PARTNER_MAP_TRANSFER = 0x4B,
 
-pub const CASH_PET_FOOD_RESULT:u16=0x4C;
-pub const SET_WEEK_EVENT_MESSAGE:u16=0x4D;
-pub const SET_POTION_DISCOUNT_RATE:u16=0x4E;
+        CASH_PET_FOOD_RESULT = 0x4C,
+        SET_WEEK_EVENT_MESSAGE = 0x4D,
+        SET_POTION_DISCOUNT_RATE = 0x4E,
 
-pub const BRIDLE_MOB_CATCH_FAIL:u16=0x4F;
-pub const IMITATED_NPC_RESULT:u16=0x50;
-pub const IMITATED_NPC_DATA:u16=0x51;
-pub const LIMITED_NPC_DISABLE_INFO:u16=0x52;
-pub const MONSTER_BOOK_SET_CARD:u16=0x53;
-pub const MONSTER_BOOK_SET_COVER:u16=0x54;
-pub const HOUR_CHANGED:u16=0x55;
-pub const MINIMAP_ON_OFF:u16=0x56;
-pub const CONSULT_AUTHKEY_UPDATE:u16=0x57;
-pub const CLASS_COMPETITION_AUTHKEY_UPDATE:u16=0x58;
-pub const WEB_BOARD_AUTHKEY_UPDATE:u16=0x59;
-pub const SESSION_VALUE:u16=0x5A;
-pub const PARTY_VALUE:u16=0x5B;
-pub const FIELD_SET_VARIABLE:u16=0x5C;
-pub const BONUS_EXP_CHANGED:u16=0x5D;//pendant of spirit etc :u16=guess, not sure about the opcode in v83)
+        BRIDLE_MOB_CATCH_FAIL = 0x4F,
+        IMITATED_NPC_RESULT = 0x50,
+        IMITATED_NPC_DATA = 0x51,
+        LIMITED_NPC_DISABLE_INFO = 0x52,
+        MONSTER_BOOK_SET_CARD = 0x53,
+        MONSTER_BOOK_SET_COVER = 0x54,
+        HOUR_CHANGED = 0x55,
+        MINIMAP_ON_OFF = 0x56,
+        CONSULT_AUTHKEY_UPDATE = 0x57,
+        CLASS_COMPETITION_AUTHKEY_UPDATE = 0x58,
+        WEB_BOARD_AUTHKEY_UPDATE = 0x59,
+        SESSION_VALUE = 0x5A,
+        PARTY_VALUE = 0x5B,
+        FIELD_SET_VARIABLE = 0x5C,
+        BONUS_EXP_CHANGED = 0x5D,//pendant of spirit etc :u16=guess, not sure about the opcode in v83)
 
-pub const FAMILY_CHART_RESULT:u16=0x5E;
-pub const FAMILY_INFO_RESULT:u16=0x5F;
-pub const FAMILY_RESULT:u16=0x60;
-pub const FAMILY_JOIN_REQUEST:u16=0x61;
-pub const FAMILY_JOIN_REQUEST_RESULT:u16=0x62;
-pub const FAMILY_JOIN_ACCEPTED:u16=0x63;
-pub const FAMILY_PRIVILEGE_LIST:u16=0x64;
-pub const FAMILY_FAMOUS_POINT_INC_RESULT:u16=0x65;
-pub const FAMILY_NOTIFY_LOGIN_OR_LOGOUT:u16=0x66; //? is logged in. LOLWUT
-pub const FAMILY_SET_PRIVILEGE:u16=0x67;
-pub const FAMILY_SUMMON_REQUEST:u16=0x68;
+        FAMILY_CHART_RESULT = 0x5E,
+        FAMILY_INFO_RESULT = 0x5F,
+        FAMILY_RESULT = 0x60,
+        FAMILY_JOIN_REQUEST = 0x61,
+        FAMILY_JOIN_REQUEST_RESULT = 0x62,
+        FAMILY_JOIN_ACCEPTED = 0x63,
+        FAMILY_PRIVILEGE_LIST = 0x64,
+        FAMILY_FAMOUS_POINT_INC_RESULT = 0x65,
+        FAMILY_NOTIFY_LOGIN_OR_LOGOUT = 0x66, //? is logged in. LOLWUT
+        FAMILY_SET_PRIVILEGE = 0x67,
+        FAMILY_SUMMON_REQUEST = 0x68,
 
-pub const NOTIFY_LEVELUP:u16=0x69;
-pub const NOTIFY_MARRIAGE:u16=0x6A;
-pub const NOTIFY_JOB_CHANGE:u16=0x6B;
-//SET_BUY_EQUIP_EXT:u16=0x6C;  //probably extra pendant slot for other versions?
-pub const MAPLE_TV_USE_RES:u16=0x6D; //It's not blank, It's a popup nibs
-pub const AVATAR_MEGAPHONE_RESULT:u16=0x6E;//bot useless..
-pub const SET_AVATAR_MEGAPHONE:u16=0x6F;
-pub const CLEAR_AVATAR_MEGAPHONE:u16=0x70;
-pub const CANCEL_NAME_CHANGE_RESULT:u16=0x71;
-pub const CANCEL_TRANSFER_WORLD_RESULT:u16=0x72;
-pub const DESTROY_SHOP_RESULT:u16=0x73;
-pub const FAKE_GM_NOTICE:u16=0x74;//bad asses
-pub const SUCCESS_IN_USE_GACHAPON_BOX:u16=0x75;
-pub const NEW_YEAR_CARD_RES:u16=0x76;
-pub const RANDOM_MORPH_RES:u16=0x77;
-pub const CANCEL_NAME_CHANGE_BY_OTHER:u16=0x78;
-pub const SET_EXTRA_PENDANT_SLOT:u16=0x79;
-pub const SCRIPT_PROGRESS_MESSAGE:u16=0x7A;
-pub const DATA_CRC_CHECK_FAILED:u16=0x7B;
-pub const MACRO_SYS_DATA_INIT:u16=0x7C;
+        NOTIFY_LEVELUP = 0x69,
+        NOTIFY_MARRIAGE = 0x6A,
+        NOTIFY_JOB_CHANGE = 0x6B,
+        //SET_BUY_EQUIP_EXT:u16=0x6C;  //probably extra pendant slot for other versions?
+        MAPLE_TV_USE_RES = 0x6D, //It's not blank, It's a popup nibs
+        AVATAR_MEGAPHONE_RESULT = 0x6E,//bot useless..
+        SET_AVATAR_MEGAPHONE = 0x6F,
+        CLEAR_AVATAR_MEGAPHONE = 0x70,
+        CANCEL_NAME_CHANGE_RESULT = 0x71,
+        CANCEL_TRANSFER_WORLD_RESULT = 0x72,
+        DESTROY_SHOP_RESULT = 0x73,
+        FAKE_GM_NOTICE = 0x74,//bad asses
+        SUCCESS_IN_USE_GACHAPON_BOX = 0x75,
+        NEW_YEAR_CARD_RES = 0x76,
+        RANDOM_MORPH_RES = 0x77,
+        CANCEL_NAME_CHANGE_BY_OTHER = 0x78,
+        SET_EXTRA_PENDANT_SLOT = 0x79,
+        SCRIPT_PROGRESS_MESSAGE = 0x7A,
+        DATA_CRC_CHECK_FAILED = 0x7B,
+        MACRO_SYS_DATA_INIT = 0x7C,
 
-/*CStage::OnPacket*/
-pub const SET_FIELD:u16=0x7D;
-pub const SET_ITC:u16=0x7E;
-pub const SET_CASH_SHOP:u16=0x7F;
+        /*CStage::OnPacket*/
+        SET_FIELD = 0x7D,
+        SET_ITC = 0x7E,
+        SET_CASH_SHOP = 0x7F,
 
-/*CField::OnPacket*/
-pub const SET_BACK_EFFECT:u16=0x80;
-pub const SET_MAP_OBJECT_VISIBLE:u16=0x81;//CMapLoadable::OnSetMapObjectVisible O_O
-pub const CLEAR_BACK_EFFECT:u16=0x82;
-pub const BLOCKED_MAP:u16=0x83;//TransferFieldRequestIgnored
-pub const BLOCKED_SERVER:u16=0x84;
-pub const FORCED_MAP_EQUIP:u16=0x85;//FIELD_SPECIFIC_DATA
-pub const MULTICHAT:u16=0x86;
-pub const WHISPER:u16=0x87;
-pub const SPOUSE_CHAT:u16=0x88;
-pub const SUMMON_ITEM_INAVAILABLE:u16=0x89; //You can't use it in this map
+        /*CField::OnPacket*/
+        SET_BACK_EFFECT = 0x80,
+        SET_MAP_OBJECT_VISIBLE = 0x81,//CMapLoadable::OnSetMapObjectVisible O_O
+        CLEAR_BACK_EFFECT = 0x82,
+        BLOCKED_MAP = 0x83,//TransferFieldRequestIgnored
+        BLOCKED_SERVER = 0x84,
+        FORCED_MAP_EQUIP = 0x85,//FIELD_SPECIFIC_DATA
+        MULTICHAT = 0x86,
+        WHISPER = 0x87,
+        SPOUSE_CHAT = 0x88,
+        SUMMON_ITEM_INAVAILABLE = 0x89, //You can't use it in this map
 
-pub const FIELD_EFFECT:u16=0x8A;
-pub const FIELD_OBSTACLE_ONOFF:u16=0x8B;
-pub const FIELD_OBSTACLE_ONOFF_LIST:u16=0x8C;
-pub const FIELD_OBSTACLE_ALL_RESET:u16=0x8D;
-pub const BLOW_WEATHER:u16=0x8E;
-pub const PLAY_JUKEBOX:u16=0x8F;
+        FIELD_EFFECT = 0x8A,
+        FIELD_OBSTACLE_ONOFF = 0x8B,
+        FIELD_OBSTACLE_ONOFF_LIST = 0x8C,
+        FIELD_OBSTACLE_ALL_RESET = 0x8D,
+        BLOW_WEATHER = 0x8E,
+        PLAY_JUKEBOX = 0x8F,
 
-pub const ADMIN_RESULT:u16=0x90;
-pub const OX_QUIZ:u16=0x91;//QUIZ
-pub const GMEVENT_INSTRUCTIONS:u16=0x92;//DESC
-pub const CLOCK:u16=0x93;
-pub const CONTI_MOVE:u16=0x94;
-pub const CONTI_STATE:u16=0x95;
-pub const SET_QUEST_CLEAR:u16=0x96;
-pub const SET_QUEST_TIME:u16=0x97;
-pub const WARN_MESSAGE:u16=0x98;
-pub const SET_OBJECT_STATE:u16=0x99;
-pub const STOP_CLOCK:u16=0x9A;
-pub const ARIANT_ARENA_SHOW_RESULT:u16=0x9B;
-pub const PYRAMID_GAUGE:u16=0x9D;
-pub const PYRAMID_SCORE:u16=0x9E;
-pub const SPAWN_PLAYER:u16=0xA0;
-pub const REMOVE_PLAYER_FROM_MAP:u16=0xA1;
-pub const CHATTEXT:u16=0xA2; //0
-pub const CHATTEXT1:u16=0xA3; //1
-pub const CHALKBOARD:u16=0xA4;
-pub const UPDATE_CHAR_BOX:u16=0xA5;
-pub const SHOW_CONSUME_EFFECT:u16=0xA6;
-pub const SHOW_SCROLL_EFFECT:u16=0xA7;
+        ADMIN_RESULT = 0x90,
+        OX_QUIZ = 0x91,//QUIZ
+        GMEVENT_INSTRUCTIONS = 0x92,//DESC
+        CLOCK = 0x93,
+        CONTI_MOVE = 0x94,
+        CONTI_STATE = 0x95,
+        SET_QUEST_CLEAR = 0x96,
+        SET_QUEST_TIME = 0x97,
+        WARN_MESSAGE = 0x98,
+        SET_OBJECT_STATE = 0x99,
+        STOP_CLOCK = 0x9A,
+        ARIANT_ARENA_SHOW_RESULT = 0x9B,
+        PYRAMID_GAUGE = 0x9D,
+        PYRAMID_SCORE = 0x9E,
+        SPAWN_PLAYER = 0xA0,
+        REMOVE_PLAYER_FROM_MAP = 0xA1,
+        CHATTEXT = 0xA2, //0
+        CHATTEXT1 = 0xA3, //1
+        CHALKBOARD = 0xA4,
+        UPDATE_CHAR_BOX = 0xA5,
+        SHOW_CONSUME_EFFECT = 0xA6,
+        SHOW_SCROLL_EFFECT = 0xA7,
 
-pub const SPAWN_PET:u16=0xA8;
-pub const MOVE_PET:u16=0xAA;
-pub const PET_CHAT:u16=0xAB;
-pub const PET_NAMECHANGE:u16=0xAC;
-pub const PET_EXCEPTION_LIST:u16=0xAD;
-pub const PET_COMMAND:u16=0xAE;
-pub const SPAWN_SPECIAL_MAPOBJECT:u16=0xAF;
-pub const REMOVE_SPECIAL_MAPOBJECT:u16=0xB0;
-pub const MOVE_SUMMON:u16=0xB1;
-pub const SUMMON_ATTACK:u16=0xB2;
-pub const DAMAGE_SUMMON:u16=0xB3;
-pub const SUMMON_SKILL:u16=0xB4;
-pub const SPAWN_DRAGON:u16=0xB5;
-pub const MOVE_DRAGON:u16=0xB6;
-pub const REMOVE_DRAGON:u16=0xB7;
-pub const MOVE_PLAYER:u16=0xB9;
-pub const CLOSE_RANGE_ATTACK:u16=0xBA;
-pub const RANGED_ATTACK:u16=0xBB;
-pub const MAGIC_ATTACK:u16=0xBC;
-pub const ENERGY_ATTACK:u16=0xBD;
-pub const SKILL_EFFECT:u16=0xBE;
-pub const CANCEL_SKILL_EFFECT:u16=0xBF;
-pub const DAMAGE_PLAYER:u16=0xC0;
-pub const FACIAL_EXPRESSION:u16=0xC1;
-pub const SHOW_ITEM_EFFECT:u16=0xC2;
-pub const SHOW_CHAIR:u16=0xC4;
-pub const UPDATE_CHAR_LOOK:u16=0xC5;
-pub const SHOW_FOREIGN_EFFECT:u16=0xC6;
-pub const GIVE_FOREIGN_BUFF:u16=0xC7;
-pub const CANCEL_FOREIGN_BUFF:u16=0xC8;
-pub const UPDATE_PARTYMEMBER_HP:u16=0xC9;
-pub const GUILD_NAME_CHANGED:u16=0xCA;
-pub const GUILD_MARK_CHANGED:u16=0xCB;
-pub const THROW_GRENADE:u16=0xCC;
-pub const CANCEL_CHAIR:u16=0xCD;
-pub const SHOW_ITEM_GAIN_INCHAT:u16=0xCE;
-pub const DOJO_WARP_UP:u16=0xCF;
-pub const LUCKSACK_PASS:u16=0xD0;
-pub const LUCKSACK_FAIL:u16=0xD1;
-pub const MESO_BAG_MESSAGE:u16=0xD2;
-pub const UPDATE_QUEST_INFO:u16=0xD3;
-pub const PLAYER_HINT:u16=0xD6;
-pub const MAKER_RESULT:u16=0xD9;
-pub const KOREAN_EVENT:u16=0xDB;
-pub const OPEN_UI:u16=0xDC;
-pub const LOCK_UI:u16=0xDD;
-pub const DISABLE_UI:u16=0xDE;
-pub const SPAWN_GUIDE:u16=0xDF;
-pub const TALK_GUIDE:u16=0xE0;
-pub const SHOW_COMBO:u16=0xE1;
-pub const COOLDOWN:u16=0xEA;
-pub const SPAWN_MONSTER:u16=0xEC;
-pub const KILL_MONSTER:u16=0xED;
-pub const SPAWN_MONSTER_CONTROL:u16=0xEE;
-pub const MOVE_MONSTER:u16=0xEF;
-pub const MOVE_MONSTER_RESPONSE:u16=0xF0;
-pub const APPLY_MONSTER_STATUS:u16=0xF2;
-pub const CANCEL_MONSTER_STATUS:u16=0xF3;
-pub const RESET_MONSTER_ANIMATION:u16=0xF4;//LOL? o.o
-//Something with mob, but can't figure out00
-pub const DAMAGE_MONSTER:u16=0xF6;
-pub const ARIANT_THING:u16=0xF9;
-pub const SHOW_MONSTER_HP:u16=0xFA;
-pub const CATCH_MONSTER:u16=0xFB;
-pub const CATCH_MONSTER_WITH_ITEM:u16=0xFC;
-pub const SHOW_MAGNET:u16=0xFD;
-pub const SPAWN_NPC:u16=0x101;
-pub const REMOVE_NPC:u16=0x102;
-pub const SPAWN_NPC_REQUEST_CONTROLLER:u16=0x103;
-pub const NPC_ACTION:u16=0x104;
-pub const SET_NPC_SCRIPTABLE:u16=0x107;
-pub const SPAWN_HIRED_MERCHANT:u16=0x109;
-pub const DESTROY_HIRED_MERCHANT:u16=0x10A;
-pub const UPDATE_HIRED_MERCHANT:u16=0x10B;
-pub const DROP_ITEM_FROM_MAPOBJECT:u16=0x10C;
-pub const REMOVE_ITEM_FROM_MAP:u16=0x10D;
-pub const CANNOT_SPAWN_KITE:u16=0x10E;
-pub const SPAWN_KITE:u16=0x10F;
-pub const REMOVE_KITE:u16=0x110;
-pub const SPAWN_MIST:u16=0x111;
-pub const REMOVE_MIST:u16=0x112;
-pub const SPAWN_DOOR:u16=0x113;
-pub const REMOVE_DOOR:u16=0x114;
-pub const REACTOR_HIT:u16=0x115;
-pub const REACTOR_SPAWN:u16=0x117;
-pub const REACTOR_DESTROY:u16=0x118;
-pub const SNOWBALL_STATE:u16=0x119;
-pub const HIT_SNOWBALL:u16=0x11A;
-pub const SNOWBALL_MESSAGE:u16=0x11B;
-pub const LEFT_KNOCK_BACK:u16=0x11C;
-pub const COCONUT_HIT:u16=0x11D;
-pub const COCONUT_SCORE:u16=0x11E;
-pub const GUILD_BOSS_HEALER_MOVE:u16=0x11F;
-pub const GUILD_BOSS_PULLEY_STATE_CHANGE:u16=0x120;
-pub const MONSTER_CARNIVAL_START:u16=0x121;
-pub const MONSTER_CARNIVAL_OBTAINED_CP:u16=0x122;
-pub const MONSTER_CARNIVAL_PARTY_CP:u16=0x123;
-pub const MONSTER_CARNIVAL_SUMMON:u16=0x124;
-pub const MONSTER_CARNIVAL_MESSAGE:u16=0x125;
-pub const MONSTER_CARNIVAL_DIED:u16=0x126;
-pub const MONSTER_CARNIVAL_LEAVE:u16=0x127;
+        SPAWN_PET = 0xA8,
+        MOVE_PET = 0xAA,
+        PET_CHAT = 0xAB,
+        PET_NAMECHANGE = 0xAC,
+        PET_EXCEPTION_LIST = 0xAD,
+        PET_COMMAND = 0xAE,
+        SPAWN_SPECIAL_MAPOBJECT = 0xAF,
+        REMOVE_SPECIAL_MAPOBJECT = 0xB0,
+        MOVE_SUMMON = 0xB1,
+        SUMMON_ATTACK = 0xB2,
+        DAMAGE_SUMMON = 0xB3,
+        SUMMON_SKILL = 0xB4,
+        SPAWN_DRAGON = 0xB5,
+        MOVE_DRAGON = 0xB6,
+        REMOVE_DRAGON = 0xB7,
+        MOVE_PLAYER = 0xB9,
+        CLOSE_RANGE_ATTACK = 0xBA,
+        RANGED_ATTACK = 0xBB,
+        MAGIC_ATTACK = 0xBC,
+        ENERGY_ATTACK = 0xBD,
+        SKILL_EFFECT = 0xBE,
+        CANCEL_SKILL_EFFECT = 0xBF,
+        DAMAGE_PLAYER = 0xC0,
+        FACIAL_EXPRESSION = 0xC1,
+        SHOW_ITEM_EFFECT = 0xC2,
+        SHOW_CHAIR = 0xC4,
+        UPDATE_CHAR_LOOK = 0xC5,
+        SHOW_FOREIGN_EFFECT = 0xC6,
+        GIVE_FOREIGN_BUFF = 0xC7,
+        CANCEL_FOREIGN_BUFF = 0xC8,
+        UPDATE_PARTYMEMBER_HP = 0xC9,
+        GUILD_NAME_CHANGED = 0xCA,
+        GUILD_MARK_CHANGED = 0xCB,
+        THROW_GRENADE = 0xCC,
+        CANCEL_CHAIR = 0xCD,
+        SHOW_ITEM_GAIN_INCHAT = 0xCE,
+        DOJO_WARP_UP = 0xCF,
+        LUCKSACK_PASS = 0xD0,
+        LUCKSACK_FAIL = 0xD1,
+        MESO_BAG_MESSAGE = 0xD2,
+        UPDATE_QUEST_INFO = 0xD3,
+        PLAYER_HINT = 0xD6,
+        MAKER_RESULT = 0xD9,
+        KOREAN_EVENT = 0xDB,
+        OPEN_UI = 0xDC,
+        LOCK_UI = 0xDD,
+        DISABLE_UI = 0xDE,
+        SPAWN_GUIDE = 0xDF,
+        TALK_GUIDE = 0xE0,
+        SHOW_COMBO = 0xE1,
+        COOLDOWN = 0xEA,
+        SPAWN_MONSTER = 0xEC,
+        KILL_MONSTER = 0xED,
+        SPAWN_MONSTER_CONTROL = 0xEE,
+        MOVE_MONSTER = 0xEF,
+        MOVE_MONSTER_RESPONSE = 0xF0,
+        APPLY_MONSTER_STATUS = 0xF2,
+        CANCEL_MONSTER_STATUS = 0xF3,
+        RESET_MONSTER_ANIMATION = 0xF4,//LOL? o.o
+        //Something with mob, but can't figure out00
+        DAMAGE_MONSTER = 0xF6,
+        ARIANT_THING = 0xF9,
+        SHOW_MONSTER_HP = 0xFA,
+        CATCH_MONSTER = 0xFB,
+        CATCH_MONSTER_WITH_ITEM = 0xFC,
+        SHOW_MAGNET = 0xFD,
+        SPAWN_NPC = 0x101,
+        REMOVE_NPC = 0x102,
+        SPAWN_NPC_REQUEST_CONTROLLER = 0x103,
+        NPC_ACTION = 0x104,
+        SET_NPC_SCRIPTABLE = 0x107,
+        SPAWN_HIRED_MERCHANT = 0x109,
+        DESTROY_HIRED_MERCHANT = 0x10A,
+        UPDATE_HIRED_MERCHANT = 0x10B,
+        DROP_ITEM_FROM_MAPOBJECT = 0x10C,
+        REMOVE_ITEM_FROM_MAP = 0x10D,
+        CANNOT_SPAWN_KITE = 0x10E,
+        SPAWN_KITE = 0x10F,
+        REMOVE_KITE = 0x110,
+        SPAWN_MIST = 0x111,
+        REMOVE_MIST = 0x112,
+        SPAWN_DOOR = 0x113,
+        REMOVE_DOOR = 0x114,
+        REACTOR_HIT = 0x115,
+        REACTOR_SPAWN = 0x117,
+        REACTOR_DESTROY = 0x118,
+        SNOWBALL_STATE = 0x119,
+        HIT_SNOWBALL = 0x11A,
+        SNOWBALL_MESSAGE = 0x11B,
+        LEFT_KNOCK_BACK = 0x11C,
+        COCONUT_HIT = 0x11D,
+        COCONUT_SCORE = 0x11E,
+        GUILD_BOSS_HEALER_MOVE = 0x11F,
+        GUILD_BOSS_PULLEY_STATE_CHANGE = 0x120,
+        MONSTER_CARNIVAL_START = 0x121,
+        MONSTER_CARNIVAL_OBTAINED_CP = 0x122,
+        MONSTER_CARNIVAL_PARTY_CP = 0x123,
+        MONSTER_CARNIVAL_SUMMON = 0x124,
+        MONSTER_CARNIVAL_MESSAGE = 0x125,
+        MONSTER_CARNIVAL_DIED = 0x126,
+        MONSTER_CARNIVAL_LEAVE = 0x127,
 
-pub const ARIANT_ARENA_USER_SCORE:u16=0x129;
-pub const SHEEP_RANCH_INFO:u16=0x12B;
-pub const SHEEP_RANCH_CLOTHES:u16=0x12C;
-pub const ARIANT_SCORE:u16=0x12D;
-pub const HORNTAIL_CAVE:u16=0x12E;
-pub const ZAKUM_SHRINE:u16=0x12F;
-pub const NPC_TALK:u16=0x130;
-pub const OPEN_NPC_SHOP:u16=0x131;
-pub const CONFIRM_SHOP_TRANSACTION:u16=0x132;
-pub const ADMIN_SHOP_MESSAGE:u16=0x133;//lame :P
-pub const ADMIN_SHOP:u16=0x134;
-pub const STORAGE:u16=0x135;
-pub const FREDRICK_MESSAGE:u16=0x136;
-pub const FREDRICK:u16=0x137;
-pub const RPS_GAME:u16=0x138;
-pub const MESSENGER:u16=0x139;
-pub const PLAYER_INTERACTION:u16=0x13A;
+        ARIANT_ARENA_USER_SCORE = 0x129,
+        SHEEP_RANCH_INFO = 0x12B,
+        SHEEP_RANCH_CLOTHES = 0x12C,
+        ARIANT_SCORE = 0x12D,
+        HORNTAIL_CAVE = 0x12E,
+        ZAKUM_SHRINE = 0x12F,
+        NPC_TALK = 0x130,
+        OPEN_NPC_SHOP = 0x131,
+        CONFIRM_SHOP_TRANSACTION = 0x132,
+        ADMIN_SHOP_MESSAGE = 0x133,//lame :P
+        ADMIN_SHOP = 0x134,
+        STORAGE = 0x135,
+        FREDRICK_MESSAGE = 0x136,
+        FREDRICK = 0x137,
+        RPS_GAME = 0x138,
+        MESSENGER = 0x139,
+        PLAYER_INTERACTION = 0x13A,
 
-pub const TOURNAMENT:u16=0x13B;
-pub const TOURNAMENT_MATCH_TABLE:u16=0x13C;
-pub const TOURNAMENT_SET_PRIZE:u16=0x13D;
-pub const TOURNAMENT_UEW:u16=0x13E;
-pub const TOURNAMENT_CHARACTERS:u16=0x13F;//they never coded this :|
+        TOURNAMENT = 0x13B,
+        TOURNAMENT_MATCH_TABLE = 0x13C,
+        TOURNAMENT_SET_PRIZE = 0x13D,
+        TOURNAMENT_UEW = 0x13E,
+        TOURNAMENT_CHARACTERS = 0x13F,//they never coded this :|
 
-pub const WEDDING_PROGRESS:u16=0x140;//byte step, int groomid, int brideid
-pub const WEDDING_CEREMONY_END:u16=0x141;
+        WEDDING_PROGRESS = 0x140,//byte step, int groomid, int brideid
+        WEDDING_CEREMONY_END = 0x141,
 
-pub const PARCEL:u16=0x142;
+        PARCEL = 0x142,
 
-pub const CHARGE_PARAM_RESULT:u16=0x143;
-pub const QUERY_CASH_RESULT:u16=0x144;
-pub const CASHSHOP_OPERATION:u16=0x145;
-pub const CASHSHOP_PURCHASE_EXP_CHANGED:u16=0x146;   // found thanks to Arnah :u16=Vertisy)
-pub const CASHSHOP_GIFT_INFO_RESULT:u16=0x147;
-pub const CASHSHOP_CHECK_NAME_CHANGE:u16=0x148;
-pub const CASHSHOP_CHECK_NAME_CHANGE_POSSIBLE_RESULT:u16=0x149;
-pub const CASHSHOP_REGISTER_NEW_CHARACTER_RESULT:u16=0x14A;
-pub const CASHSHOP_CHECK_TRANSFER_WORLD_POSSIBLE_RESULT:u16=0x14B;
-pub const CASHSHOP_GACHAPON_STAMP_RESULT:u16=0x14C;
-pub const CASHSHOP_CASH_ITEM_GACHAPON_RESULT:u16=0x14D;
-pub const CASHSHOP_CASH_GACHAPON_OPEN_RESULT:u16=0x14E;
+        CHARGE_PARAM_RESULT = 0x143,
+        QUERY_CASH_RESULT = 0x144,
+        CASHSHOP_OPERATION = 0x145,
+        CASHSHOP_PURCHASE_EXP_CHANGED = 0x146,   // found thanks to Arnah :u16=Vertisy)
+        CASHSHOP_GIFT_INFO_RESULT = 0x147,
+        CASHSHOP_CHECK_NAME_CHANGE = 0x148,
+        CASHSHOP_CHECK_NAME_CHANGE_POSSIBLE_RESULT = 0x149,
+        CASHSHOP_REGISTER_NEW_CHARACTER_RESULT = 0x14A,
+        CASHSHOP_CHECK_TRANSFER_WORLD_POSSIBLE_RESULT = 0x14B,
+        CASHSHOP_GACHAPON_STAMP_RESULT = 0x14C,
+        CASHSHOP_CASH_ITEM_GACHAPON_RESULT = 0x14D,
+        CASHSHOP_CASH_GACHAPON_OPEN_RESULT = 0x14E,
 
-pub const KEYMAP:u16=0x14F;
-pub const AUTO_HP_POT:u16=0x150;
-pub const AUTO_MP_POT:u16=0x151;
-pub const SEND_TV:u16=0x155;
-pub const REMOVE_TV:u16=0x156;
-pub const ENABLE_TV:u16=0x157;
-pub const MTS_OPERATION2:u16=0x15B;
-pub const MTS_OPERATION:u16=0x15C;
-pub const MAPLELIFE_RESULT:u16=0x15D;
-pub const MAPLELIFE_ERROR:u16=0x15E;
-pub const VICIOUS_HAMMER:u16=0x162;
-pub const VEGA_SCROLL:u16=0x166;
\ No newline at end of file
+        KEYMAP = 0x14F,
+        AUTO_HP_POT = 0x150,
+        AUTO_MP_POT = 0x151,
+        SEND_TV = 0x155,
+        REMOVE_TV = 0x156,
+        ENABLE_TV = 0x157,
+        MTS_OPERATION2 = 0x15B,
+        MTS_OPERATION = 0x15C,
+        MAPLELIFE_RESULT = 0x15D,
+        MAPLELIFE_ERROR = 0x15E,
+        VICIOUS_HAMMER = 0x162,
+        VEGA_SCROLL = 0x166,
+    }
+}
+
+#[cfg(test)]
+mod test_server_ops {
+    use std::collections::HashSet;
+    use std::convert::TryFrom;
+
+    use super::ServerOp;
+
+    #[test]
+    fn test_round_trip() {
+        for op in ServerOp::ALL {
+            assert_eq!(ServerOp::try_from(u16::from(*op)).unwrap(), *op);
+            assert_eq!(ServerOp::from_name(op.name()), Some(*op));
+        }
+        assert_eq!(ServerOp::try_from(0x00).unwrap().to_string(), "LOGIN_STATUS");
+        assert!(ServerOp::try_from(0xFFFF).is_err());
+    }
+
+    #[test]
+    fn test_values_are_unique() {
+        let values = ServerOp::ALL.iter().map(|op| u16::from(*op)).collect::<HashSet<_>>();
+        assert_eq!(values.len(), ServerOp::ALL.len());
+    }
+}
@@ -1,21 +1,23 @@
-use bytes::{Buf, BufMut};
+//! Packets sent by the server.
+
+use std::net::SocketAddr;
+
+use bytes::BufMut;
 use num_traits::FromPrimitive;
 
-use crate::io::{BufRead, Error, InPacket, OutPacket, Result};
-use crate::net::server_ops;
-use crate::time::SystemTime;
+use crate::game::{
+    decode_stats, encode_stats, AvatarLook, CharacterData, CharacterEntry, ForcedStat, InventoryChange, InventoryType,
+    MovePath, Point, Stat,
+};
+use crate::io::{BufRead, BufWrite, Decode, Encode, Error, InPacket, Opcode, OutPacket, Result};
+use crate::net::ServerOp;
+use crate::time::{FileTime, SystemTime};
 
+#[derive(Debug, InPacket, OutPacket)]
+#[opcode(ServerOp::PING)]
 pub struct Ping;
 
-impl OutPacket for Ping {
-    fn serialize(&self) -> Vec<u8> {
-        let mut p = Vec::new();
-        p.put_u16_le(server_ops::PING);
-        p
-    }
-}
-
-#[derive(Debug, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
 pub enum LoginError {
     IDDeletedOrBlocked = 3,
     IncorrectPassword = 4,
@@ -42,6 +44,7 @@ pub enum LoginError {
 pub enum LoginStatus {
     Success {
         id: u32,
+        /// 10 for accounts that have not picked one yet, see `client_packet::SetGender`.
         gender: u8,
         grade: u8,
         sub_grade: u8,
@@ -53,7 +56,7 @@ pub enum LoginStatus {
         request_pin: u8,
         request_pic: u8,
     },
-    Failed(String),
+    Failed(LoginError),
     PermanentBan,
     TemporalBan {
         until: SystemTime,
@@ -61,22 +64,26 @@ pub enum LoginStatus {
     },
 }
 
+impl Opcode for LoginStatus {
+    const OPCODE: u16 = ServerOp::LOGIN_STATUS as u16;
+}
+
 impl InPacket for LoginStatus {
     fn deserialize<B: BufRead>(buf: &mut B) -> Result<Self> {
-        let flag = buf.get_i32_le();
+        let flag = buf.try_get_i32_le()?;
         match flag {
             0 => {
-                let id = buf.get_u32();
-                let gender = buf.get_u8();
-                let grade = buf.get_u8();
-                let sub_grade = buf.get_u8();
-                let country_code = buf.get_u8();
-                let name = buf.get_ascii_string();
-                let quiet_ban_reason = buf.get_u8();
-                let quiet_ban_lift_date = buf.get_time();
-                let creation = buf.get_time();
-                let request_pin = buf.get_u8();
-                let request_pic = buf.get_u8();
+                let id = buf.try_get_u32_le()?;
+                let gender = buf.try_get_u8()?;
+                let grade = buf.try_get_u8()?;
+                let sub_grade = buf.try_get_u8()?;
+                let country_code = buf.try_get_u8()?;
+                let name = buf.try_get_ascii_string()?;
+                let quiet_ban_reason = buf.try_get_u8()?;
+                let quiet_ban_lift_date = buf.try_get_time()?;
+                let creation = buf.try_get_time()?;
+                let request_pin = buf.try_get_u8()?;
+                let request_pic = buf.try_get_u8()?;
                 Ok(LoginStatus::Success {
                     id,
                     gender,
@@ -92,8 +99,8 @@ impl InPacket for LoginStatus {
                 })
             }
             2 => {
-                let ban_reason = buf.get_u8();
-                let until = buf.get_time();
+                let ban_reason = buf.try_get_u8()?;
+                let until = buf.try_get_time()?;
                 if ban_reason == 0 {
                     Ok(LoginStatus::PermanentBan)
                 } else {
@@ -106,7 +113,7 @@ impl InPacket for LoginStatus {
             }
             flag => {
                 let reason: LoginError = FromPrimitive::from_i32(flag).unwrap_or(LoginError::Unknown);
-                Ok(LoginStatus::Failed(format!("{:?}", reason)))
+                Ok(LoginStatus::Failed(reason))
             }
         }
     }
@@ -115,8 +122,1879 @@ impl InPacket for LoginStatus {
 impl OutPacket for LoginStatus {
     fn serialize(&self) -> Vec<u8> {
         let mut p = Vec::new();
-        p.put_u16_le(server_ops::LOGIN_STATUS);
+        p.put_u16_le(ServerOp::LOGIN_STATUS.into());
+
+        p
+    }
+}
+
+/// Answers `SET_GENDER`.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::GENDER_DONE)]
+pub struct GenderDone {
+    pub gender: u8,
+    pub success: bool,
+}
+
+/// Answers `ACCEPT_TOS` on servers that do not send the login result again.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::CONFIRM_EULA_RESULT)]
+pub struct ConfirmEulaResult {
+    pub success: bool,
+}
+
+/// Answers `RELOG`, the client may log in again on the same connection.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::RELOG_RESPONSE)]
+pub struct RelogResponse {
+    pub success: bool,
+}
+
+/// One entry of the server list, sent once per world before `ServerList::End`.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct World {
+    pub id: u8,
+    #[packet(string)]
+    pub name: String,
+    /// Ribbon shown next to the name: 0 none, 1 event, 2 new, 3 hot.
+    pub flag: u8,
+    #[packet(string)]
+    pub event_message: String,
+    /// Event bonus in percent, 100 without an event.
+    pub exp_rate: u16,
+    pub drop_rate: u16,
+    pub block_char_creation: bool,
+    #[packet(list = u8)]
+    pub channels: Vec<Channel>,
+    #[packet(list = u16)]
+    pub balloons: Vec<Balloon>,
+}
+
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct Channel {
+    #[packet(string)]
+    pub name: String,
+    /// Number of players, the client shows it relative to the world's capacity.
+    pub load: i32,
+    pub world: u8,
+    /// Zero-based, one less than the number in `name`.
+    pub id: u8,
+    pub adult: bool,
+}
+
+/// A message bubble over the world selection.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct Balloon {
+    pub x: i16,
+    pub y: i16,
+    #[packet(string)]
+    pub message: String,
+}
+
+/// Answer to `SERVERLIST_REQUEST`, one packet per world followed by `End`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerList {
+    World(World),
+    End,
+}
+
+/// World id marking the end of the server list.
+const SERVER_LIST_END: u8 = 0xFF;
+
+impl Opcode for ServerList {
+    const OPCODE: u16 = ServerOp::SERVERLIST as u16;
+}
+
+impl InPacket for ServerList {
+    fn deserialize<B: BufRead>(buf: &mut B) -> Result<Self> {
+        // The end marker takes the place of the world id, peek so `World` still reads its own id.
+        if buf.peek_u8() == Some(SERVER_LIST_END) {
+            buf.try_get_u8()?;
+            return Ok(ServerList::End);
+        }
+        Ok(ServerList::World(World::decode(buf)?))
+    }
+}
+
+impl OutPacket for ServerList {
+    fn serialize(&self) -> Vec<u8> {
+        let mut p = Vec::new();
+        p.put_u16_le(ServerOp::SERVERLIST.into());
+        match self {
+            ServerList::World(world) => world.encode(&mut p),
+            ServerList::End => p.put_u8(SERVER_LIST_END),
+        }
+        p
+    }
+}
+
+/// Answer to `SERVERSTATUS_REQUEST`.
+#[derive(Debug, InPacket, OutPacket)]
+#[opcode(ServerOp::SERVERSTATUS)]
+pub struct ServerStatus {
+    /// 0 normal, 1 highly populated, 2 full.
+    pub status: u16,
+}
+
+#[derive(Debug, InPacket, OutPacket)]
+#[opcode(ServerOp::LAST_CONNECTED_WORLD)]
+pub struct LastConnectedWorld {
+    pub world: i32,
+}
+
+#[derive(Debug, InPacket, OutPacket)]
+#[opcode(ServerOp::RECOMMENDED_WORLD_MESSAGE)]
+pub struct RecommendedWorldMessage {
+    #[packet(list = u8)]
+    pub worlds: Vec<RecommendedWorld>,
+}
+
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct RecommendedWorld {
+    pub world: i32,
+    #[packet(string)]
+    pub message: String,
+}
+
+/// Answer to `CHARLIST_REQUEST`, the characters of the account on the picked world.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::CHARLIST)]
+pub struct CharList {
+    /// 0 on success.
+    pub status: u8,
+    #[packet(list = u8)]
+    pub characters: Vec<CharacterEntry>,
+    /// 0 to register a PIC, 1 to ask for it, 2 if PICs are disabled.
+    pub pic: u8,
+    pub slots: i32,
+}
+
+/// Answers to the `VIEW_ALL_CHAR` request: the totals first, then one packet per world.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ViewAllChar {
+    Characters {
+        world: u8,
+        characters: Vec<CharacterEntry>,
+        /// 1 to ask for the PIC, 2 if PICs are disabled.
+        pic: u8,
+    },
+    Count {
+        worlds: i32,
+        characters: i32,
+    },
+    /// 2 already connected, 3 unknown error, 5 no characters found.
+    Failed(u8),
+}
+
+impl Opcode for ViewAllChar {
+    const OPCODE: u16 = ServerOp::VIEW_ALL_CHAR as u16;
+}
+
+impl InPacket for ViewAllChar {
+    fn deserialize<B: BufRead>(buf: &mut B) -> Result<Self> {
+        match buf.try_get_u8()? {
+            0 => {
+                let world = buf.try_get_u8()?;
+                let count = buf.try_get_u8()?;
+                let characters = (0..count)
+                    .map(|_| CharacterEntry::decode_view_all(buf))
+                    .collect::<Result<Vec<_>>>()?;
+                let pic = buf.try_get_u8()?;
+                Ok(ViewAllChar::Characters { world, characters, pic })
+            }
+            1 => Ok(ViewAllChar::Count {
+                worlds: buf.try_get_i32_le()?,
+                characters: buf.try_get_i32_le()?,
+            }),
+            code => Ok(ViewAllChar::Failed(code)),
+        }
+    }
+}
+
+impl OutPacket for ViewAllChar {
+    fn serialize(&self) -> Vec<u8> {
+        let mut p = Vec::new();
+        p.put_u16_le(ServerOp::VIEW_ALL_CHAR.into());
+        match self {
+            ViewAllChar::Characters { world, characters, pic } => {
+                p.put_u8(0);
+                p.put_u8(*world);
+                p.put_u8(characters.len() as u8);
+                for character in characters {
+                    character.encode_view_all(&mut p);
+                }
+                p.put_u8(*pic);
+            }
+            ViewAllChar::Count { worlds, characters } => {
+                p.put_u8(1);
+                p.put_i32_le(*worlds);
+                p.put_i32_le(*characters);
+            }
+            ViewAllChar::Failed(code) => p.put_u8(*code),
+        }
+        p
+    }
+}
+
+/// Answer to `CHECK_CHAR_NAME`.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::CHAR_NAME_RESPONSE)]
+pub struct CharNameResponse {
+    #[packet(string)]
+    pub name: String,
+    /// Whether the name is taken or not allowed.
+    pub unavailable: bool,
+}
+
+/// Answer to `CREATE_CHAR`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AddNewCharEntry {
+    Added(Box<CharacterEntry>),
+    Failed(u8),
+}
+
+impl Opcode for AddNewCharEntry {
+    const OPCODE: u16 = ServerOp::ADD_NEW_CHAR_ENTRY as u16;
+}
+
+impl InPacket for AddNewCharEntry {
+    fn deserialize<B: BufRead>(buf: &mut B) -> Result<Self> {
+        match buf.try_get_u8()? {
+            0 => Ok(AddNewCharEntry::Added(Box::new(CharacterEntry::decode(buf)?))),
+            status => Ok(AddNewCharEntry::Failed(status)),
+        }
+    }
+}
+
+impl OutPacket for AddNewCharEntry {
+    fn serialize(&self) -> Vec<u8> {
+        let mut p = Vec::new();
+        p.put_u16_le(ServerOp::ADD_NEW_CHAR_ENTRY.into());
+        match self {
+            AddNewCharEntry::Added(character) => {
+                p.put_u8(0);
+                character.encode(&mut p);
+            }
+            AddNewCharEntry::Failed(status) => p.put_u8(*status),
+        }
+        p
+    }
+}
+
+/// Answer to `DELETE_CHAR`.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::DELETE_CHAR_RESPONSE)]
+pub struct DeleteCharResponse {
+    pub character: i32,
+    /// 0 on success, see `DeleteCharError` for the others.
+    pub status: u8,
+}
+
+/// Why a character could not be deleted, as told by `DeleteCharResponse::status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
+pub enum DeleteCharError {
+    SystemError = 6,
+    TooManyConnections = 10,
+    GuildMaster = 18,
+    IncorrectPic = 20,
+    Engaged = 22,
+    WorldTransfer = 24,
+    FamilyMember = 29,
+    Unknown = 0xFF,
+}
+
+/// Asks for the PIN or tells how the last one went.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::CHECK_PINCODE)]
+pub struct CheckPincode {
+    /// See `PinOperation`.
+    pub mode: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
+pub enum PinOperation {
+    Accepted = 0,
+    /// The account has no PIN yet, the client should register one.
+    Register = 1,
+    /// The PIN sent was wrong.
+    Invalid = 2,
+    SystemError = 3,
+    /// The client should send the PIN.
+    Request = 4,
+}
+
+impl CheckPincode {
+    pub fn operation(&self) -> Option<PinOperation> {
+        FromPrimitive::from_u8(self.mode)
+    }
+}
+
+/// Answer to `REGISTER_PIN`.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::UPDATE_PINCODE)]
+pub struct UpdatePincode {
+    /// 0 when the PIN was registered.
+    pub status: u8,
+}
+
+/// Sent instead of migrating when the PIC given with a character selection is wrong.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::CHECK_SPW_RESULT)]
+pub struct CheckSpwResult {
+    pub status: u8,
+}
+
+/// Sends the client to the channel of the character it picked.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::SERVER_IP)]
+pub struct ServerIp {
+    /// 0 on success.
+    pub status: u16,
+    pub ip: [u8; 4],
+    pub port: u16,
+    pub character: i32,
+    pub reserved: [u8; 5],
+}
+
+impl ServerIp {
+    pub fn addr(&self) -> SocketAddr {
+        SocketAddr::from((self.ip, self.port))
+    }
+}
+
+/// Sends a client in the field to another channel, or to the cash shop.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::CHANGE_CHANNEL)]
+pub struct ChangeChannel {
+    /// Always 1.
+    pub mode: u8,
+    pub ip: [u8; 4],
+    pub port: u16,
+}
+
+impl ChangeChannel {
+    pub fn addr(&self) -> SocketAddr {
+        SocketAddr::from((self.ip, self.port))
+    }
+}
+
+/// Puts the character on a map, with all of its data when it just entered the channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetField {
+    /// Zero-based.
+    pub channel: i32,
+    /// Counts the field changes, which the client checks its requests against.
+    pub field_key: u8,
+    pub notifier: Option<Notifier>,
+    pub entry: FieldEntry,
+    pub server_time: FileTime,
+}
+
+/// A message box the client shows on entering the field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notifier {
+    pub title: String,
+    /// Never empty, the box is left out without lines.
+    pub lines: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldEntry {
+    /// Sent after a migration, the map and position are those of `data.stat`.
+    CharacterInfo {
+        /// Seeds of the client's damage randomizer.
+        seeds: [i32; 3],
+        data: Box<CharacterData>,
+    },
+    /// A map change within the channel.
+    Warp {
+        revive: bool,
+        map: i32,
+        spawn_point: u8,
+        hp: i16,
+        /// Where the character lands instead of the spawn point, when chased to the map by a GM.
+        chase: Option<(i32, i32)>,
+    },
+}
+
+impl Opcode for SetField {
+    const OPCODE: u16 = ServerOp::SET_FIELD as u16;
+}
+
+impl InPacket for SetField {
+    fn deserialize<B: BufRead>(buf: &mut B) -> Result<Self> {
+        let channel = buf.try_get_i32_le()?;
+        let field_key = buf.try_get_u8()?;
+        let character_info = buf.try_get_bool()?;
+        let notifier = match buf.try_get_u16_le()? {
+            0 => None,
+            lines => Some(Notifier {
+                title: buf.try_get_ascii_string()?,
+                lines: (0..lines).map(|_| buf.try_get_ascii_string()).collect::<Result<_>>()?,
+            }),
+        };
+        let entry = if character_info {
+            FieldEntry::CharacterInfo {
+                seeds: Decode::decode(buf)?,
+                data: Box::new(CharacterData::decode(buf)?),
+            }
+        } else {
+            FieldEntry::Warp {
+                revive: buf.try_get_bool()?,
+                map: buf.try_get_i32_le()?,
+                spawn_point: buf.try_get_u8()?,
+                hp: buf.try_get_i16_le()?,
+                chase: if buf.try_get_bool()? { Some((buf.try_get_i32_le()?, buf.try_get_i32_le()?)) } else { None },
+            }
+        };
+        Ok(Self {
+            channel,
+            field_key,
+            notifier,
+            entry,
+            server_time: FileTime::decode(buf)?,
+        })
+    }
+}
+
+impl OutPacket for SetField {
+    fn serialize(&self) -> Vec<u8> {
+        let mut p = Vec::new();
+        p.put_u16_le(ServerOp::SET_FIELD.into());
+        p.put_i32_le(self.channel);
+        p.put_u8(self.field_key);
+        p.put_u8(matches!(self.entry, FieldEntry::CharacterInfo { .. }) as u8);
+        match &self.notifier {
+            // Without lines the count is all there is, like for no box at all.
+            Some(notifier) if !notifier.lines.is_empty() => {
+                p.put_u16_le(notifier.lines.len() as u16);
+                p.put_ascii_string_with_length(&notifier.title);
+                for line in &notifier.lines {
+                    p.put_ascii_string_with_length(line);
+                }
+            }
+            _ => p.put_u16_le(0),
+        }
+        match &self.entry {
+            FieldEntry::CharacterInfo { seeds, data } => {
+                seeds.encode(&mut p);
+                data.encode(&mut p);
+            }
+            FieldEntry::Warp {
+                revive,
+                map,
+                spawn_point,
+                hp,
+                chase,
+            } => {
+                p.put_u8(*revive as u8);
+                p.put_i32_le(*map);
+                p.put_u8(*spawn_point);
+                p.put_i16_le(*hp);
+                match chase {
+                    Some((x, y)) => {
+                        p.put_u8(1);
+                        p.put_i32_le(*x);
+                        p.put_i32_le(*y);
+                    }
+                    None => p.put_u8(0),
+                }
+            }
+        }
+        self.server_time.encode(&mut p);
+        p
+    }
+}
+
+/// Changes to the inventories, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InventoryOperation {
+    /// Whether it answers a request of the client, which waits for it before sending another.
+    pub exclusive: bool,
+    pub changes: Vec<InventoryChange>,
+}
+
+impl InventoryOperation {
+    /// The trailing byte the client reads when worn items changed: 1 when one was taken off, 2 otherwise.
+    fn equipped_change(&self) -> Option<u8> {
+        self.changes.iter().fold(None, |moved, change| match *change {
+            InventoryChange::Move { from, to, .. } if from < 0 || to < 0 => Some(if from < 0 { 1 } else { 2 }),
+            InventoryChange::Remove { slot, .. } if slot < 0 => Some(2),
+            _ => moved,
+        })
+    }
+}
+
+impl Opcode for InventoryOperation {
+    const OPCODE: u16 = ServerOp::INVENTORY_OPERATION as u16;
+}
+
+impl InPacket for InventoryOperation {
+    fn deserialize<B: BufRead>(buf: &mut B) -> Result<Self> {
+        let exclusive = buf.try_get_bool()?;
+        let count = buf.try_get_u8()?;
+        let operation = Self {
+            exclusive,
+            changes: (0..count).map(|_| InventoryChange::decode(buf)).collect::<Result<_>>()?,
+        };
+        if operation.equipped_change().is_some() {
+            buf.try_get_u8()?;
+        }
+        Ok(operation)
+    }
+}
+
+impl OutPacket for InventoryOperation {
+    fn serialize(&self) -> Vec<u8> {
+        let mut p = Vec::new();
+        p.put_u16_le(ServerOp::INVENTORY_OPERATION.into());
+        p.put_u8(self.exclusive as u8);
+        p.put_u8(self.changes.len() as u8);
+        for change in &self.changes {
+            change.encode(&mut p);
+        }
+        if let Some(moved) = self.equipped_change() {
+            p.put_u8(moved);
+        }
+        p
+    }
+}
+
+/// More slots for an inventory.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::INVENTORY_GROW)]
+pub struct InventoryGrow {
+    pub inventory: InventoryType,
+    pub slot_limit: u8,
+}
+
+/// New values of some stats of the character, applied with `CharacterData::apply_stats`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatChanged {
+    /// Whether it answers a request of the client, which waits for it before sending another.
+    pub exclusive: bool,
+    /// In mask order once decoded.
+    pub stats: Vec<(Stat, i64)>,
+}
+
+impl Opcode for StatChanged {
+    const OPCODE: u16 = ServerOp::STAT_CHANGED as u16;
+}
+
+impl InPacket for StatChanged {
+    fn deserialize<B: BufRead>(buf: &mut B) -> Result<Self> {
+        Ok(Self {
+            exclusive: buf.try_get_bool()?,
+            stats: decode_stats(buf)?,
+        })
+    }
+}
+
+impl OutPacket for StatChanged {
+    fn serialize(&self) -> Vec<u8> {
+        let mut p = Vec::new();
+        p.put_u16_le(ServerOp::STAT_CHANGED.into());
+        p.put_u8(self.exclusive as u8);
+        encode_stats(&self.stats, &mut p);
+        p
+    }
+}
+
+/// Overrides some stats until `ForcedStatReset`, whatever the character's own are.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForcedStatSet {
+    pub stats: Vec<(ForcedStat, i64)>,
+}
+
+impl Opcode for ForcedStatSet {
+    const OPCODE: u16 = ServerOp::FORCED_STAT_SET as u16;
+}
+
+impl InPacket for ForcedStatSet {
+    fn deserialize<B: BufRead>(buf: &mut B) -> Result<Self> {
+        Ok(Self {
+            stats: decode_stats(buf)?,
+        })
+    }
+}
+
+impl OutPacket for ForcedStatSet {
+    fn serialize(&self) -> Vec<u8> {
+        let mut p = Vec::new();
+        p.put_u16_le(ServerOp::FORCED_STAT_SET.into());
+        encode_stats(&self.stats, &mut p);
+        p
+    }
+}
+
+/// Ends a `ForcedStatSet`.
+#[derive(Debug, InPacket, OutPacket)]
+#[opcode(ServerOp::FORCED_STAT_RESET)]
+pub struct ForcedStatReset;
+
+/// Another character on the map moved.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::MOVE_PLAYER)]
+pub struct MovePlayer {
+    pub character: i32,
+    pub path: MovePath,
+}
+
+/// A monster moved, as told by the character controlling it.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::MOVE_MONSTER)]
+pub struct MoveMonster {
+    pub object: i32,
+    /// Always 0.
+    pub reserved: u8,
+    /// Whether the monster may use a skill next.
+    pub skill_possible: bool,
+    /// The attack or skill the monster uses while moving, -1 for none.
+    pub action: i8,
+    pub skill: u8,
+    pub skill_level: u8,
+    pub option: u16,
+    pub path: MovePath,
+}
+
+/// A pet moved.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::MOVE_PET)]
+pub struct MovePet {
+    /// Its owner.
+    pub character: i32,
+    pub slot: u8,
+    pub path: MovePath,
+}
+
+/// The emblem of a guild, all zero without one.
+#[derive(Debug, Clone, Default, PartialEq, InPacket, OutPacket)]
+pub struct GuildMark {
+    pub background: i16,
+    pub background_color: u8,
+    pub logo: i16,
+    pub logo_color: u8,
+}
+
+/// Bits of `ForeignBuffs::mask[3]` and `mask[2]` the client shows on other characters.
+const BUFF_DARK_SIGHT: u32 = 0x400;
+const BUFF_SOUL_ARROW: u32 = 0x10000;
+const BUFF_COMBO: u32 = 0x200000;
+const BUFF_SHADOW_PARTNER: u32 = 0x4000000;
+const BUFF_MORPH: u32 = 0x2;
+/// Bit of the first two-state buff in `ForeignBuffs::mask[1]`, the others follow it.
+const TWO_STATE_SHIFT: u32 = 18;
+const TWO_STATE_COUNT: u32 = 7;
+const TWO_STATE_MOUNT: u32 = 3;
+const TWO_STATE_PARTY_BOOSTER: u32 = 4;
+const TWO_STATE_HOMING_BEACON: u32 = 5;
+
+/// A buff other characters see that is either on or off: an energy charge, a dash, a mount, a party
+/// booster, a homing beacon or an undead curse, in the order of their mask bits.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TwoStateBuff {
+    /// The mount item for the mount.
+    pub value: i32,
+    /// The skill or item it comes from.
+    pub reason: i32,
+    /// Client time of its last change.
+    pub updated: i32,
+    /// When the party booster started, 0 on the others.
+    pub started: i32,
+    /// The monster the homing beacon marks, 0 on the others.
+    pub target: i32,
+    /// Seconds it lasts, 0 on the mount and the homing beacon.
+    pub term: u16,
+}
+
+impl TwoStateBuff {
+    fn encode<B: BufWrite>(&self, kind: u32, buf: &mut B) {
+        buf.put_i32_le(self.value);
+        buf.put_i32_le(self.reason);
+        buf.put_i32_le(self.updated);
+        buf.put_u8(0);
+        match kind {
+            TWO_STATE_MOUNT => {}
+            TWO_STATE_HOMING_BEACON => buf.put_i32_le(self.target),
+            _ => {
+                if kind == TWO_STATE_PARTY_BOOSTER {
+                    buf.put_u8(0);
+                    buf.put_i32_le(self.started);
+                }
+                buf.put_u16_le(self.term);
+            }
+        }
+    }
+
+    fn decode<B: BufRead>(kind: u32, buf: &mut B) -> Result<Self> {
+        let mut buff = Self {
+            value: buf.try_get_i32_le()?,
+            reason: buf.try_get_i32_le()?,
+            updated: buf.try_get_i32_le()?,
+            ..Self::default()
+        };
+        // This byte and the one before `started` are always 0 from v83 servers.
+        buf.try_get_u8()?;
+        match kind {
+            TWO_STATE_MOUNT => {}
+            TWO_STATE_HOMING_BEACON => buff.target = buf.try_get_i32_le()?,
+            _ => {
+                if kind == TWO_STATE_PARTY_BOOSTER {
+                    buf.try_get_u8()?;
+                    buff.started = buf.try_get_i32_le()?;
+                }
+                buff.term = buf.try_get_u16_le()?;
+            }
+        }
+        Ok(buff)
+    }
+}
+
+/// The buffs of a character that others see, like `MonsterStats` a mask followed by what its bits carry.
+///
+/// Besides the two-state buffs, bits 18 to 24 of `mask[1]`, only dark sight, soul arrow, shadow partner, the
+/// combo and the morph are known. Masks with other buffs are refused, the size of their values is unknown.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ForeignBuffs {
+    /// Most significant word first, as sent.
+    pub mask: [u32; 4],
+    /// Orbs of the combo attack, with its bit.
+    pub combo: Option<u8>,
+    /// The form shown instead of the look, with its bit.
+    pub morph: Option<i16>,
+    pub defense_attack: u8,
+    pub defense_state: u8,
+    /// One per two-state bit of `mask[1]` in bit order.
+    pub two_state: Vec<TwoStateBuff>,
+}
+
+impl ForeignBuffs {
+    /// The kinds of the two-state buffs set in `mask`, in bit order.
+    fn two_state_kinds(mask: &[u32; 4]) -> impl Iterator<Item = u32> {
+        let bits = mask[1] >> TWO_STATE_SHIFT;
+        (0..TWO_STATE_COUNT).filter(move |kind| bits & (1 << kind) != 0)
+    }
+}
+
+impl Encode for ForeignBuffs {
+    fn encode<B: BufWrite>(&self, buf: &mut B) {
+        self.mask.encode(buf);
+        if let Some(combo) = self.combo {
+            buf.put_u8(combo);
+        }
+        if let Some(morph) = self.morph {
+            buf.put_i16_le(morph);
+        }
+        buf.put_u8(self.defense_attack);
+        buf.put_u8(self.defense_state);
+        for (kind, buff) in Self::two_state_kinds(&self.mask).zip(&self.two_state) {
+            buff.encode(kind, buf);
+        }
+    }
+}
+
+impl Decode for ForeignBuffs {
+    fn decode<B: BufRead>(buf: &mut B) -> Result<Self> {
+        let remaining = buf.remaining();
+        let mask = <[u32; 4]>::decode(buf)?;
+        let two_state_bits = ((1 << TWO_STATE_COUNT) - 1) << TWO_STATE_SHIFT;
+        let known = BUFF_DARK_SIGHT | BUFF_SOUL_ARROW | BUFF_COMBO | BUFF_SHADOW_PARTNER;
+        if mask[0] != 0 || mask[1] & !two_state_bits != 0 || mask[2] & !BUFF_MORPH != 0 || mask[3] & !known != 0 {
+            // The values of other buffs would have to be skipped, without their sizes the rest is lost.
+            return Err(Error::protocol(remaining, format!("Unknown remote buffs {:08X?}", mask)));
+        }
+        let combo = if mask[3] & BUFF_COMBO != 0 { Some(buf.try_get_u8()?) } else { None };
+        let morph = if mask[2] & BUFF_MORPH != 0 { Some(buf.try_get_i16_le()?) } else { None };
+        Ok(Self {
+            mask,
+            combo,
+            morph,
+            defense_attack: buf.try_get_u8()?,
+            defense_state: buf.try_get_u8()?,
+            two_state: Self::two_state_kinds(&mask)
+                .map(|kind| TwoStateBuff::decode(kind, buf))
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+/// Another character appeared on the map.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpawnPlayer {
+    pub character: i32,
+    pub level: u8,
+    pub name: String,
+    /// Empty without a guild.
+    pub guild: String,
+    pub guild_mark: GuildMark,
+    pub buffs: ForeignBuffs,
+    pub job: i16,
+    pub look: AvatarLook,
+    /// How many of the cash item 5110000 the character has, shown with an effect.
+    pub choco_count: i32,
+    /// Item whose effect surrounds the character, 0 for none.
+    pub item_effect: i32,
+    /// The chair the character sits on, 0 for none.
+    pub chair: i32,
+    pub position: Point,
+    pub stance: u8,
+    pub foothold: i16,
+    /// The pets, mount, mini room, chalkboard and rings that follow, kept as sent.
+    pub remote: Vec<u8>,
+}
+
+impl Opcode for SpawnPlayer {
+    const OPCODE: u16 = ServerOp::SPAWN_PLAYER as u16;
+}
+
+impl InPacket for SpawnPlayer {
+    fn deserialize<B: BufRead>(buf: &mut B) -> Result<Self> {
+        Ok(Self {
+            character: buf.try_get_i32_le()?,
+            level: buf.try_get_u8()?,
+            name: buf.try_get_ascii_string()?,
+            guild: buf.try_get_ascii_string()?,
+            guild_mark: GuildMark::decode(buf)?,
+            buffs: ForeignBuffs::decode(buf)?,
+            job: buf.try_get_i16_le()?,
+            look: AvatarLook::decode(buf)?,
+            choco_count: buf.try_get_i32_le()?,
+            item_effect: buf.try_get_i32_le()?,
+            chair: buf.try_get_i32_le()?,
+            position: Point::decode(buf)?,
+            stance: buf.try_get_u8()?,
+            foothold: buf.try_get_i16_le()?,
+            remote: buf.try_get_bytes(buf.remaining())?.to_vec(),
+        })
+    }
+}
+
+impl OutPacket for SpawnPlayer {
+    fn serialize(&self) -> Vec<u8> {
+        let mut p = Vec::new();
+        p.put_u16_le(ServerOp::SPAWN_PLAYER.into());
+        p.put_i32_le(self.character);
+        p.put_u8(self.level);
+        p.put_ascii_string_with_length(&self.name);
+        p.put_ascii_string_with_length(&self.guild);
+        self.guild_mark.encode(&mut p);
+        self.buffs.encode(&mut p);
+        p.put_i16_le(self.job);
+        self.look.encode(&mut p);
+        p.put_i32_le(self.choco_count);
+        p.put_i32_le(self.item_effect);
+        p.put_i32_le(self.chair);
+        self.position.encode(&mut p);
+        p.put_u8(self.stance);
+        p.put_i16_le(self.foothold);
+        p.put_slice(&self.remote);
+        p
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::REMOVE_PLAYER_FROM_MAP)]
+pub struct RemovePlayerFromMap {
+    pub character: i32,
+}
+
+/// A status of a monster, like a freeze or a poison.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+pub struct MonsterStatValue {
+    pub value: i16,
+    /// The skill that caused it.
+    pub reason: i32,
+    pub duration: i16,
+}
+
+/// The statuses of a monster, one value per bit of the mask in bit order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MonsterStats {
+    pub mask: [u32; 4],
+    pub values: Vec<MonsterStatValue>,
+}
+
+impl Encode for MonsterStats {
+    fn encode<B: BufWrite>(&self, buf: &mut B) {
+        self.mask.encode(buf);
+        for value in &self.values {
+            value.encode(buf);
+        }
+    }
+}
+
+impl Decode for MonsterStats {
+    fn decode<B: BufRead>(buf: &mut B) -> Result<Self> {
+        let mask = <[u32; 4]>::decode(buf)?;
+        let count = mask.iter().map(|bits| bits.count_ones()).sum::<u32>();
+        Ok(Self {
+            mask,
+            values: (0..count).map(|_| MonsterStatValue::decode(buf)).collect::<Result<_>>()?,
+        })
+    }
+}
+
+/// A monster as it appears, to every character or to the one controlling it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Monster {
+    /// 5 without a controller, 1 otherwise.
+    pub controller_status: u8,
+    pub id: i32,
+    pub stats: MonsterStats,
+    pub position: Point,
+    pub stance: u8,
+    pub origin_foothold: i16,
+    pub foothold: i16,
+    /// -2 for a new monster, -1 for one already there, others for the effect it appears with.
+    pub appear: i8,
+    /// Sent with the effects that need one.
+    pub appear_option: Option<i32>,
+    pub team: u8,
+    pub item_effect: i32,
+}
+
+/// Whether an `appear` effect of a monster comes with an option.
+fn has_appear_option(appear: i8) -> bool {
+    appear == -3 || appear >= 0
+}
+
+impl Encode for Monster {
+    fn encode<B: BufWrite>(&self, buf: &mut B) {
+        buf.put_u8(self.controller_status);
+        buf.put_i32_le(self.id);
+        self.stats.encode(buf);
+        self.position.encode(buf);
+        buf.put_u8(self.stance);
+        buf.put_i16_le(self.origin_foothold);
+        buf.put_i16_le(self.foothold);
+        buf.put_i8(self.appear);
+        if has_appear_option(self.appear) {
+            buf.put_i32_le(self.appear_option.unwrap_or_default());
+        }
+        buf.put_u8(self.team);
+        buf.put_i32_le(self.item_effect);
+    }
+}
+
+impl Decode for Monster {
+    fn decode<B: BufRead>(buf: &mut B) -> Result<Self> {
+        let controller_status = buf.try_get_u8()?;
+        let id = buf.try_get_i32_le()?;
+        let stats = MonsterStats::decode(buf)?;
+        let position = Point::decode(buf)?;
+        let stance = buf.try_get_u8()?;
+        let origin_foothold = buf.try_get_i16_le()?;
+        let foothold = buf.try_get_i16_le()?;
+        let appear = buf.try_get_i8()?;
+        let appear_option = if has_appear_option(appear) { Some(buf.try_get_i32_le()?) } else { None };
+        Ok(Self {
+            controller_status,
+            id,
+            stats,
+            position,
+            stance,
+            origin_foothold,
+            foothold,
+            appear,
+            appear_option,
+            team: buf.try_get_u8()?,
+            item_effect: buf.try_get_i32_le()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::SPAWN_MONSTER)]
+pub struct SpawnMonster {
+    pub object: i32,
+    pub monster: Monster,
+}
+
+/// Gives the control of a monster to the character, which then sends its moves, or takes it back.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpawnMonsterControl {
+    Control {
+        /// Whether the monster chases the character.
+        aggro: bool,
+        object: i32,
+        monster: Box<Monster>,
+    },
+    Stop {
+        object: i32,
+    },
+}
+
+impl Opcode for SpawnMonsterControl {
+    const OPCODE: u16 = ServerOp::SPAWN_MONSTER_CONTROL as u16;
+}
+
+impl InPacket for SpawnMonsterControl {
+    fn deserialize<B: BufRead>(buf: &mut B) -> Result<Self> {
+        let mode = buf.try_get_u8()?;
+        let object = buf.try_get_i32_le()?;
+        Ok(match mode {
+            0 => SpawnMonsterControl::Stop { object },
+            mode => SpawnMonsterControl::Control {
+                aggro: mode == 2,
+                object,
+                monster: Box::new(Monster::decode(buf)?),
+            },
+        })
+    }
+}
+
+impl OutPacket for SpawnMonsterControl {
+    fn serialize(&self) -> Vec<u8> {
+        let mut p = Vec::new();
+        p.put_u16_le(ServerOp::SPAWN_MONSTER_CONTROL.into());
+        match self {
+            SpawnMonsterControl::Control { aggro, object, monster } => {
+                p.put_u8(if *aggro { 2 } else { 1 });
+                p.put_i32_le(*object);
+                monster.encode(&mut p);
+            }
+            SpawnMonsterControl::Stop { object } => {
+                p.put_u8(0);
+                p.put_i32_le(*object);
+            }
+        }
+        p
+    }
+}
+
+/// A monster died or disappeared.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::KILL_MONSTER)]
+pub struct KillMonster {
+    pub object: i32,
+    /// 0 disappears at once, 1 fades out.
+    pub animation: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::SPAWN_NPC)]
+pub struct SpawnNpc {
+    pub object: i32,
+    pub npc: i32,
+    pub position: Point,
+    /// 1 facing right.
+    pub facing: u8,
+    pub foothold: i16,
+    /// Bounds it walks between.
+    pub rx0: i16,
+    pub rx1: i16,
+    pub visible: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::REMOVE_NPC)]
+pub struct RemoveNpc {
+    pub object: i32,
+}
+
+/// An item or meso on the ground, dropped or already there when the character came.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DropItemFromMapObject {
+    /// 1 falls from `origin`, 2 was already there, 3 disappears as it lands.
+    pub mode: u8,
+    pub object: i32,
+    pub meso: bool,
+    /// The item id, or the amount of meso.
+    pub item: i32,
+    /// The character or party that may loot it first.
+    pub owner: i32,
+    /// 0 for the owner only, 1 its party, 2 anyone, 3 anyone and explosive.
+    pub ownership: u8,
+    pub position: Point,
+    pub source: i32,
+    /// Where it falls from and after how long, unless it was already there.
+    pub origin: Option<(Point, i16)>,
+    /// Items only.
+    pub expiration: Option<FileTime>,
+    /// Whether a monster or a reactor dropped it rather than a character.
+    pub by_monster: bool,
+}
+
+impl Opcode for DropItemFromMapObject {
+    const OPCODE: u16 = ServerOp::DROP_ITEM_FROM_MAPOBJECT as u16;
+}
+
+impl InPacket for DropItemFromMapObject {
+    fn deserialize<B: BufRead>(buf: &mut B) -> Result<Self> {
+        let mode = buf.try_get_u8()?;
+        let object = buf.try_get_i32_le()?;
+        let meso = buf.try_get_bool()?;
+        let item = buf.try_get_i32_le()?;
+        let owner = buf.try_get_i32_le()?;
+        let ownership = buf.try_get_u8()?;
+        let position = Point::decode(buf)?;
+        let source = buf.try_get_i32_le()?;
+        let origin = if mode != 2 { Some((Point::decode(buf)?, buf.try_get_i16_le()?)) } else { None };
+        let expiration = if !meso { Some(FileTime::decode(buf)?) } else { None };
+        Ok(Self {
+            mode,
+            object,
+            meso,
+            item,
+            owner,
+            ownership,
+            position,
+            source,
+            origin,
+            expiration,
+            by_monster: buf.try_get_bool()?,
+        })
+    }
+}
+
+impl OutPacket for DropItemFromMapObject {
+    fn serialize(&self) -> Vec<u8> {
+        let mut p = Vec::new();
+        p.put_u16_le(ServerOp::DROP_ITEM_FROM_MAPOBJECT.into());
+        p.put_u8(self.mode);
+        p.put_i32_le(self.object);
+        p.put_u8(self.meso as u8);
+        p.put_i32_le(self.item);
+        p.put_i32_le(self.owner);
+        p.put_u8(self.ownership);
+        self.position.encode(&mut p);
+        p.put_i32_le(self.source);
+        if self.mode != 2 {
+            let (origin, delay) = self.origin.unwrap_or_default();
+            origin.encode(&mut p);
+            p.put_i16_le(delay);
+        }
+        if !self.meso {
+            self.expiration.unwrap_or(FileTime::NEVER).encode(&mut p);
+        }
+        p.put_u8(self.by_monster as u8);
+        p
+    }
+}
+
+/// A drop disappeared, expired or was looted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoveItemFromMap {
+    /// 0 expires, 1 disappears, 2 looted by a character, 3 explodes, 5 looted by a pet.
+    pub animation: u8,
+    pub object: i32,
+    /// The character that looted it.
+    pub looter: Option<i32>,
+    /// The slot of the pet that looted it.
+    pub pet: Option<u8>,
+}
+
+impl Opcode for RemoveItemFromMap {
+    const OPCODE: u16 = ServerOp::REMOVE_ITEM_FROM_MAP as u16;
+}
+
+impl InPacket for RemoveItemFromMap {
+    fn deserialize<B: BufRead>(buf: &mut B) -> Result<Self> {
+        let animation = buf.try_get_u8()?;
+        let object = buf.try_get_i32_le()?;
+        let looter = if animation >= 2 { Some(buf.try_get_i32_le()?) } else { None };
+        let pet = if animation == 5 { Some(buf.try_get_u8()?) } else { None };
+        Ok(Self {
+            animation,
+            object,
+            looter,
+            pet,
+        })
+    }
+}
+
+impl OutPacket for RemoveItemFromMap {
+    fn serialize(&self) -> Vec<u8> {
+        let mut p = Vec::new();
+        p.put_u16_le(ServerOp::REMOVE_ITEM_FROM_MAP.into());
+        p.put_u8(self.animation);
+        p.put_i32_le(self.object);
+        if self.animation >= 2 {
+            p.put_i32_le(self.looter.unwrap_or_default());
+        }
+        if self.animation == 5 {
+            p.put_u8(self.pet.unwrap_or_default());
+        }
+        p
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::REACTOR_SPAWN)]
+pub struct ReactorSpawn {
+    pub object: i32,
+    pub reactor: i32,
+    pub state: u8,
+    pub position: Point,
+    pub flipped: bool,
+    #[packet(string)]
+    pub name: String,
+}
+
+/// A reactor was hit and went to another state.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::REACTOR_HIT)]
+pub struct ReactorHit {
+    pub object: i32,
+    pub state: u8,
+    pub position: Point,
+    pub stance: i16,
+    pub reserved: u8,
+    pub frame_delay: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::REACTOR_DESTROY)]
+pub struct ReactorDestroy {
+    pub object: i32,
+    pub state: u8,
+    pub position: Point,
+}
 
+/// A mystic door, the one in town going by the same owner.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::SPAWN_DOOR)]
+pub struct SpawnDoor {
+    /// Whether it was just cast rather than already there.
+    pub launched: bool,
+    pub owner: i32,
+    pub position: Point,
+}
+
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::REMOVE_DOOR)]
+pub struct RemoveDoor {
+    pub animation: u8,
+    pub owner: i32,
+}
+
+/// A poison or recovery cloud over an area.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::SPAWN_MIST)]
+pub struct SpawnMist {
+    pub object: i32,
+    /// 0 from a monster, 1 poison, 2 smoke, 4 recovery.
+    pub kind: i32,
+    /// The character or monster that cast it.
+    pub owner: i32,
+    pub skill: i32,
+    pub skill_level: u8,
+    pub delay: i16,
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+    pub reserved: i32,
+}
+
+impl SpawnMist {
+    pub fn center(&self) -> Point {
+        Point::new(((self.left + self.right) / 2) as i16, ((self.top + self.bottom) / 2) as i16)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::REMOVE_MIST)]
+pub struct RemoveMist {
+    pub object: i32,
+}
+
+/// A summon of a character, like a puppet or a bird.
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::SPAWN_SPECIAL_MAPOBJECT)]
+pub struct SpawnSummon {
+    pub owner: i32,
+    pub object: i32,
+    pub skill: i32,
+    pub character_level: u8,
+    pub skill_level: u8,
+    pub position: Point,
+    pub stance: u8,
+    pub foothold: i16,
+    /// 0 stays, 1 follows its owner, others fly around.
+    pub movement: u8,
+    /// 0 for puppets, which only draw attacks.
+    pub attacks: u8,
+    /// 0 when it appears with an animation.
+    pub animation: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::REMOVE_SPECIAL_MAPOBJECT)]
+pub struct RemoveSummon {
+    pub owner: i32,
+    pub object: i32,
+    pub animation: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, InPacket, OutPacket)]
+#[opcode(ServerOp::MOVE_SUMMON)]
+pub struct MoveSummon {
+    pub owner: i32,
+    pub object: i32,
+    pub path: MovePath,
+}
+
+#[cfg(test)]
+mod test_server_packet {
+    use std::io::Cursor;
+
+    use bytes::BufMut;
+
+    use crate::io::{BufRead, BufWrite, Decode, Encode, Error, InPacket, OutPacket};
+    use crate::time::{FileTime, SystemTime};
+
+    use crate::game::test_character::{look, stat};
+    use crate::game::test_character_data::character_data;
+    use crate::game::test_item::bundle;
+    use crate::game::test_movement::every_kind;
+    use crate::game::{CharacterEntry, ForcedStat, InventoryChange, InventoryType, Point, Stat};
+
+    use super::{
+        AddNewCharEntry, Balloon, Channel, CharList, CharNameResponse, CheckPincode, DropItemFromMapObject, FieldEntry,
+        ForcedStatReset, ForcedStatSet, ForeignBuffs, GuildMark, InventoryGrow, InventoryOperation, LoginStatus,
+        Monster, MonsterStatValue, MonsterStats, MoveMonster, MovePet, MovePlayer, MoveSummon, Notifier, PinOperation,
+        RemoveItemFromMap, ServerIp, ServerList, SetField, SpawnMist, SpawnMonster, SpawnMonsterControl, SpawnNpc,
+        SpawnPlayer, SpawnSummon, StatChanged, ViewAllChar, World,
+    };
+
+    fn login_success() -> Vec<u8> {
+        let mut p = Vec::new();
+        p.put_i32_le(0);
+        p.put_u32_le(0x0102);
+        p.put_u8(0);
+        p.put_u8(0);
+        p.put_u8(0);
+        p.put_u8(0);
+        p.put_ascii_string_with_length("admin");
+        p.put_u8(0);
+        p.put_slice(&SystemTime::now().serialize());
+        p.put_slice(&SystemTime::now().serialize());
+        p.put_u8(1);
+        p.put_u8(0);
         p
     }
+
+    #[test]
+    fn test_login_success() {
+        match LoginStatus::deserialize(&mut Cursor::new(login_success())).unwrap() {
+            LoginStatus::Success { id, name, request_pin, .. } => {
+                assert_eq!(id, 0x0102);
+                assert_eq!(name, "admin");
+                assert_eq!(request_pin, 1);
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_truncated_login_status() {
+        let p = login_success();
+        for len in 0..p.len() {
+            assert!(LoginStatus::deserialize(&mut Cursor::new(&p[..len])).is_err(), "len {}", len);
+        }
+    }
+
+    fn scania() -> World {
+        World {
+            id: 0,
+            name: "Scania".to_owned(),
+            flag: 2,
+            event_message: "".to_owned(),
+            exp_rate: 100,
+            drop_rate: 100,
+            block_char_creation: false,
+            channels: vec![Channel {
+                name: "Scania-1".to_owned(),
+                load: 30,
+                world: 0,
+                id: 0,
+                adult: false,
+            }],
+            balloons: vec![Balloon {
+                x: -5,
+                y: 10,
+                message: "Hi".to_owned(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_server_list() {
+        let mut p = vec![0x0A, 0x00, 0x00];
+        p.extend_from_slice(&[0x06, 0x00, b'S', b'c', b'a', b'n', b'i', b'a', 0x02, 0x00, 0x00]);
+        p.extend_from_slice(&[0x64, 0x00, 0x64, 0x00, 0x00, 0x01]);
+        p.extend_from_slice(&[0x08, 0x00, b'S', b'c', b'a', b'n', b'i', b'a', b'-', b'1']);
+        p.extend_from_slice(&[0x1E, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        p.extend_from_slice(&[0x01, 0x00, 0xFB, 0xFF, 0x0A, 0x00, 0x02, 0x00, b'H', b'i']);
+        assert_eq!(ServerList::World(scania()).serialize(), p);
+
+        let mut buf = Cursor::new(&p[2..]);
+        assert_eq!(ServerList::deserialize(&mut buf).unwrap(), ServerList::World(scania()));
+        assert_eq!(buf.remaining(), 0);
+        for len in 2..p.len() {
+            assert!(ServerList::deserialize(&mut Cursor::new(&p[2..len])).is_err(), "len {}", len);
+        }
+
+        assert_eq!(ServerList::End.serialize(), [0x0A, 0x00, 0xFF]);
+        let mut buf = Cursor::new(&[0xFF][..]);
+        assert_eq!(ServerList::deserialize(&mut buf).unwrap(), ServerList::End);
+        assert_eq!(buf.remaining(), 0);
+    }
+
+    fn entry(id: i32) -> CharacterEntry {
+        let mut stat = stat();
+        stat.id = id;
+        CharacterEntry {
+            stat,
+            look: look(),
+            ranking: None,
+        }
+    }
+
+    #[test]
+    fn test_char_list() {
+        let list = CharList {
+            status: 0,
+            characters: vec![entry(1), entry(2)],
+            pic: 2,
+            slots: 3,
+        };
+        let p = list.serialize();
+        let entry_len = entry(1).serialize().len();
+        assert_eq!(&p[..4], [0x0B, 0x00, 0x00, 0x02]);
+        assert_eq!(&p[4 + 2 * entry_len..], [0x02, 0x03, 0x00, 0x00, 0x00]);
+        assert_eq!(CharList::deserialize(&mut Cursor::new(&p[2..])).unwrap(), list);
+    }
+
+    #[test]
+    fn test_view_all_char() {
+        let count = ViewAllChar::Count { worlds: 2, characters: 5 };
+        assert_eq!(count.serialize(), [0x08, 0x00, 0x01, 0x02, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00]);
+
+        let characters = ViewAllChar::Characters {
+            world: 1,
+            characters: vec![entry(1)],
+            pic: 2,
+        };
+        let p = characters.serialize();
+        assert_eq!(p.len(), 2 + 3 + entry(1).serialize().len() - 1 + 1);
+        for packet in [count, characters, ViewAllChar::Failed(5)] {
+            let p = packet.serialize();
+            assert_eq!(ViewAllChar::deserialize(&mut Cursor::new(&p[2..])).unwrap(), packet);
+        }
+    }
+
+    #[test]
+    fn test_character_creation() {
+        let p = [0x0D, 0x00, 0x03, 0x00, b'B', b'o', b'b', 0x01];
+        let response = CharNameResponse::deserialize(&mut Cursor::new(&p[2..])).unwrap();
+        assert_eq!(response, CharNameResponse { name: "Bob".to_owned(), unavailable: true });
+        assert_eq!(response.serialize(), p);
+
+        let added = AddNewCharEntry::Added(Box::new(entry(3)));
+        let p = added.serialize();
+        assert_eq!(&p[..3], [0x0E, 0x00, 0x00]);
+        assert_eq!(AddNewCharEntry::deserialize(&mut Cursor::new(&p[2..])).unwrap(), added);
+        assert_eq!(AddNewCharEntry::deserialize(&mut Cursor::new(&[0x0A][..])).unwrap(), AddNewCharEntry::Failed(10));
+    }
+
+    #[test]
+    fn test_pin_operation() {
+        let check = CheckPincode::deserialize(&mut Cursor::new(&[0x04][..])).unwrap();
+        assert_eq!(check.operation(), Some(PinOperation::Request));
+        assert_eq!(check.serialize(), [0x06, 0x00, 0x04]);
+        assert_eq!(CheckPincode { mode: 9 }.operation(), None);
+    }
+
+    #[test]
+    fn test_server_ip() {
+        let p = [
+            0x0C, 0x00, 0x00, 0x00, 127, 0, 0, 1, 0x89, 0x21, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let server_ip = ServerIp::deserialize(&mut Cursor::new(&p[2..])).unwrap();
+        assert_eq!(server_ip.addr(), "127.0.0.1:8585".parse().unwrap());
+        assert_eq!(server_ip.character, 7);
+        assert_eq!(server_ip.serialize(), p);
+    }
+
+    #[test]
+    fn test_set_field_warp() {
+        let warp = SetField {
+            channel: 1,
+            field_key: 0,
+            notifier: None,
+            entry: FieldEntry::Warp {
+                revive: false,
+                map: 100000000,
+                spawn_point: 2,
+                hp: 300,
+                chase: None,
+            },
+            server_time: FileTime::ZERO,
+        };
+        let p = [
+            0x7D, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xE1, 0xF5, 0x05, 0x02, 0x2C, 0x01,
+            0x00, 0x00, 0x40, 0xE0, 0xFD, 0x3B, 0x37, 0x4F, 0x01,
+        ];
+        assert_eq!(warp.serialize(), p);
+        assert_eq!(SetField::deserialize(&mut Cursor::new(&p[2..])).unwrap(), warp);
+
+        let chased = SetField {
+            entry: FieldEntry::Warp {
+                revive: false,
+                map: 100000000,
+                spawn_point: 0,
+                hp: 300,
+                chase: Some((-120, 34)),
+            },
+            ..warp
+        };
+        let p = chased.serialize();
+        assert_eq!(&p[18..27], [0x01, 0x88, 0xFF, 0xFF, 0xFF, 0x22, 0x00, 0x00, 0x00]);
+        assert_eq!(SetField::deserialize(&mut Cursor::new(&p[2..])).unwrap(), chased);
+    }
+
+    #[test]
+    fn test_set_field_character_info() {
+        let info = SetField {
+            channel: 0,
+            field_key: 1,
+            notifier: Some(Notifier {
+                title: "Notice".to_owned(),
+                lines: vec!["Welcome".to_owned()],
+            }),
+            entry: FieldEntry::CharacterInfo {
+                seeds: [1, 2, 3],
+                data: Box::new(character_data()),
+            },
+            server_time: FileTime::from_unix_millis(1_600_000_000_000),
+        };
+        let p = info.serialize();
+        let mut expected = vec![0x7D, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01, 0x01, 0x00];
+        expected.extend_from_slice(&[0x06, 0x00, b'N', b'o', b't', b'i', b'c', b'e']);
+        expected.extend_from_slice(&[0x07, 0x00, b'W', b'e', b'l', b'c', b'o', b'm', b'e']);
+        expected.extend_from_slice(&[1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0]);
+        expected.extend_from_slice(&character_data().serialize());
+        expected.extend_from_slice(&(116_444_736_000_000_000i64 + 16_000_000_000_000_000).to_le_bytes());
+        assert_eq!(p, expected);
+        assert_eq!(SetField::deserialize(&mut Cursor::new(&p[2..])).unwrap(), info);
+    }
+
+    #[test]
+    fn test_set_field_empty_notifier() {
+        let warp = SetField {
+            channel: 1,
+            field_key: 0,
+            notifier: None,
+            entry: FieldEntry::Warp {
+                revive: false,
+                map: 100000000,
+                spawn_point: 2,
+                hp: 300,
+                chase: None,
+            },
+            server_time: FileTime::ZERO,
+        };
+        let empty = SetField {
+            notifier: Some(Notifier {
+                title: "Notice".to_owned(),
+                lines: Vec::new(),
+            }),
+            ..warp.clone()
+        };
+        let p = empty.serialize();
+        assert_eq!(p, warp.serialize());
+        assert_eq!(SetField::deserialize(&mut Cursor::new(&p[2..])).unwrap(), warp);
+    }
+
+    #[test]
+    fn test_inventory_operation() {
+        let looted = InventoryOperation {
+            exclusive: false,
+            changes: vec![
+                InventoryChange::Quantity {
+                    inventory: InventoryType::Use,
+                    slot: 1,
+                    quantity: 150,
+                },
+                InventoryChange::Add {
+                    inventory: InventoryType::Use,
+                    slot: 2,
+                    item: bundle(2000000, 50),
+                },
+            ],
+        };
+        let p = looted.serialize();
+        assert_eq!(&p[..10], [0x1D, 0x00, 0x00, 0x02, 0x01, 0x02, 0x01, 0x00, 0x96, 0x00]);
+        assert_eq!(p.len(), 14 + bundle(2000000, 50).serialize().len());
+        assert_eq!(InventoryOperation::deserialize(&mut Cursor::new(&p[2..])).unwrap(), looted);
+
+        // Taking off an item ends with the equipped change.
+        let p = [0x1D, 0x00, 0x01, 0x01, 0x02, 0x01, 0xF5, 0xFF, 0x03, 0x00, 0x01];
+        let unequipped = InventoryOperation {
+            exclusive: true,
+            changes: vec![InventoryChange::Move {
+                inventory: InventoryType::Equip,
+                from: -11,
+                to: 3,
+            }],
+        };
+        let mut buf = Cursor::new(&p[2..]);
+        assert_eq!(InventoryOperation::deserialize(&mut buf).unwrap(), unequipped);
+        assert_eq!(buf.remaining(), 0);
+        assert_eq!(unequipped.serialize(), p);
+        assert!(InventoryOperation::deserialize(&mut Cursor::new(&p[2..p.len() - 1])).is_err());
+
+        let grow = InventoryGrow {
+            inventory: InventoryType::Etc,
+            slot_limit: 28,
+        };
+        assert_eq!(grow.serialize(), [0x1E, 0x00, 0x04, 0x1C]);
+        assert_eq!(InventoryGrow::deserialize(&mut Cursor::new(&[0x04, 0x1C][..])).unwrap(), grow);
+    }
+
+    #[test]
+    fn test_stat_changed() {
+        let p = [0x1F, 0x00, 0x01, 0x00, 0x44, 0x01, 0x00, 0xFF, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00];
+        let changed = StatChanged::deserialize(&mut Cursor::new(&p[2..])).unwrap();
+        assert_eq!(
+            changed,
+            StatChanged {
+                exclusive: true,
+                stats: vec![(Stat::Hp, 255), (Stat::Ap, 5), (Stat::Exp, 0)],
+            }
+        );
+        assert_eq!(changed.serialize(), p);
+
+        let forced = ForcedStatSet {
+            stats: vec![(ForcedStat::Speed, 120), (ForcedStat::Str, 999)],
+        };
+        assert_eq!(forced.serialize(), [0x22, 0x00, 0x01, 0x04, 0x00, 0x00, 0xE7, 0x03, 0x78]);
+        let decoded = ForcedStatSet::deserialize(&mut Cursor::new(&forced.serialize()[2..])).unwrap();
+        assert_eq!(decoded.stats, [(ForcedStat::Str, 999), (ForcedStat::Speed, 120)]);
+        assert_eq!(ForcedStatReset.serialize(), [0x23, 0x00]);
+    }
+
+    #[test]
+    fn test_movement() {
+        let path = every_kind().serialize();
+        let mut p = vec![0xB9, 0x00, 0x07, 0x00, 0x00, 0x00];
+        p.extend_from_slice(&path);
+        let moved = MovePlayer::deserialize(&mut Cursor::new(&p[2..])).unwrap();
+        assert_eq!(moved, MovePlayer { character: 7, path: every_kind() });
+        assert_eq!(moved.serialize(), p);
+
+        let mut p = vec![0xEF, 0x00, 0x64, 0x00, 0x00, 0x00, 0x00, 0x01, 0xFF, 0x00, 0x00, 0x00, 0x00];
+        p.extend_from_slice(&path);
+        let mut buf = Cursor::new(&p[2..]);
+        let monster = MoveMonster::deserialize(&mut buf).unwrap();
+        assert_eq!((monster.object, monster.skill_possible, monster.action), (100, true, -1));
+        assert_eq!(monster.path, every_kind());
+        assert_eq!(buf.remaining(), 0);
+        assert_eq!(monster.serialize(), p);
+
+        let mut p = vec![0xAA, 0x00, 0x07, 0x00, 0x00, 0x00, 0x01];
+        p.extend_from_slice(&path);
+        let pet = MovePet::deserialize(&mut Cursor::new(&p[2..])).unwrap();
+        assert_eq!((pet.character, pet.slot), (7, 1));
+        assert_eq!(pet.serialize(), p);
+    }
+
+    #[test]
+    fn test_spawn_player() {
+        // As v83 servers send it: every two-state buff set, a mount and the same update time on each.
+        let mut p = vec![0xA0, 0x00, 0x07, 0x00, 0x00, 0x00, 0x0A, 0x03, 0x00];
+        p.extend_from_slice(b"bot");
+        p.extend_from_slice(&[0x00, 0x00, 0x01, 0x00, 0x02, 0x03, 0x00, 0x04]);
+        p.extend_from_slice(&[0, 0, 0, 0, 0x00, 0x00, 0xFC, 0x01, 0, 0, 0, 0, 0, 0, 0, 0]);
+        p.extend_from_slice(&[0; 10]);
+        p.put_i32_le(0x1234);
+        p.extend_from_slice(&[0; 11]);
+        p.put_i32_le(0x1234);
+        p.extend_from_slice(&[0; 11]);
+        p.put_i32_le(0x1234);
+        p.extend_from_slice(&[0; 3]);
+        p.extend_from_slice(&[0xB0, 0x05, 0x1D, 0x00, 0xEC, 0x03, 0x00, 0x00]);
+        p.put_i32_le(0x1234);
+        p.extend_from_slice(&[0; 9]);
+        p.put_i32_le(0x1234);
+        p.extend_from_slice(&[0; 16]);
+        p.put_i32_le(0x1234);
+        p.extend_from_slice(&[0; 13]);
+        p.put_i32_le(0x1234);
+        p.extend_from_slice(&[0; 3]);
+        p.put_i16_le(312);
+        p.extend_from_slice(&look().serialize());
+        p.extend_from_slice(&[0; 12]);
+        p.extend_from_slice(&[0x2C, 0x01, 0x9C, 0xFF, 0x05, 0x00, 0x00]);
+        p.extend_from_slice(&[0x00, 0x00]);
+        let mut buf = Cursor::new(&p[2..]);
+        let player = SpawnPlayer::deserialize(&mut buf).unwrap();
+        assert_eq!(buf.remaining(), 0);
+        assert_eq!((player.character, player.level, player.name.as_str()), (7, 10, "bot"));
+        assert!(player.guild.is_empty());
+        assert_eq!(player.guild_mark, GuildMark { background: 1, background_color: 2, logo: 3, logo_color: 4 });
+        assert_eq!(player.buffs.two_state.len(), 7);
+        assert!(player.buffs.two_state.iter().all(|buff| buff.updated == 0x1234));
+        assert_eq!((player.buffs.two_state[3].value, player.buffs.two_state[3].reason), (1902000, 1004));
+        assert_eq!((player.job, &player.look), (312, &look()));
+        assert_eq!((player.position, player.stance, player.foothold), (Point::new(300, -100), 5, 0));
+        assert_eq!(player.remote, [0x00, 0x00]);
+        assert_eq!(player.serialize(), p);
+
+        let morphed = ForeignBuffs {
+            mask: [0, 0, 0x02, 0x200400],
+            combo: Some(3),
+            morph: Some(1000),
+            ..ForeignBuffs::default()
+        };
+        let mut p = Vec::new();
+        morphed.encode(&mut p);
+        assert_eq!(p[16..], [0x03, 0xE8, 0x03, 0x00, 0x00]);
+        assert_eq!(ForeignBuffs::decode(&mut Cursor::new(&p)).unwrap(), morphed);
+
+        let p = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x80, 0, 0, 0];
+        let err = ForeignBuffs::decode(&mut Cursor::new(&p[..])).unwrap_err().locate(p.len());
+        assert!(matches!(err, Error::Protocol { offset: 0, .. }));
+    }
+
+    fn snail() -> Monster {
+        Monster {
+            controller_status: 5,
+            id: 100100,
+            stats: MonsterStats::default(),
+            position: Point::new(-50, 120),
+            stance: 5,
+            origin_foothold: 12,
+            foothold: 12,
+            appear: -2,
+            appear_option: None,
+            team: 0xFF,
+            item_effect: 0,
+        }
+    }
+
+    #[test]
+    fn test_monsters() {
+        let spawn = SpawnMonster { object: 1000, monster: snail() };
+        let p = spawn.serialize();
+        let expected = [
+            0xEC, 0x00, 0xE8, 0x03, 0x00, 0x00, 0x05, 0x04, 0x87, 0x01, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0xCE, 0xFF, 0x78, 0x00, 0x05, 0x0C, 0x00, 0x0C, 0x00, 0xFE, 0xFF, 0x00, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(p, expected);
+        assert_eq!(SpawnMonster::deserialize(&mut Cursor::new(&p[2..])).unwrap(), spawn);
+
+        // A status value per mask bit, and an option with the appear effects that take one.
+        let mut monster = snail();
+        monster.stats = MonsterStats {
+            mask: [0x0000_0101, 0, 0, 0],
+            values: vec![
+                MonsterStatValue { value: 10, reason: 2101003, duration: 5 },
+                MonsterStatValue { value: 1, reason: 2201004, duration: 3 },
+            ],
+        };
+        monster.appear = 3;
+        monster.appear_option = Some(1001);
+        let control = SpawnMonsterControl::Control { aggro: true, object: 1000, monster: Box::new(monster) };
+        let p = control.serialize();
+        assert_eq!(&p[..3], [0xEE, 0x00, 0x02]);
+        assert_eq!(p.len(), 3 + 4 + expected.len() - 6 + 2 * 8 + 4);
+        let mut buf = Cursor::new(&p[2..]);
+        assert_eq!(SpawnMonsterControl::deserialize(&mut buf).unwrap(), control);
+        assert_eq!(buf.remaining(), 0);
+
+        let stop = SpawnMonsterControl::Stop { object: 1000 };
+        assert_eq!(stop.serialize(), [0xEE, 0x00, 0x00, 0xE8, 0x03, 0x00, 0x00]);
+        assert_eq!(SpawnMonsterControl::deserialize(&mut Cursor::new(&stop.serialize()[2..])).unwrap(), stop);
+    }
+
+    #[test]
+    fn test_npc() {
+        let p = [
+            0x01, 0x01, 0x64, 0x00, 0x00, 0x00, 0x48, 0x6C, 0x8D, 0x00, 0x2C, 0x01, 0x9C, 0xFF, 0x01, 0x03, 0x00,
+            0xF4, 0x01, 0x64, 0x00, 0x01,
+        ];
+        let npc = SpawnNpc::deserialize(&mut Cursor::new(&p[2..])).unwrap();
+        assert_eq!((npc.object, npc.npc, npc.position), (100, 9268296, Point::new(300, -100)));
+        assert_eq!((npc.rx0, npc.rx1, npc.visible), (500, 100, true));
+        assert_eq!(npc.serialize(), p);
+    }
+
+    #[test]
+    fn test_drops() {
+        let meso = DropItemFromMapObject {
+            mode: 1,
+            object: 200,
+            meso: true,
+            item: 50,
+            owner: 7,
+            ownership: 0,
+            position: Point::new(10, 20),
+            source: 1000,
+            origin: Some((Point::new(10, 0), 100)),
+            expiration: None,
+            by_monster: true,
+        };
+        let expected = [
+            0x0C, 0x01, 0x01, 0xC8, 0x00, 0x00, 0x00, 0x01, 0x32, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x00,
+            0x0A, 0x00, 0x14, 0x00, 0xE8, 0x03, 0x00, 0x00, 0x0A, 0x00, 0x00, 0x00, 0x64, 0x00, 0x01,
+        ];
+        assert_eq!(meso.serialize(), expected);
+        assert_eq!(DropItemFromMapObject::deserialize(&mut Cursor::new(&expected[2..])).unwrap(), meso);
+
+        // Already on the ground: no origin, but an expiration for items.
+        let item = DropItemFromMapObject {
+            mode: 2,
+            meso: false,
+            item: 4000000,
+            origin: None,
+            expiration: Some(FileTime::NEVER),
+            ..meso
+        };
+        let p = item.serialize();
+        assert_eq!(p.len(), expected.len() - 6 + 8);
+        let mut buf = Cursor::new(&p[2..]);
+        assert_eq!(DropItemFromMapObject::deserialize(&mut buf).unwrap(), item);
+        assert_eq!(buf.remaining(), 0);
+
+        let expired = [0x0D, 0x01, 0x00, 0xC8, 0x00, 0x00, 0x00];
+        let removed = RemoveItemFromMap::deserialize(&mut Cursor::new(&expired[2..])).unwrap();
+        assert_eq!(removed, RemoveItemFromMap { animation: 0, object: 200, looter: None, pet: None });
+        assert_eq!(removed.serialize(), expired);
+        let by_pet = [0x0D, 0x01, 0x05, 0xC8, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x01];
+        let removed = RemoveItemFromMap::deserialize(&mut Cursor::new(&by_pet[2..])).unwrap();
+        assert_eq!((removed.looter, removed.pet), (Some(7), Some(1)));
+        assert_eq!(removed.serialize(), by_pet);
+    }
+
+    #[test]
+    fn test_mist_and_summon() {
+        let p = [
+            0x11, 0x01, 0x2C, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x1B, 0x36, 0x20,
+            0x00, 0x14, 0x08, 0x00, 0x9C, 0xFF, 0xFF, 0xFF, 0x38, 0xFF, 0xFF, 0xFF, 0x2C, 0x01, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let mist = SpawnMist::deserialize(&mut Cursor::new(&p[2..])).unwrap();
+        assert_eq!((mist.object, mist.kind, mist.owner, mist.skill), (300, 1, 7, 2111003));
+        assert_eq!(mist.center(), Point::new(100, -100));
+        assert_eq!(mist.serialize(), p);
+
+        let p = [
+            0xAF, 0x00, 0x07, 0x00, 0x00, 0x00, 0x90, 0x01, 0x00, 0x00, 0x5A, 0x78, 0x2F, 0x00, 0x46, 0x14, 0x0A,
+            0x00, 0x14, 0x00, 0x04, 0x0C, 0x00, 0x01, 0x01, 0x00,
+        ];
+        let summon = SpawnSummon::deserialize(&mut Cursor::new(&p[2..])).unwrap();
+        assert_eq!((summon.owner, summon.object, summon.skill), (7, 400, 3111002));
+        assert_eq!((summon.position, summon.movement), (Point::new(10, 20), 1));
+        assert_eq!(summon.serialize(), p);
+
+        let mut p = vec![0xB1, 0x00, 0x07, 0x00, 0x00, 0x00, 0x90, 0x01, 0x00, 0x00];
+        p.extend_from_slice(&every_kind().serialize());
+        let moved = MoveSummon::deserialize(&mut Cursor::new(&p[2..])).unwrap();
+        assert_eq!(moved, MoveSummon { owner: 7, object: 400, path: every_kind() });
+        assert_eq!(moved.serialize(), p);
+    }
 }
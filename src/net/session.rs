@@ -1,15 +1,23 @@
-use std::io::{Cursor, Read, Write};
+use std::io::{Read, Write};
 use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
 
-use bytes::{Buf, BufMut, Bytes};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 
-use crate::io::{BufRead, BufWrite, Error, InPacket, OutPacket, Result};
+use crate::io::{BufWrite, Encode, Error, InPacket, OutPacket, Result};
 
-use super::codec::{Decoder, Encoder, MAX_PACKET_SIZE};
+use super::codec::{Decoder, Encoder};
+use super::handshake::{ConnectOptions, HandshakeReader};
+use super::profile::{OpcodeTable, VersionProfile};
 
-#[derive(Debug, Clone)]
+/// The handshake the server sends right after accepting a connection.
+///
+/// IVs are seen from the side holding the `Proto`: the server's `send_iv` is the client's `recv_iv`.
+/// It is read from after its length prefix, but serialized with it, as the server sends it.
+#[derive(Debug, Clone, InPacket)]
 pub struct Proto {
     pub version: u16,
+    #[packet(string)]
     pub patch: String,
     pub send_iv: [u8; 4],
     pub recv_iv: [u8; 4],
@@ -17,6 +25,7 @@ pub struct Proto {
 }
 
 impl Proto {
+    /// The same handshake seen from the other end of the connection.
     pub fn remote(&self) -> Self {
         Self {
             version: self.version,
@@ -30,163 +39,542 @@ impl Proto {
 
 impl OutPacket for Proto {
     fn serialize(&self) -> Vec<u8> {
-        let packet_len = std::mem::size_of::<u16>() // version
-            + (std::mem::size_of::<u16>() + self.patch.len())  // patch
-            + self.recv_iv.len()
-            + self.send_iv.len()
-            + std::mem::size_of::<u8>(); // locale
-
-        let mut out = Vec::new();
-        out.put_u16_le(packet_len as u16);
-        out.put_u16_le(self.version);
-        out.put_ascii_string_with_length(self.patch.as_str());
-        out.put_slice(&self.send_iv);
-        out.put_slice(&self.recv_iv);
-        out.put_u8(self.locale);
+        let mut body = Vec::new();
+        self.version.encode(&mut body);
+        body.put_ascii_string_with_length(&self.patch);
+        self.send_iv.encode(&mut body);
+        self.recv_iv.encode(&mut body);
+        self.locale.encode(&mut body);
 
+        let mut out = Vec::with_capacity(2 + body.len());
+        out.put_u16_le(body.len() as u16);
+        out.extend_from_slice(&body);
         out
     }
 }
 
-impl InPacket for Proto {
-    fn deserialize<B: BufRead>(buf: &mut B) -> Result<Self> {
-        let version = buf.get_u16_le();
-        let patch = buf.get_ascii_string();
-
-        let mut send_iv = [0u8; 4];
-        buf.copy_to_slice(&mut send_iv);
-
-        let mut recv_iv = [0u8; 4];
-        buf.copy_to_slice(&mut recv_iv);
-
-        let locale = buf.get_u8();
-
-        Ok(Proto {
-            version,
-            patch,
-            send_iv,
-            recv_iv,
-            locale,
-        })
-    }
-}
-
-pub struct Session {
-    socket: TcpStream,
+/// A connection over a nonblocking socket.
+///
+/// Packets passed in and out start with their opcode in the crate's numbering, the session
+/// translates them according to its `VersionProfile`. Outgoing bytes the socket cannot take yet stay
+/// queued until the next `send` or `flush`.
+///
+/// `recv` never blocks, `recv_timeout` waits for a packet. To wait on many sessions at once, hand
+/// them to a `Poller`, which turns them into sessions over `mio` sockets.
+pub struct Session<S = TcpStream> {
+    socket: S,
     proto: Proto,
     enc: Encoder,
     dec: Decoder,
-    buf: [u8; MAX_PACKET_SIZE],
-    pos: usize,
+    outgoing: OpcodeTable,
+    incoming: OpcodeTable,
+    pending: BytesMut,
+    closed: bool,
 }
 
-impl std::fmt::Display for Session {
+impl<S: std::fmt::Debug> std::fmt::Display for Session<S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self.socket)
     }
 }
 
-impl std::fmt::Debug for Session {
+impl<S: std::fmt::Debug> std::fmt::Debug for Session<S> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match self {
-            Session { socket, proto, enc, dec, buf, pos } => {
-                f.debug_struct("Session")
-                    .field("socket", socket)
-                    .field("proto", proto)
-                    .field("enc", enc)
-                    .field("dec", dec)
-                    .field("pos", pos)
-                    .finish()
-            }
-        }
+        f.debug_struct("Session")
+            .field("socket", &self.socket)
+            .field("proto", &self.proto)
+            .field("enc", &self.enc)
+            .field("dec", &self.dec)
+            .field("pending", &self.pending.len())
+            .finish()
     }
 }
 
 impl Session {
-    pub fn connect_server<A: ToSocketAddrs>(addr: A) -> Result<Self> {
-        let mut socket = TcpStream::connect(addr)?;
-        let mut buf = [0u8; MAX_PACKET_SIZE];
+    /// Connects to a server and waits for its handshake, which must match `options.profile`.
+    pub fn connect_server<A: ToSocketAddrs>(addr: A, options: &ConnectOptions) -> Result<Self> {
+        let mut socket = connect_timeout(addr, options.timeout)?;
+        let deadline = Instant::now() + options.timeout;
 
-        let len = socket.read(&mut buf)?;
-        let mut reader = Bytes::copy_from_slice(&buf[..len]);
-        let handshake_body_size = reader.get_u16_le() as usize;
-        if reader.remaining() != handshake_body_size {
-            return Err(Error::from(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                "Wrong proto size",
-            )));
-        }
+        let mut handshake = HandshakeReader::new();
+        let mut chunk = [0u8; 256];
+        let proto = loop {
+            if let Some(proto) = handshake.parse()? {
+                break proto;
+            }
 
-        let proto = Proto::deserialize(&mut reader)?;
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(handshake_timed_out());
+            }
+            socket.set_read_timeout(Some(deadline - now))?;
+            match socket.read(&mut chunk) {
+                Ok(0) => return Err(Error::Handshake("Connection closed during handshake".to_owned())),
+                Ok(len) => handshake.append(&chunk[..len]),
+                Err(e) if is_timeout(&e) => return Err(handshake_timed_out()),
+                Err(e) => return Err(Error::from(e)),
+            }
+        };
         debug!("{:#?}", proto);
+        options.verify(&proto)?;
+
         let enc = Encoder::client(proto.version, proto.send_iv);
-        let dec = Decoder::client(proto.version, proto.recv_iv);
+        let mut dec = Decoder::client(proto.version, proto.recv_iv);
+        dec.append(&handshake.into_remaining());
 
+        socket.set_read_timeout(None)?;
         socket.set_nonblocking(true)?;
 
-        let pos = len - 2 - handshake_body_size;
-
         Ok(Session {
             socket,
             proto,
             enc,
             dec,
-            buf,
-            pos,
+            outgoing: options.profile.client.clone(),
+            incoming: options.profile.server.clone(),
+            pending: BytesMut::new(),
+            closed: false,
         })
     }
 
-    pub fn accept_client(mut socket: TcpStream, proto: Proto) -> std::io::Result<Self> {
+    /// Serves an accepted connection, greeting the client with `proto`.
+    pub fn accept_client(mut socket: TcpStream, proto: Proto, profile: &VersionProfile) -> std::io::Result<Self> {
         let enc = Encoder::server(proto.version, proto.send_iv);
         let dec = Decoder::server(proto.version, proto.recv_iv);
 
         socket.set_nonblocking(true)?;
-        socket.write_all(proto.serialize().as_ref())?;
+        socket.write_all(proto.remote().serialize().as_ref())?;
 
         Ok(Session {
             socket,
             proto,
             enc,
             dec,
-            buf: [0u8; MAX_PACKET_SIZE],
-            pos: 0,
+            outgoing: profile.server.clone(),
+            incoming: profile.client.clone(),
+            pending: BytesMut::new(),
+            closed: false,
         })
     }
 
+    /// Waits up to `timeout` for the next packet, `None` if none arrived in time.
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<Option<Bytes>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(packet) = self.next_packet()? {
+                return Ok(Some(packet));
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            self.socket.set_nonblocking(false)?;
+            self.socket.set_read_timeout(Some(deadline - now))?;
+            let read = self.dec.read_from(&mut self.socket);
+            self.socket.set_nonblocking(true)?;
+            match read {
+                Ok(0) => {
+                    self.closed = true;
+                    return Err(closed());
+                }
+                Ok(_) => {}
+                Err(e) if is_timeout(&e) => return Ok(None),
+                Err(e) => return Err(Error::from(e)),
+            }
+        }
+    }
+
+    /// Moves the connection onto a `mio` socket, for registration with a `Poller`.
+    pub fn into_mio(self) -> Session<mio::net::TcpStream> {
+        self.map_socket(mio::net::TcpStream::from_std)
+    }
+}
+
+impl<S> Session<S> {
+    pub fn proto(&self) -> &Proto {
+        &self.proto
+    }
+
+    pub fn socket(&self) -> &S {
+        &self.socket
+    }
+
+    pub fn socket_mut(&mut self) -> &mut S {
+        &mut self.socket
+    }
+
+    /// Whether the remote side closed the connection or reading from it failed.
+    ///
+    /// Packets received before that can still be taken out with `recv`.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Whether sent bytes are still waiting for the socket to become writable.
+    pub fn has_pending_writes(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    fn map_socket<T, F: FnOnce(S) -> T>(self, f: F) -> Session<T> {
+        Session {
+            socket: f(self.socket),
+            proto: self.proto,
+            enc: self.enc,
+            dec: self.dec,
+            outgoing: self.outgoing,
+            incoming: self.incoming,
+            pending: self.pending,
+            closed: self.closed,
+        }
+    }
+
+    /// Maps the opcode of a received packet to the crate's numbering, dropping packets unknown to the profile.
+    fn translate(&self, packet: Bytes) -> Option<Bytes> {
+        let wire = packet.slice(..2);
+        match self.incoming.decode(packet) {
+            Some(packet) => {
+                let op = u16::from_le_bytes([packet[0], packet[1]]);
+                trace!("Recv {} ({} bytes)", self.incoming.name(op).unwrap_or("?"), packet.len());
+                Some(packet)
+            }
+            None => {
+                warn!("Dropping message with opcode {:02X?} unknown to version {}", wire.as_ref(), self.proto.version);
+                None
+            }
+        }
+    }
+
+    /// Decodes the next buffered packet known to the profile, without reading from the socket.
+    fn next_packet(&mut self) -> Result<Option<Bytes>> {
+        while let Some(packet) = self.dec.decode()? {
+            if let Some(packet) = self.translate(packet) {
+                return Ok(Some(packet));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<S: Read + Write> Session<S> {
     pub fn send_packet<P: OutPacket>(&mut self, b: P) -> Result<()> {
-        self.send(b.serialize().as_slice())
+        self.send_serialized(b.serialize())
     }
 
+    /// Sends an already serialized packet.
     pub fn send<B: AsRef<[u8]>>(&mut self, b: B) -> Result<()> {
-        let encoded_data = self.enc.encode(b.as_ref());
-        self.socket
-            .write_all(&encoded_data)
-            .map_err(|e| Error::from(e))
+        self.send_serialized(b.as_ref().to_vec())
+    }
+
+    fn send_serialized(&mut self, mut data: Vec<u8>) -> Result<()> {
+        if let Some(op) = data.get(..2).map(|op| u16::from_le_bytes([op[0], op[1]])) {
+            trace!("Send {} ({} bytes)", self.outgoing.name(op).unwrap_or("?"), data.len());
+        }
+        self.outgoing.encode(&mut data)?;
+        let encoded_data = self.enc.encode(&data);
+        self.pending.extend_from_slice(&encoded_data);
+        self.flush()
+    }
+
+    /// Writes as much of the queued bytes as the socket takes without blocking.
+    pub fn flush(&mut self) -> Result<()> {
+        while !self.pending.is_empty() {
+            match self.socket.write(&self.pending) {
+                Ok(0) => return Err(closed()),
+                Ok(len) => self.pending.advance(len),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(Error::from(e)),
+            }
+        }
+        Ok(())
     }
 
+    /// Returns the next received packet, `None` if no complete packet is available yet.
     pub fn recv(&mut self) -> Result<Option<Bytes>> {
-        match self.socket.read(&mut self.buf[self.pos..]) {
-            Ok(bytes_read) => {
-                if bytes_read > 0 {
-                    //debug!("Accept {} bytes", bytes_read);
-                    self.pos += bytes_read;
-                }
-                self.process()
+        if let Some(packet) = self.next_packet()? {
+            return Ok(Some(packet));
+        }
+        let filled = self.fill();
+        match self.next_packet()? {
+            Some(packet) => Ok(Some(packet)),
+            None => filled.map(|_| None),
+        }
+    }
+
+    /// Like `recv`, but returns every complete packet received so far instead of only the first one.
+    ///
+    /// Packets decoded before an error are returned first: the bytes that failed stay buffered, so the
+    /// next call raises the error.
+    pub fn recv_all(&mut self) -> Result<Vec<Bytes>> {
+        let filled = self.fill();
+        let mut packets = Vec::new();
+        for packet in self.dec.packets() {
+            match packet {
+                Ok(packet) => packets.push(packet),
+                Err(e) if packets.is_empty() => return Err(e),
+                Err(_) => break,
             }
-            Err(e) => {
-                match e.kind() {
-                    std::io::ErrorKind::WouldBlock => self.process(),
-                    _ => Err(Error::from(e))
+        }
+        let packets = packets.into_iter().filter_map(|p| self.translate(p)).collect::<Vec<_>>();
+        match filled {
+            Err(e) if packets.is_empty() => Err(e),
+            _ => Ok(packets),
+        }
+    }
+
+    /// Reads everything the socket has, until it would block.
+    fn fill(&mut self) -> Result<()> {
+        if self.closed {
+            return Err(closed());
+        }
+        loop {
+            match self.dec.read_from(&mut self.socket) {
+                Ok(0) => {
+                    self.closed = true;
+                    return Err(closed());
+                }
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.closed = true;
+                    return Err(Error::from(e));
                 }
             }
         }
     }
+}
+
+fn connect_timeout<A: ToSocketAddrs>(addr: A, timeout: Duration) -> std::io::Result<TcpStream> {
+    let mut last_err = None;
+    for addr in addr.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(socket) => return Ok(socket),
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "Could not resolve to any address")
+    }))
+}
+
+fn is_timeout(e: &std::io::Error) -> bool {
+    e.kind() == std::io::ErrorKind::WouldBlock || e.kind() == std::io::ErrorKind::TimedOut
+}
+
+fn closed() -> Error {
+    Error::Io(std::io::ErrorKind::UnexpectedEof.into())
+}
+
+fn handshake_timed_out() -> Error {
+    Error::Handshake("Timed out waiting for handshake".to_owned())
+}
+
+#[cfg(test)]
+pub(crate) mod test_session {
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::time::Duration;
+
+    use bytes::Bytes;
+
+    use crate::io::{Error, InPacket, OutPacket};
+    use crate::net::codec::{Decoder, Encoder};
+    use crate::net::handshake::ConnectOptions;
+    use crate::net::profile::VersionProfile;
+
+    use super::{Proto, Session};
+
+    /// The hello of a v83 server, shared by the tests of everything built on sessions.
+    pub(crate) fn proto() -> Proto {
+        Proto {
+            version: 83,
+            patch: "1".to_owned(),
+            send_iv: [0x46, 0x72, 0x7A, 0x21],
+            recv_iv: [0x52, 0x30, 0x78, 0x61],
+            locale: 8,
+        }
+    }
+
+    fn v83() -> ConnectOptions {
+        ConnectOptions {
+            timeout: Duration::from_secs(5),
+            ..Default::default()
+        }
+    }
 
-    fn process(&mut self) -> Result<Option<Bytes>> {
-        if self.pos > 0 {
-            self.dec.append(&self.buf[..self.pos]);
-            self.pos = 0;
+    fn recv_blocking(sess: &mut Session) -> bytes::Bytes {
+        loop {
+            if let Some(packet) = sess.recv().unwrap() {
+                return packet;
+            }
         }
-        self.dec.decode()
+    }
+
+    fn serve<F: FnOnce(std::net::TcpStream) + Send + 'static>(f: F) -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || f(listener.accept().unwrap().0));
+        addr
+    }
+
+    #[test]
+    fn test_hello_bytes() {
+        // As serialized before the derives: length, version, patch, send IV, receive IV and locale.
+        let expected = [
+            0x0E, 0x00, 0x53, 0x00, 0x01, 0x00, b'1', 0x46, 0x72, 0x7A, 0x21, 0x52, 0x30, 0x78, 0x61, 0x08,
+        ];
+        assert_eq!(proto().serialize(), expected);
+
+        let parsed = Proto::deserialize(&mut std::io::Cursor::new(&expected[2..])).unwrap();
+        assert_eq!(parsed.serialize(), expected);
+    }
+
+    #[test]
+    fn test_split_handshake_with_trailing_packet() {
+        let addr = serve(|mut socket| {
+            let hello = proto().remote().serialize();
+            let mut enc = Encoder::server(83, proto().send_iv);
+
+            socket.write_all(&hello[..5]).unwrap();
+            std::thread::sleep(Duration::from_millis(20));
+
+            let mut rest = hello[5..].to_vec();
+            rest.extend(enc.encode(&[0x11, 0x00]));
+            socket.write_all(&rest).unwrap();
+            std::thread::sleep(Duration::from_millis(200));
+        });
+
+        let mut sess = Session::connect_server(addr, &v83()).unwrap();
+        assert_eq!(recv_blocking(&mut sess).as_ref(), [0x11, 0x00]);
+    }
+
+    #[test]
+    fn test_profile_translation() {
+        let profile = VersionProfile::from_toml(
+            r#"
+            version = 83
+            patch = "1"
+            locale = 8
+
+            [client]
+            PONG = 0x30
+
+            [server]
+            PING = 0x40
+            "#,
+        )
+        .unwrap();
+
+        let addr = serve(|mut socket| {
+            let mut enc = Encoder::server(83, proto().send_iv);
+            let mut dec = Decoder::server(83, proto().recv_iv);
+            let mut wire = proto().remote().serialize();
+            wire.extend(enc.encode(&[0x11, 0x00]));
+            wire.extend(enc.encode(&[0x40, 0x00]));
+            socket.write_all(&wire).unwrap();
+
+            loop {
+                dec.read_from(&mut socket).unwrap();
+                if let Some(pong) = dec.decode().unwrap() {
+                    assert_eq!(pong.as_ref(), [0x30, 0x00]);
+                    socket.write_all(&enc.encode(&[0x40, 0x00])).unwrap();
+                    break;
+                }
+            }
+            std::thread::sleep(Duration::from_millis(200));
+        });
+
+        let options = ConnectOptions {
+            profile,
+            ..v83()
+        };
+        let mut sess = Session::connect_server(addr, &options).unwrap();
+
+        assert_eq!(recv_blocking(&mut sess).as_ref(), [0x11, 0x00]);
+        assert!(sess.send([0x14, 0x00]).is_err());
+        sess.send([0x18, 0x00]).unwrap();
+        assert_eq!(recv_blocking(&mut sess).as_ref(), [0x11, 0x00]);
+    }
+
+    #[test]
+    fn test_recv_all_before_bad_header() {
+        let addr = serve(|mut socket| {
+            let mut wire = proto().remote().serialize();
+            wire.extend(Encoder::server(83, proto().send_iv).encode(&[0x11, 0x00]));
+            wire.extend([0xDE, 0xAD, 0xBE, 0xEF, 0x00, 0x00]);
+            socket.write_all(&wire).unwrap();
+            std::thread::sleep(Duration::from_millis(300));
+        });
+        let mut sess = Session::connect_server(addr, &v83()).unwrap();
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        let mut packets = Vec::new();
+        while packets.is_empty() {
+            assert!(std::time::Instant::now() < deadline);
+            packets = sess.recv_all().unwrap();
+        }
+        assert_eq!(packets, [Bytes::from_static(&[0x11, 0x00])]);
+        assert!(matches!(sess.recv_all(), Err(Error::HeaderMismatch)));
+    }
+
+    #[test]
+    fn test_recv_timeout() {
+        let addr = serve(|mut socket| {
+            socket.write_all(&proto().remote().serialize()).unwrap();
+            std::thread::sleep(Duration::from_millis(300));
+            socket.write_all(&Encoder::server(83, proto().send_iv).encode(&[0x11, 0x00])).unwrap();
+            std::thread::sleep(Duration::from_millis(200));
+        });
+        let mut sess = Session::connect_server(addr, &v83()).unwrap();
+
+        let start = std::time::Instant::now();
+        assert!(sess.recv_timeout(Duration::from_millis(100)).unwrap().is_none());
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        let packet = sess.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+        assert_eq!(packet.as_ref(), [0x11, 0x00]);
+        assert!(matches!(sess.recv_timeout(Duration::from_secs(5)), Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn test_unexpected_version() {
+        let addr = serve(|mut socket| {
+            socket.write_all(&proto().remote().serialize()).unwrap();
+        });
+
+        let mut options = v83();
+        options.profile.version = 95;
+        let err = Session::connect_server(addr, &options).unwrap_err();
+        assert!(matches!(err, Error::Handshake(_)));
+        assert!(err.is_fatal());
+    }
+
+    #[test]
+    fn test_handshake_timeout() {
+        let addr = serve(|_socket| std::thread::sleep(Duration::from_millis(500)));
+
+        let options = ConnectOptions {
+            timeout: Duration::from_millis(50),
+            ..v83()
+        };
+        assert!(matches!(Session::connect_server(addr, &options), Err(Error::Handshake(_))));
+    }
+
+    #[test]
+    fn test_accept_client() {
+        let addr = serve(|socket| {
+            let mut sess = Session::accept_client(socket, proto(), &VersionProfile::v83()).unwrap();
+            sess.send([0x11, 0x00]).unwrap();
+            assert_eq!(recv_blocking(&mut sess).as_ref(), [0x18, 0x00]);
+            sess.send([0x11, 0x00]).unwrap();
+        });
+
+        // Both ends only understand each other if the client got the IVs swapped.
+        let mut sess = Session::connect_server(addr, &v83()).unwrap();
+        assert_eq!(recv_blocking(&mut sess).as_ref(), [0x11, 0x00]);
+        sess.send([0x18, 0x00]).unwrap();
+        assert_eq!(recv_blocking(&mut sess).as_ref(), [0x11, 0x00]);
     }
 }
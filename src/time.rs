@@ -1,8 +1,9 @@
-use crate::io::{InPacket, BufRead, Result, Error, OutPacket};
-use bytes::{Buf, BufMut};
+//! The `SYSTEMTIME` and `FILETIME` layouts the client uses for dates.
+
+use crate::io::{InPacket, OutPacket};
 use chrono::prelude::*;
 
-#[derive(Debug)]
+#[derive(Debug, InPacket, OutPacket)]
 pub struct SystemTime {
     year: u16,
     month: u16,
@@ -41,42 +42,30 @@ impl std::fmt::Display for SystemTime {
     }
 }
 
-impl InPacket for SystemTime {
-    fn deserialize<B: BufRead>(buf: &mut B) -> Result<Self> {
-        let year = buf.get_u16_le();
-        let month = buf.get_u16_le();
-        let dayofweek = buf.get_u16_le();
-        let day = buf.get_u16_le();
-        let hour = buf.get_u16_le();
-        let minute = buf.get_u16_le();
-        let second = buf.get_u16_le();
-        let milliseconds = buf.get_u16_le();
+/// A `FILETIME`: 100 ns intervals since 1601-01-01, used for expirations and most timestamps in game data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, InPacket, OutPacket)]
+pub struct FileTime(pub i64);
+
+/// `FileTime` of the Unix epoch.
+const UNIX_EPOCH: i64 = 116_444_736_000_000_000;
 
-        Ok(Self {
-            year,
-            month,
-            dayofweek,
-            day,
-            hour,
-            minute,
-            second,
-            milliseconds,
-        })
+impl FileTime {
+    /// Expiration of items that never expire.
+    pub const NEVER: FileTime = FileTime(150_842_304_000_000_000);
+    /// Placeholder date servers send where the client expects a time but none applies.
+    pub const ZERO: FileTime = FileTime(94_354_848_000_000_000);
+    /// Expiration of permanent pets.
+    pub const PERMANENT: FileTime = FileTime(150_841_440_000_000_000);
+
+    pub fn from_unix_millis(millis: i64) -> Self {
+        FileTime(millis * 10_000 + UNIX_EPOCH)
     }
-}
 
-impl OutPacket for SystemTime {
-    fn serialize(&self) -> Vec<u8> {
-        let mut p = Vec::new();
-        p.put_u16_le(self.year);
-        p.put_u16_le(self.month);
-        p.put_u16_le(self.dayofweek);
-        p.put_u16_le(self.day);
-        p.put_u16_le(self.hour);
-        p.put_u16_le(self.minute);
-        p.put_u16_le(self.second);
-        p.put_u16_le(self.milliseconds);
+    pub fn unix_millis(self) -> i64 {
+        (self.0 - UNIX_EPOCH) / 10_000
+    }
 
-        p
+    pub fn now() -> Self {
+        Self::from_unix_millis(Local::now().timestamp_millis())
     }
-}
\ No newline at end of file
+}